@@ -0,0 +1,130 @@
+use crate::comm_channel::{CommunicationChannel, FE};
+use crate::field::VoleField;
+use crate::error::Result;
+use crate::counting_channel::CommStats;
+
+use p256::EncodedPoint;
+use lambdaworks_math::field::element::FieldElement;
+
+use std::future::Future;
+use std::pin::pin;
+use std::task::{Context, Poll, RawWaker, RawWakerVTable, Waker};
+
+/// Async counterpart of `CommunicationChannel`, same messages and same wire format.
+///
+/// The protocols are written once against this trait, their blocking methods run
+/// the same code over a `SyncChannel`.
+///
+/// The async drivers only yield while waiting on the channel. LPN encoding, GGM expansion
+/// and the MPFSS worker threads run inside the future, so an extend holds its executor
+/// thread until it is done. On tokio, run sessions on the multi-threaded runtime and wrap
+/// the calls in `tokio::task::block_in_place`, or move whole sessions to `spawn_blocking`,
+/// so that they do not stall the other tasks.
+///
+/// The methods are plain `async fn`s, so their futures are not required to be `Send`:
+/// code that spawns them on a work-stealing executor has to be generic over a concrete
+/// channel type whose futures are.
+pub trait AsyncCommunicationChannel {
+    async fn send_32byte_block(&mut self, data: &[[u8; 32]]) -> Result<()>;
+    async fn receive_32byte_block(&mut self) -> Result<Vec<[u8; 32]>>;
+    async fn send_bits(&mut self, bits: &[bool]) -> Result<()>;
+    async fn receive_bits(&mut self) -> Result<Vec<bool>>;
+    async fn send_stark252(&mut self, elements: &[FE]) -> Result<()>;
+    async fn receive_stark252(&mut self, count: usize) -> Result<Vec<FE>>;
+    async fn send_field_elements<F: VoleField>(&mut self, elements: &[FieldElement<F>]) -> Result<()>;
+    async fn receive_field_elements<F: VoleField>(&mut self, count: usize) -> Result<Vec<FieldElement<F>>>;
+    async fn send_point(&mut self, point: &EncodedPoint) -> Result<()>;
+    async fn receive_point(&mut self) -> Result<EncodedPoint>;
+    async fn send_data(&mut self, data: &[[u8; 16]]) -> Result<()>;
+    async fn receive_data(&mut self) -> Result<Vec<[u8; 16]>>;
+    async fn flush(&mut self) -> Result<()>;
+
+    /// Traffic counters, only counting channels keep them.
+    fn stats(&self) -> Option<CommStats> {
+        None
+    }
+}
+
+/// Presents a blocking channel as an async one whose futures are always ready.
+pub struct SyncChannel<'a, C: CommunicationChannel>(pub &'a mut C);
+
+impl<'a, C: CommunicationChannel> AsyncCommunicationChannel for SyncChannel<'a, C> {
+    async fn send_32byte_block(&mut self, data: &[[u8; 32]]) -> Result<()> {
+        self.0.send_32byte_block(data)
+    }
+
+    async fn receive_32byte_block(&mut self) -> Result<Vec<[u8; 32]>> {
+        self.0.receive_32byte_block()
+    }
+
+    async fn send_bits(&mut self, bits: &[bool]) -> Result<()> {
+        self.0.send_bits(bits)
+    }
+
+    async fn receive_bits(&mut self) -> Result<Vec<bool>> {
+        self.0.receive_bits()
+    }
+
+    async fn send_stark252(&mut self, elements: &[FE]) -> Result<()> {
+        self.0.send_stark252(elements)
+    }
+
+    async fn receive_stark252(&mut self, count: usize) -> Result<Vec<FE>> {
+        self.0.receive_stark252(count)
+    }
+
+    async fn send_field_elements<F: VoleField>(&mut self, elements: &[FieldElement<F>]) -> Result<()> {
+        self.0.send_field_elements(elements)
+    }
+
+    async fn receive_field_elements<F: VoleField>(&mut self, count: usize) -> Result<Vec<FieldElement<F>>> {
+        self.0.receive_field_elements(count)
+    }
+
+    async fn send_point(&mut self, point: &EncodedPoint) -> Result<()> {
+        self.0.send_point(point)
+    }
+
+    async fn receive_point(&mut self) -> Result<EncodedPoint> {
+        self.0.receive_point()
+    }
+
+    async fn send_data(&mut self, data: &[[u8; 16]]) -> Result<()> {
+        self.0.send_data(data)
+    }
+
+    async fn receive_data(&mut self) -> Result<Vec<[u8; 16]>> {
+        self.0.receive_data()
+    }
+
+    async fn flush(&mut self) -> Result<()> {
+        self.0.flush()
+    }
+
+    fn stats(&self) -> Option<CommStats> {
+        self.0.stats()
+    }
+}
+
+/// Runs a protocol future over a `SyncChannel` to completion.
+///
+/// Every await point of such a future is ready at once, so a single poll is enough.
+pub(crate) fn block_on<T>(future: impl Future<Output = T>) -> T {
+    let mut future = pin!(future);
+    let waker = noop_waker();
+    let mut cx = Context::from_waker(&waker);
+    match future.as_mut().poll(&mut cx) {
+        Poll::Ready(value) => value,
+        Poll::Pending => panic!("block_on only drives futures over a SyncChannel"),
+    }
+}
+
+// `Waker::noop` needs Rust 1.85, above the crate's rust-version.
+fn noop_waker() -> Waker {
+    fn clone(_: *const ()) -> RawWaker {
+        RawWaker::new(std::ptr::null(), &NOOP_VTABLE)
+    }
+    fn noop(_: *const ()) {}
+    static NOOP_VTABLE: RawWakerVTable = RawWakerVTable::new(clone, noop, noop, noop);
+    unsafe { Waker::from_raw(RawWaker::new(std::ptr::null(), &NOOP_VTABLE)) }
+}
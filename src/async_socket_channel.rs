@@ -0,0 +1,111 @@
+use crate::async_comm_channel::AsyncCommunicationChannel;
+
+use tokio::net::TcpStream;
+use tokio::io::{AsyncReadExt, AsyncWriteExt};
+use p256::EncodedPoint;
+
+use lambdaworks_math::field::fields::fft_friendly::stark_252_prime_field::Stark252PrimeField;
+use lambdaworks_math::field::element::FieldElement;
+use crate::field::VoleField;
+use crate::framing::{self, Framer, MessageKind, HEADER_LEN};
+use crate::error::Result;
+
+pub type FE = FieldElement<Stark252PrimeField>;
+
+/// Tokio version of `TcpChannel`, the two speak the same wire format.
+///
+/// The protocols compute between messages without yielding, see `AsyncCommunicationChannel`.
+pub struct AsyncTcpChannel {
+    stream: TcpStream,
+    framer: Framer,
+}
+
+impl AsyncTcpChannel {
+    /// Creates a new AsyncTcpChannel
+    pub fn new(stream: TcpStream) -> Self {
+        Self { stream, framer: Framer::default() }
+    }
+
+    /// Tags every frame with `session`, the peer must use the same one
+    pub fn with_session(mut self, session: u32) -> Self {
+        self.framer = Framer::new(session);
+        self
+    }
+
+    /// Writes the header and the payload in one go
+    async fn send_frame(&mut self, kind: MessageKind, payload: &[u8]) -> Result<()> {
+        let frame = self.framer.frame(kind, payload);
+        self.stream.write_all(&frame).await?;
+        Ok(())
+    }
+
+    async fn receive_frame(&mut self, kind: MessageKind) -> Result<Vec<u8>> {
+        let mut header = [0u8; HEADER_LEN];
+        self.stream.read_exact(&mut header).await?;
+        let len = self.framer.check(&header, kind)?;
+
+        let mut payload = vec![0u8; len];
+        self.stream.read_exact(&mut payload).await?;
+        Ok(payload)
+    }
+}
+
+impl AsyncCommunicationChannel for AsyncTcpChannel {
+    async fn send_bits(&mut self, bits: &[bool]) -> Result<()> {
+        self.send_frame(MessageKind::Bits, &framing::encode_bits(bits)).await
+    }
+
+    async fn receive_bits(&mut self) -> Result<Vec<bool>> {
+        let payload = self.receive_frame(MessageKind::Bits).await?;
+        framing::decode_bits(&payload)
+    }
+
+    async fn send_stark252(&mut self, elements: &[FE]) -> Result<()> {
+        self.send_field_elements(elements).await
+    }
+
+    async fn receive_stark252(&mut self, count: usize) -> Result<Vec<FE>> {
+        self.receive_field_elements(count).await
+    }
+
+    async fn send_field_elements<F: VoleField>(&mut self, elements: &[FieldElement<F>]) -> Result<()> {
+        self.send_frame(MessageKind::FieldElements, &framing::encode_field_elements(elements)).await
+    }
+
+    async fn receive_field_elements<F: VoleField>(&mut self, count: usize) -> Result<Vec<FieldElement<F>>> {
+        let payload = self.receive_frame(MessageKind::FieldElements).await?;
+        framing::decode_field_elements(&payload, count)
+    }
+
+    async fn send_point(&mut self, point: &EncodedPoint) -> Result<()> {
+        self.send_frame(MessageKind::Point, point.as_bytes()).await
+    }
+
+    async fn receive_point(&mut self) -> Result<EncodedPoint> {
+        let payload = self.receive_frame(MessageKind::Point).await?;
+        framing::decode_point(&payload)
+    }
+
+    async fn send_data(&mut self, data: &[[u8; 16]]) -> Result<()> {
+        self.send_frame(MessageKind::Data, &framing::encode_blocks(data)).await
+    }
+
+    async fn receive_data(&mut self) -> Result<Vec<[u8; 16]>> {
+        let payload = self.receive_frame(MessageKind::Data).await?;
+        framing::decode_blocks(&payload)
+    }
+
+    async fn send_32byte_block(&mut self, data: &[[u8; 32]]) -> Result<()> {
+        self.send_frame(MessageKind::Block32, &framing::encode_blocks(data)).await
+    }
+
+    async fn receive_32byte_block(&mut self) -> Result<Vec<[u8; 32]>> {
+        let payload = self.receive_frame(MessageKind::Block32).await?;
+        framing::decode_blocks(&payload)
+    }
+
+    async fn flush(&mut self) -> Result<()> {
+        self.stream.flush().await?;
+        Ok(())
+    }
+}
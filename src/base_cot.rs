@@ -1,68 +1,96 @@
-use crate::iknp::IKNP;
+use crate::iknp::{IKNP, send_blocks, receive_blocks};
+use crate::ot_extension::{CotSender, CotReceiver, OtExtension};
 use crate::prg::PRG;
 use crate::comm_channel::CommunicationChannel;
+use crate::async_comm_channel::{AsyncCommunicationChannel, SyncChannel, block_on};
 use crate::preot::OTPre;
-
-pub struct BaseCot {
+use crate::error::{Result, VoleError};
+
+/// Base COTs from an OT extension, IKNP unless given another one, with `N`-byte blocks
+/// whose LSB is the receiver's choice bit.
+///
+/// The width is picked with the type: `BaseCot::<16>::new` for the standard 128-bit COTs,
+/// e.g. for boolean circuits, and `BaseCot::new` for the 256-bit ones of the Stark252
+/// SPFSS, both can fill an `OTPre` of their width. `with_extension` runs on e.g. a
+/// `SoftSpokenOT` instead, which sends less for more computation.
+pub struct BaseCot<const N: usize = 32, E: OtExtension<N> = IKNP<N>> {
     party: usize, // Alice: 0, Bob: 1
-    one: [u8; 32],
-    minus_one: [u8; 32],
-    ot_delta: Option<[u8; 32]>,
-    iknp: IKNP,
+    one: [u8; N],
+    minus_one: [u8; N],
+    ot_delta: Option<[u8; N]>,
+    extension: E,
     malicious: bool,
 }
 
-impl BaseCot {
+impl<const N: usize> BaseCot<N> {
     pub fn new(party: usize, malicious: bool) -> Self {
-        let one = [
-            1, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0,
-            0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0,
-        ]; // Little-endian representation of 1
-        let minus_one = [
-            254, 255, 255, 255, 255, 255, 255, 255, 255, 255, 255, 255, 255, 255, 255, 255,
-            255, 255, 255, 255, 255, 255, 255, 255, 255, 255, 255, 255, 255, 255, 255, 255,
-        ]; // Little-endian representation of u128::MAX - 1
+        Self::with_extension(party, IKNP::new(malicious))
+    }
+}
+
+impl<const N: usize, E: OtExtension<N>> BaseCot<N, E> {
+    /// Runs on `extension`, which must not be set up yet
+    pub fn with_extension(party: usize, extension: E) -> Self {
+        let mut one = [0u8; N]; // Little-endian representation of 1
+        one[0] = 1;
+        let mut minus_one = [255u8; N]; // Every bit but the LSB
+        minus_one[0] = 254;
 
         BaseCot {
             party,
             one,
             minus_one,
             ot_delta: None,
-            iknp: IKNP::new(malicious),
-            malicious,
+            malicious: extension.malicious(),
+            extension,
         }
     }
 
-    pub fn cot_gen_pre<IO: CommunicationChannel>(&mut self, io: &mut IO, deltain: Option<[u8; 32]>) {
+    pub fn cot_gen_pre<IO: CommunicationChannel>(&mut self, io: &mut IO, deltain: Option<[u8; N]>) -> Result<()> {
+        block_on(self.cot_gen_pre_async(&mut SyncChannel(io), deltain))
+    }
+
+    /// Async variant of `cot_gen_pre`.
+    pub async fn cot_gen_pre_async<IO: AsyncCommunicationChannel>(&mut self, io: &mut IO, deltain: Option<[u8; N]>) -> Result<()> {
         if let Some(deltain) = deltain {
             if self.party == 0 {
                 self.ot_delta = Some(deltain);
-                let delta_bool = block_to_bool(&deltain);
-                self.iknp.setup_send(io, Some(&delta_bool), None);
+                self.extension.setup_sender(io, &deltain).await?;
             } else {
-                self.iknp.setup_recv(io, None, None);
+                self.extension.setup_receiver(io).await?;
             }
         } else {
             if self.party == 0 {
                 let mut prg = PRG::new(None, 0);
-                let mut tmp = [[0u8; 32]];
-                prg.random_32byte_block(&mut tmp);
+                let mut tmp = [[0u8; N]];
+                prg.random_blocks(&mut tmp);
                 let mut delta = tmp[0];
                 delta = bitwise_and(&delta, &self.minus_one);
                 delta = bitwise_xor(&delta, &self.one);
                 self.ot_delta = Some(delta);
-                let delta_bool = block_to_bool(&delta);
-                self.iknp.setup_send(io, Some(&delta_bool), None);
+                self.extension.setup_sender(io, &delta).await?;
             } else {
-                self.iknp.setup_recv(io, None, None);
+                self.extension.setup_receiver(io).await?;
             }
         }
+        Ok(())
     }
 
-    pub fn cot_gen<IO: CommunicationChannel>(&mut self, io: &mut IO, ot_data: &mut [[u8; 32]], size: usize, pre_bool: Option<&[bool]>) {
+    /// The sender's COT correlation, available after `cot_gen_pre`.
+    pub fn delta(&self) -> Option<[u8; N]> {
+        self.ot_delta
+    }
+
+    pub fn cot_gen<IO: CommunicationChannel>(&mut self, io: &mut IO, ot_data: &mut [[u8; N]], size: usize, pre_bool: Option<&[bool]>) -> Result<()> {
+        block_on(self.cot_gen_async(&mut SyncChannel(io), ot_data, size, pre_bool))
+    }
+
+    /// Async variant of `cot_gen`.
+    pub async fn cot_gen_async<IO: AsyncCommunicationChannel>(&mut self, io: &mut IO, ot_data: &mut [[u8; N]], size: usize, pre_bool: Option<&[bool]>) -> Result<()> {
         if self.party == 0 {
-            self.iknp.send_cot(io, ot_data, size);
-            io.flush();
+            self.extension.extend_send(io, ot_data, size).await?;
+            self.extension.check_send(io, ot_data, size).await?;
+            io.flush().await?;
             for block in ot_data.iter_mut() {
                 *block = bitwise_and(block, &self.minus_one);
             }
@@ -79,27 +107,63 @@ impl BaseCot {
                 prg.random_bool_array(&mut pre_bool_ini);
             }
 
-            self.iknp.recv_cot(io, ot_data, &pre_bool_ini, size);
+            self.extension.extend_recv(io, ot_data, &pre_bool_ini, size).await?;
+            self.extension.check_recv(io, ot_data, &pre_bool_ini, size).await?;
 
-            let ch = [
-                [0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0,
-                0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0],
-                [1, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0,
-                0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0],
-            ]; 
+            let ch = [[0u8; N], self.one];
 
             for (i, block) in ot_data.iter_mut().enumerate() {
                 *block = bitwise_xor(&bitwise_and(block, &self.minus_one), &ch[pre_bool_ini[i] as usize]);
             }
         }
+        Ok(())
     }
 
-    pub fn cot_gen_preot<IO: CommunicationChannel>(&mut self, io: &mut IO, pre_ot: &mut OTPre, size: usize, pre_bool: Option<&[bool]>) {
-        let mut ot_data = vec![[0u8; 32]; size]; // Allocate space for `ot_data`
+    // Debugging check for COT
+    pub fn check_cot<IO: CommunicationChannel>(&mut self, io: &mut IO, data: &[[u8; N]], len: usize) -> Result<bool> {
+        block_on(self.check_cot_async(&mut SyncChannel(io), data, len))
+    }
+
+    /// Async variant of `check_cot`.
+    pub async fn check_cot_async<IO: AsyncCommunicationChannel>(&mut self, io: &mut IO, data: &[[u8; N]], len: usize) -> Result<bool> {
+        if self.party == 0 {
+            if let Some(delta) = self.ot_delta {
+                send_blocks(io, &[delta]).await?;
+            }
+            send_blocks(io, data).await?;
+            io.flush().await?;
+            Ok(true)
+        } else {
+            let mut tmp = vec![[0u8; N]; len];
+            let mut ch = [[0u8; N]; 2];
+            ch[1] = *receive_blocks::<_, N>(io).await?.first()
+                .ok_or_else(|| VoleError::MalformedMessage("Missing COT delta".to_string()))?;
+            ch[0] = [0u8; N];
+            tmp = receive_blocks(io).await?;
+            if tmp.len() < len {
+                return Err(VoleError::MalformedMessage("Too few COTs to check".to_string()));
+            }
+            for i in 0..len {
+                tmp[i] = bitwise_xor(&tmp[i], &ch[get_lsb(&data[i]) as usize]);
+            }
+            Ok(cmp_blocks(&tmp, data))
+        }
+    }
+}
+
+impl<const N: usize, E: OtExtension<N>> BaseCot<N, E> {
+    pub fn cot_gen_preot<IO: CommunicationChannel>(&mut self, io: &mut IO, pre_ot: &mut OTPre<N>, size: usize, pre_bool: Option<&[bool]>) -> Result<()> {
+        block_on(self.cot_gen_preot_async(&mut SyncChannel(io), pre_ot, size, pre_bool))
+    }
+
+    /// Async variant of `cot_gen_preot`.
+    pub async fn cot_gen_preot_async<IO: AsyncCommunicationChannel>(&mut self, io: &mut IO, pre_ot: &mut OTPre<N>, size: usize, pre_bool: Option<&[bool]>) -> Result<()> {
+        let mut ot_data = vec![[0u8; N]; size]; // Allocate space for `ot_data`
 
         if self.party == 0 {
             // ALICE
-            self.iknp.send_cot(io, &mut ot_data, size);
+            self.extension.extend_send(io, &mut ot_data, size).await?;
+            self.extension.check_send(io, &ot_data, size).await?;
             // io.flush();
 
             // Apply `minus_one` to all blocks
@@ -125,15 +189,11 @@ impl BaseCot {
                 prg.random_bool_array(&mut pre_bool_ini);
             }
 
-            // Call `recv_cot` on `iknp`
-            self.iknp.recv_cot(io, &mut ot_data, &pre_bool_ini, size);
+            // Receive the COTs from the extension
+            self.extension.extend_recv(io, &mut ot_data, &pre_bool_ini, size).await?;
+            self.extension.check_recv(io, &ot_data, &pre_bool_ini, size).await?;
 
-            let ch = [
-                [0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0,
-                0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0],
-                [1, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0,
-                0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0],
-            ]; 
+            let ch = [[0u8; N], self.one];
 
             // Modify `ot_data` based on `pre_bool_ini`
             for (i, block) in ot_data.iter_mut().enumerate() {
@@ -143,71 +203,22 @@ impl BaseCot {
             // Call `recv_pre` on `pre_ot`
             pre_ot.recv_pre(&ot_data, Some(&pre_bool_ini));
         }
-    }
-
-    // Debugging check for COT
-    pub fn check_cot<IO: CommunicationChannel>(&mut self, io: &mut IO, data: &[[u8; 32]], len: usize) -> bool {
-        if self.party == 0 {
-            if let Some(delta) = self.ot_delta {
-                io.send_32byte_block(&[delta]);
-            }
-            io.send_32byte_block(data);
-            io.flush();
-            true
-        } else {
-            let mut tmp = vec![[0u8; 32]; len];
-            let mut ch = [[0u8; 32]; 2];
-            ch[1] = io.receive_32byte_block()[0];
-            ch[0] = [0u8; 32];
-            tmp = io.receive_32byte_block();
-            for i in 0..len {
-                tmp[i] = bitwise_xor(&tmp[i], &ch[get_lsb(&data[i]) as usize]);
-            }
-            cmp_blocks(&tmp, data)
-        }
+        Ok(())
     }
 }
 
-fn block_to_bool(block: &[u8; 32]) -> [bool; 256] {
-    let mut result = [false; 256];
-    for (i, byte) in block.iter().enumerate() {
-        for bit in 0..8 {
-            result[i * 8 + bit] = (byte >> bit) & 1 != 0;
-        }
-    }
-    result
-}
-
-fn get_lsb(block: &[u8; 32]) -> bool {
+fn get_lsb<const N: usize>(block: &[u8; N]) -> bool {
     block[0] & 1 != 0
 }
 
-fn cmp_blocks(a: &[[u8; 32]], b: &[[u8; 32]]) -> bool {
+fn cmp_blocks<const N: usize>(a: &[[u8; N]], b: &[[u8; N]]) -> bool {
     a == b
 }
 
-fn bitwise_xor(a: &[u8; 32], b: &[u8; 32]) -> [u8; 32] {
-    [
-        a[0] ^ b[0], a[1] ^ b[1], a[2] ^ b[2], a[3] ^ b[3],
-        a[4] ^ b[4], a[5] ^ b[5], a[6] ^ b[6], a[7] ^ b[7],
-        a[8] ^ b[8], a[9] ^ b[9], a[10] ^ b[10], a[11] ^ b[11],
-        a[12] ^ b[12], a[13] ^ b[13], a[14] ^ b[14], a[15] ^ b[15],
-        a[16] ^ b[16], a[17] ^ b[17], a[18] ^ b[18], a[19] ^ b[19],
-        a[20] ^ b[20], a[21] ^ b[21], a[22] ^ b[22], a[23] ^ b[23],
-        a[24] ^ b[24], a[25] ^ b[25], a[26] ^ b[26], a[27] ^ b[27],
-        a[28] ^ b[28], a[29] ^ b[29], a[30] ^ b[30], a[31] ^ b[31],
-    ]
+fn bitwise_xor<const N: usize>(a: &[u8; N], b: &[u8; N]) -> [u8; N] {
+    std::array::from_fn(|i| a[i] ^ b[i])
 }
 
-fn bitwise_and(a: &[u8; 32], b: &[u8; 32]) -> [u8; 32] {
-    [
-        a[0] & b[0], a[1] & b[1], a[2] & b[2], a[3] & b[3],
-        a[4] & b[4], a[5] & b[5], a[6] & b[6], a[7] & b[7],
-        a[8] & b[8], a[9] & b[9], a[10] & b[10], a[11] & b[11],
-        a[12] & b[12], a[13] & b[13], a[14] & b[14], a[15] & b[15],
-        a[16] & b[16], a[17] & b[17], a[18] & b[18], a[19] & b[19],
-        a[20] & b[20], a[21] & b[21], a[22] & b[22], a[23] & b[23],
-        a[24] & b[24], a[25] & b[25], a[26] & b[26], a[27] & b[27],
-        a[28] & b[28], a[29] & b[29], a[30] & b[30], a[31] & b[31],
-    ]
-}
\ No newline at end of file
+fn bitwise_and<const N: usize>(a: &[u8; N], b: &[u8; N]) -> [u8; N] {
+    std::array::from_fn(|i| a[i] & b[i])
+}
@@ -1,114 +1,137 @@
 use crate::cope::Cope;
 use crate::prg::PRG;
 use crate::comm_channel::CommunicationChannel;
-use lambdaworks_math::field::fields::fft_friendly::stark_252_prime_field::Stark252PrimeField;
+use crate::async_comm_channel::{AsyncCommunicationChannel, SyncChannel, block_on};
+use crate::error::{Result, VoleError, CheckFailure};
+use crate::field::{VoleField, VoleSubfield, DefaultField};
 use lambdaworks_math::field::element::FieldElement;
-use lambdaworks_math::field::traits::IsPrimeField;
 
-pub type F = Stark252PrimeField;
-pub type FE = FieldElement<F>;
-
-pub struct BaseSvole {
+/// Base sVOLE with u in `F` and Δ, shares in `K`, `K = F` being the plain case.
+pub struct BaseSvole<F: VoleSubfield<K> = DefaultField, K: VoleField = F> {
     party: u8,              // 0 for sender, 1 for receiver
-    cope: Cope,     // COPE instance
-    delta: Option<FE>,      // Delta for the sender
+    cope: Cope<F, K>,  // COPE instance
+    delta: Option<FieldElement<K>>,      // Delta for the sender
 }
 
-impl BaseSvole {
+impl<F: VoleSubfield<K>, K: VoleField> BaseSvole<F, K> {
     /// Sender's constructor
-    pub fn new_sender<IO: CommunicationChannel>(io: &mut IO, delta: FE) -> Self {
-        let mut cope = Cope::new(0, F::field_bit_size());
-        cope.initialize_sender(io, delta.clone());
-        Self {
+    pub fn new_sender<IO: CommunicationChannel>(io: &mut IO, delta: FieldElement<K>) -> Result<Self> {
+        block_on(Self::new_sender_async(&mut SyncChannel(io), delta))
+    }
+
+    /// Async variant of `new_sender`.
+    pub async fn new_sender_async<IO: AsyncCommunicationChannel>(io: &mut IO, delta: FieldElement<K>) -> Result<Self> {
+        let mut cope = Cope::new(0, F::DEGREE * F::field_bit_size());
+        cope.initialize_sender_async(io, delta.clone()).await?;
+        Ok(Self {
             party: 0,
             cope,
             delta: Some(delta),
-        }
+        })
     }
 
     /// Receiver's constructor
-    pub fn new_receiver<IO: CommunicationChannel>(io: &mut IO) -> Self {
-        let mut cope = Cope::new(1, F::field_bit_size());
-        cope.initialize_receiver(io);
-        Self {
+    pub fn new_receiver<IO: CommunicationChannel>(io: &mut IO) -> Result<Self> {
+        block_on(Self::new_receiver_async(&mut SyncChannel(io)))
+    }
+
+    /// Async variant of `new_receiver`.
+    pub async fn new_receiver_async<IO: AsyncCommunicationChannel>(io: &mut IO) -> Result<Self> {
+        let mut cope = Cope::new(1, F::DEGREE * F::field_bit_size());
+        cope.initialize_receiver_async(io).await?;
+        Ok(Self {
             party: 1,
             cope,
             delta: None,
-        }
+        })
     }
 
     /// Sender: Triple generation
-    pub fn triple_gen_send<IO: CommunicationChannel>(&mut self, io: &mut IO, share: &mut [FE], size: usize) {
+    pub fn triple_gen_send<IO: CommunicationChannel>(&mut self, io: &mut IO, share: &mut [FieldElement<K>], size: usize) -> Result<()> {
+        block_on(self.triple_gen_send_async(&mut SyncChannel(io), share, size))
+    }
+
+    /// Async variant of `triple_gen_send`.
+    pub async fn triple_gen_send_async<IO: AsyncCommunicationChannel>(&mut self, io: &mut IO, share: &mut [FieldElement<K>], size: usize) -> Result<()> {
         // Generate share_recv = share_send + delta * u_recv
-        self.cope.extend_sender_batch(io, share, size);
-        let mut b = vec![FE::zero(); 1];
-        self.cope.extend_sender_batch(io, &mut b, 1);
-        self.sender_check(io, share, b[0], size);
+        self.cope.extend_sender_batch_async(io, share, size).await?;
+        // The mask needs u in K, pack DEGREE subfield correlations
+        let mut b = vec![FieldElement::zero(); F::DEGREE];
+        self.cope.extend_sender_batch_async(io, &mut b, F::DEGREE).await?;
+        self.sender_check_async(io, share, F::compose(&b), size).await
     }
 
     /// Receiver: Triple generation
-    pub fn triple_gen_recv<IO: CommunicationChannel>(&mut self, io: &mut IO, share: &mut [FE], u: &mut [FE], size: usize) {
+    pub fn triple_gen_recv<IO: CommunicationChannel>(&mut self, io: &mut IO, share: &mut [FieldElement<K>], u: &mut [FieldElement<F>], size: usize) -> Result<()> {
+        block_on(self.triple_gen_recv_async(&mut SyncChannel(io), share, u, size))
+    }
+
+    /// Async variant of `triple_gen_recv`.
+    pub async fn triple_gen_recv_async<IO: AsyncCommunicationChannel>(&mut self, io: &mut IO, share: &mut [FieldElement<K>], u: &mut [FieldElement<F>], size: usize) -> Result<()> {
         // Generate share_recv = share_send + delta * u_recv
         let mut prg = PRG::new(None, 0);
-        let mut x = vec![FE::zero(); 1];
-        prg.random_stark252_elements(&mut x);
+        let mut x = vec![FieldElement::zero(); F::DEGREE];
+        prg.random_field_elements(&mut x);
 
-        prg.random_stark252_elements(u);
+        prg.random_field_elements(u);
 
-        self.cope.extend_receiver_batch(io, share, u, size);
+        self.cope.extend_receiver_batch_async(io, share, u, size).await?;
 
-        let mut c = vec![FE::zero(); 1];
-        self.cope.extend_receiver_batch(io, &mut c, &x, 1);
+        let mut c = vec![FieldElement::zero(); F::DEGREE];
+        self.cope.extend_receiver_batch_async(io, &mut c, &x, F::DEGREE).await?;
 
-        self.receiver_check(io, share, u, c[0], x[0], size);
+        let a: Vec<FieldElement<K>> = x.iter().map(|xi| F::embed(xi)).collect();
+        self.receiver_check_async(io, share, u, F::compose(&c), F::compose(&a), size).await
     }
 
     /// Sender: Consistency check
-    fn sender_check<IO: CommunicationChannel>(&mut self, io: &mut IO, share: &[FE], b: FE, size: usize) {
+    async fn sender_check_async<IO: AsyncCommunicationChannel>(&mut self, io: &mut IO, share: &[FieldElement<K>], b: FieldElement<K>, size: usize) -> Result<()> {
         // Generate check seed and send it to Receiver
         let mut seed = vec![[0u8; 16]; 1];
         let mut seed_prg = PRG::new(None, 0);
         seed_prg.random_block(&mut seed);
-        io.send_data(&seed);
+        io.send_data(&seed).await?;
+        io.flush().await?;
 
         let chi = self.generate_hash_coeff(seed[0], size);
 
         let y = self.vector_inner_product_mod(share, &chi) + b;
-        let mut xz = io.receive_stark252(2).expect("Failed to receive xz");
+        let mut xz = io.receive_field_elements::<K>(2).await?;
 
         xz[1] = xz[1] * self.delta.unwrap();
         let y_check = y + xz[1];
         if y_check != xz[0] {
-            panic!("Base sVOLE check failed!");
-        } else {
-            println!("Base sVOLE generated successfully!");
+            return Err(VoleError::ConsistencyCheck(CheckFailure::BaseSvole));
         }
+        Ok(())
     }
 
     /// Receiver: Consistency check
-    fn receiver_check<IO: CommunicationChannel>(&mut self, io: &mut IO, share: &[FE], x: &[FE], c: FE, a: FE, size: usize) {
-        let received_data = io.receive_data();
-        let seed = received_data[0];
+    async fn receiver_check_async<IO: AsyncCommunicationChannel>(&mut self, io: &mut IO, share: &[FieldElement<K>], x: &[FieldElement<F>], c: FieldElement<K>, a: FieldElement<K>, size: usize) -> Result<()> {
+        let received_data = io.receive_data().await?;
+        let seed = *received_data.first()
+            .ok_or_else(|| VoleError::MalformedMessage("Missing base sVOLE check seed".to_string()))?;
         // let seed = <[u8; 16]>::try_from(&received_data[0..16]).expect("Invalid seed size");
 
         let chi = self.generate_hash_coeff(seed, size);
 
         let xz_0 = self.vector_inner_product_mod(share, &chi) + c;
-        let xz_1 = self.vector_inner_product_mod(x, &chi) + a;
+        let xz_1 = x.iter().zip(&chi).fold(FieldElement::zero(), |acc, (xi, ci)| acc + F::scale(xi, ci)) + a;
 
-        io.send_stark252(&[xz_0, xz_1]).expect("Failed to send xz");
+        io.send_field_elements(&[xz_0, xz_1]).await?;
+        io.flush().await
     }
 
     /// Generate hash coefficients based on a seed
-    fn generate_hash_coeff(&self, seed: [u8; 16], size: usize) -> Vec<FE> {
-        let mut coeffs = vec![FE::zero(); size];
+    fn generate_hash_coeff(&self, seed: [u8; 16], size: usize) -> Vec<FieldElement<K>> {
+        let mut coeffs = vec![FieldElement::zero(); size];
         let mut prg = PRG::new(Some(&seed), 0);
-        prg.random_stark252_elements(&mut coeffs);
+        prg.random_field_elements(&mut coeffs);
         coeffs
     }
 
     /// Compute modular inner product
-    fn vector_inner_product_mod(&self, vec1: &[FE], vec2: &[FE]) -> FE {
-        vec1.iter().zip(vec2).fold(FE::zero(), |acc, (v1, v2)| acc + (*v1 * *v2))
+    fn vector_inner_product_mod(&self, vec1: &[FieldElement<K>], vec2: &[FieldElement<K>]) -> FieldElement<K> {
+        K::inner_product(vec1, vec2)
     }
 }
@@ -0,0 +1,65 @@
+// Times IKNP COT extension over an in-memory channel and checks the correlation
+// Usage: bench_iknp [number of COTs]
+
+extern crate vole_rust;
+extern crate rand;
+
+use vole_rust::iknp::IKNP;
+use vole_rust::mem_channel::MemChannel;
+use rand::Rng;
+use std::env;
+use std::thread;
+use std::time::Instant;
+
+fn run<const N: usize>(malicious: bool, length: usize) {
+    let (mut alice, mut bob) = MemChannel::pair();
+    let mut rng = rand::thread_rng();
+    let s: Vec<bool> = (0..8 * N).map(|_| rng.gen()).collect();
+    let r: Vec<bool> = (0..length).map(|_| rng.gen()).collect();
+    let mut delta = [0u8; N];
+    for (i, &bit) in s.iter().enumerate() {
+        delta[i / 8] |= (bit as u8) << (i % 8);
+    }
+
+    let sender = thread::spawn(move || {
+        let mut iknp = IKNP::<N>::new(malicious);
+        iknp.setup_send(&mut alice, Some(&s), None).expect("Failed to set up IKNP");
+        let mut data = vec![[0u8; N]; length];
+        let start = Instant::now();
+        iknp.send_cot(&mut alice, &mut data, length).expect("Failed to send COTs");
+        (data, start.elapsed())
+    });
+
+    let mut iknp = IKNP::<N>::new(malicious);
+    iknp.setup_recv(&mut bob, None, None).expect("Failed to set up IKNP");
+    let mut data = vec![[0u8; N]; length];
+    let start = Instant::now();
+    iknp.recv_cot(&mut bob, &mut data, &r, length).expect("Failed to receive COTs");
+    let elapsed = start.elapsed();
+    let (sent, send_elapsed) = sender.join().unwrap();
+
+    for i in 0..length {
+        let mut expected = sent[i];
+        if r[i] {
+            for (byte, d) in expected.iter_mut().zip(delta.iter()) {
+                *byte ^= d;
+            }
+        }
+        assert_eq!(data[i], expected, "COT {} is not correlated", i);
+    }
+    let elapsed = elapsed.max(send_elapsed);
+    println!(
+        "{} {}-bit IKNP: {} COTs in {:?}, {:.2} million COTs/s",
+        if malicious { "Malicious" } else { "Semi-honest" }, 8 * N,
+        length, elapsed, length as f64 / elapsed.as_secs_f64() / 1e6
+    );
+}
+
+fn main() {
+    let args: Vec<String> = env::args().collect();
+    let length: usize = args.get(1).and_then(|s| s.parse().ok()).unwrap_or(1 << 20);
+    run::<16>(false, length);
+    run::<16>(true, length);
+    run::<32>(false, length);
+    run::<32>(true, length);
+}
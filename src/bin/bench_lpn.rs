@@ -0,0 +1,39 @@
+// Times the primal LPN encoding on one thread and on all cores
+// The encodings must be identical, only the time may differ
+// Usage: bench_lpn [rows] [k]
+
+extern crate vole_rust;
+
+use vole_rust::lpn::Lpn;
+use vole_rust::fp61::{Fp61, Mersenne61Field};
+use vole_rust::parallel::default_threads;
+use std::env;
+use std::time::{Duration, Instant};
+
+// Returns the receiver's encoding of (K, M) and the time it took
+fn encode(n: usize, k: usize, threads: usize) -> (Vec<Fp61>, Vec<Fp61>, Duration) {
+    let pre_k: Vec<Fp61> = (0..k).map(|i| Fp61::from(3 * i as u64 + 1)).collect();
+    let pre_m: Vec<Fp61> = (0..k).map(|i| Fp61::from(5 * i as u64 + 2)).collect();
+    let mut y: Vec<Fp61> = (0..n).map(|i| Fp61::from(i as u64)).collect();
+    let mut z = vec![Fp61::zero(); n];
+
+    let mut lpn = Lpn::<Mersenne61Field>::new(k, n, &[7u8; 16], &[9u8; 32]);
+    lpn.set_threads(threads);
+    let start = Instant::now();
+    lpn.compute_recv(&mut y, &mut z, &pre_k, &pre_m);
+    (y, z, start.elapsed())
+}
+
+fn main() {
+    let args: Vec<String> = env::args().collect();
+    let n: usize = args.get(1).and_then(|s| s.parse().ok()).unwrap_or(1 << 20);
+    let k: usize = args.get(2).and_then(|s| s.parse().ok()).unwrap_or(158000);
+    let threads = default_threads();
+
+    let (y1, z1, single) = encode(n, k, 1);
+    println!("{} rows, k = {}: {:?} on 1 thread", n, k, single);
+    let (yn, zn, multi) = encode(n, k, threads);
+    println!("{} rows, k = {}: {:?} on {} threads", n, k, multi, threads);
+    assert!(y1 == yn && z1 == zn, "Encodings differ between 1 and {} threads", threads);
+    println!("Encodings match");
+}
@@ -0,0 +1,68 @@
+// Times VoleTriple setup and extension for each LPN parameter set over an emulated link
+// Usage: bench_lpn_params [lan|wan|local] [number of VOLEs]
+
+extern crate vole_rust;
+
+use vole_rust::mem_channel::MemChannel;
+use vole_rust::sim_channel::{SimChannel, LinkConfig};
+use vole_rust::counting_channel::CountingChannel;
+use vole_rust::comm_channel::CommunicationChannel;
+use vole_rust::vole_triple::{VoleTriple, PrimalLPNParameterFp61, FP_DEFAULT, WOLVERINE_LPN, PHUOC_LPN};
+use vole_rust::fp61::{Fp61, Mersenne61Field};
+use vole_rust::error::Result;
+use std::env;
+use std::thread;
+use std::time::{Duration, Instant};
+
+fn param(name: &str) -> PrimalLPNParameterFp61 {
+    match name {
+        "FP_DEFAULT" => FP_DEFAULT,
+        "WOLVERINE_LPN" => WOLVERINE_LPN,
+        _ => PHUOC_LPN,
+    }
+}
+
+// Returns the setup and extension times of one party
+fn run_party<IO: CommunicationChannel>(party: usize, io: &mut IO, name: &str, num: usize) -> Result<(Duration, Duration)> {
+    let start = Instant::now();
+    let mut vole = VoleTriple::<Mersenne61Field>::new(party, false, io, param(name))?;
+    if party == 0 {
+        vole.setup_sender(io, Fp61::from(7))?;
+    } else {
+        vole.setup_receiver(io)?;
+    }
+    let setup = start.elapsed();
+
+    vole.extend_initialization();
+    let mut y = vec![Fp61::zero(); num];
+    let mut z = vec![Fp61::zero(); num];
+    let start = Instant::now();
+    vole.extend(io, &mut y, &mut z, num)?;
+    Ok((setup, start.elapsed()))
+}
+
+fn main() {
+    let args: Vec<String> = env::args().collect();
+    let (link_name, link) = match args.get(1).map(|s| s.as_str()) {
+        Some("wan") => ("WAN", LinkConfig::wan()),
+        Some("local") => ("local", LinkConfig::unlimited()),
+        _ => ("LAN", LinkConfig::lan()),
+    };
+    let num: usize = args.get(2).and_then(|s| s.parse().ok()).unwrap_or(1 << 20);
+
+    println!("{} link: {:?} one-way, {:?} B/s, {} VOLEs", link_name, link.latency, link.bandwidth, num);
+    for name in ["PHUOC_LPN", "FP_DEFAULT", "WOLVERINE_LPN"].iter() {
+        let (alice, bob) = MemChannel::pair();
+        let mut alice = CountingChannel::new(SimChannel::new(alice, link));
+        let mut bob = CountingChannel::new(SimChannel::new(bob, link));
+
+        let sender = thread::spawn(move || run_party(0, &mut alice, name, num).map(|t| (t, alice.snapshot())));
+        let (setup, extend) = run_party(1, &mut bob, name, num).expect("VOLE receiver failed");
+        let (_, sender_stats) = sender.join().unwrap().expect("VOLE sender failed");
+
+        println!(
+            "{:>14}: setup {:>10.3?}, extend {:>10.3?}, sender {}",
+            name, setup, extend, sender_stats
+        );
+    }
+}
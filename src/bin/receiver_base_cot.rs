@@ -18,27 +18,26 @@ fn main() {
     let mut receiver_cot = BaseCot::new(1, false);
 
     // Set up the receiver's precomputation phase
-    receiver_cot.cot_gen_pre(&mut channel, None);
+    receiver_cot.cot_gen_pre(&mut channel, None).expect("Failed to set up base COT");
 
     // Original COT generation
     let size = 60; // Number of COTs
     let times = 100;
-    let mut original_ot_data = vec![[0u8; 32]; size];
-    let mut choice_bits = vec![false; size];
+    let choice_bits = vec![false; size];
 
     let mut receiver_pre_ot = OTPre::new(size, times);
-    receiver_cot.cot_gen_preot(&mut channel, &mut receiver_pre_ot, size * times, None);
+    receiver_cot.cot_gen_preot(&mut channel, &mut receiver_pre_ot, size * times, None).expect("Failed to generate pre-OTs");
 
     let start = Instant::now();
-    for s in 0..times {
-        receiver_pre_ot.choices_recver(&mut channel, &choice_bits);
+    for _s in 0..times {
+        receiver_pre_ot.choices_recver(&mut channel, &choice_bits).expect("Failed to send choice bits");
     }
-    channel.flush();
+    channel.flush().expect("Failed to flush channel");
     receiver_pre_ot.reset();
     // Receive data using OTPre
     for s in 0..times {
         let mut received_data = vec![[0u8; 32]; size];
-        receiver_pre_ot.recv(&mut channel, &mut received_data, &choice_bits, size, s);
+        receiver_pre_ot.recv(&mut channel, &mut received_data, &choice_bits, size, s).expect("Failed to receive OT");
     }
     let duration = start.elapsed();
     println!("Time taken: {:?}", duration);
@@ -28,7 +28,7 @@ fn main() {
     let mut channel = TcpChannel::new(stream);
 
     // Set up BaseSvole
-    let mut receiver_svole = BaseSvole::new_receiver(&mut channel);
+    let mut receiver_svole = BaseSvole::new_receiver(&mut channel).expect("Failed to set up base sVOLE");
 
     // Test triple generation
     let batch_size = 20000;
@@ -37,7 +37,7 @@ fn main() {
 
     let start = Instant::now();
 
-    receiver_svole.triple_gen_recv(&mut channel, &mut shares, &mut u_batch, batch_size);
+    receiver_svole.triple_gen_recv(&mut channel, &mut shares, &mut u_batch, batch_size).expect("Failed to generate base sVOLE");
 
     let duration = start.elapsed();
     println!("Triple generation (recv) time: {:?}", duration);
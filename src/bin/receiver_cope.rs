@@ -3,7 +3,7 @@ extern crate lambdaworks_math;
 extern crate rand;
 extern crate rand_chacha;
 
-use std::net::{TcpListener, TcpStream};
+use std::net::TcpListener;
 use std::time::Instant;
 use vole_rust::socket_channel::TcpChannel;
 use vole_rust::cope::Cope;
@@ -11,13 +11,13 @@ use lambdaworks_math::field::fields::fft_friendly::stark_252_prime_field::Stark2
 use lambdaworks_math::field::element::FieldElement;
 use lambdaworks_math::field::traits::IsPrimeField;
 use lambdaworks_math::unsigned_integer::element::UnsignedInteger;
-use rand::{RngCore, random, SeedableRng};
+use rand::{random, SeedableRng};
 use rand_chacha::ChaCha20Rng;
 
 pub type F = Stark252PrimeField;
 pub type FE = FieldElement<F>;
 
-pub fn rand_field_element(rng: &mut dyn rand::RngCore) -> FE {
+pub fn rand_field_element(_rng: &mut dyn rand::RngCore) -> FE {
     let rand_big = UnsignedInteger { limbs: random() };
     FE::new(rand_big)
 }
@@ -35,15 +35,15 @@ fn main() {
     let mut receiver_cope = Cope::new(1, m);
 
     // Receiver initializes
-    receiver_cope.initialize_receiver(&mut channel);
+    receiver_cope.initialize_receiver(&mut channel).expect("Failed to initialize COPE");
 
     // Generate a random u
     let u = rand_field_element(&mut rng);
     println!("Receiver u: {}", u);
 
     // Test extend
-    let single_result = receiver_cope.extend_receiver(&mut channel, u);
-    receiver_cope.check_triple(&mut channel, &[u], &[single_result], 1);
+    let single_result = receiver_cope.extend_receiver(&mut channel, u).expect("Failed to extend COPE");
+    receiver_cope.check_triple(&mut channel, &[u], &[single_result], 1).expect("Failed to check triples");
 
     // // Test extend
     // let single_result = receiver_cope.extend_receiver(u);
@@ -55,11 +55,11 @@ fn main() {
     let batch_size = 20000;
     let u_batch: Vec<FE> = (0..batch_size).map(|_| rand_field_element(&mut rng)).collect();
     let mut batch_result = vec![FE::zero(); batch_size];
-    receiver_cope.extend_receiver_batch(&mut channel, &mut batch_result, &u_batch, batch_size);
+    receiver_cope.extend_receiver_batch(&mut channel, &mut batch_result, &u_batch, batch_size).expect("Failed to extend COPE");
 
     let duration = start.elapsed();
     println!("Time taken: {:?}", duration);
 
-    receiver_cope.check_triple(&mut channel, &u_batch, &batch_result, batch_size);
+    receiver_cope.check_triple(&mut channel, &u_batch, &batch_result, batch_size).expect("Failed to check triples");
 
 }
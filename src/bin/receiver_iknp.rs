@@ -2,7 +2,6 @@ extern crate vole_rust;
 extern crate rand;
 
 use vole_rust::iknp::IKNP;
-use vole_rust::comm_channel::CommunicationChannel;
 use vole_rust::socket_channel::TcpChannel;
 use std::net::TcpListener;
 use rand::Rng;
@@ -14,14 +13,14 @@ fn main() {
     let mut io = TcpChannel::new(stream);
 
     let mut receiver_iknp = IKNP::new(true);
-    receiver_iknp.setup_recv(&mut io, None, None);
+    receiver_iknp.setup_recv(&mut io, None, None).expect("Failed to set up IKNP");
 
-    const length: usize = 2048;
-    let mut data = vec![[0u8; 32]; length];
+    const LENGTH: usize = 2048;
+    let mut data = vec![[0u8; 32]; LENGTH];
     let mut rng = rand::thread_rng();
-    let r: [bool; length] = [(); length].map(|_| rng.gen_bool(0.5)); // Example choice bits
+    let r: [bool; LENGTH] = [(); LENGTH].map(|_| rng.gen_bool(0.5)); // Example choice bits
 
-    receiver_iknp.recv_cot(&mut io, &mut data, &r, length);
+    receiver_iknp.recv_cot(&mut io, &mut data, &r, LENGTH).expect("Failed to run IKNP");
 
     println!("Choice bits: {:?}", &r[..5]);
     println!("Receiver COT data: {:?}", &data[..5]);
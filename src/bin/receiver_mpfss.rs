@@ -2,9 +2,7 @@ extern crate vole_rust;
 extern crate lambdaworks_math;
 extern crate rand;
 
-use vole_rust::comm_channel::CommunicationChannel;
 use vole_rust::socket_channel::TcpChannel;
-use vole_rust::spfss_sender::SpfssSenderFp;
 use vole_rust::preot::OTPre;
 use vole_rust::base_cot::BaseCot;
 use vole_rust::mpfss_reg::MpfssReg;
@@ -29,32 +27,31 @@ fn main() {
     let (stream, _) = listener.accept().expect("Failed to accept connection");
     let mut channel = TcpChannel::new(stream);
 
-    const log_bin_sz: usize = 4;
-    const t: usize = 100;
-    const n: usize = t * (1 << log_bin_sz);
-    const k: usize = 2;
+    const LOG_BIN_SZ: usize = 4;
+    const T: usize = 100;
+    const N: usize = T * (1 << LOG_BIN_SZ);
 
     // Initialize BaseCot for the sender (ALICE)
     let mut receiver_cot = BaseCot::new(1, false);
 
     // Set up the sender's precomputation phase
-    receiver_cot.cot_gen_pre(&mut channel, None);
-    let mut pre_ot = OTPre::new(log_bin_sz, t);
-    receiver_cot.cot_gen_preot(&mut channel, &mut pre_ot, log_bin_sz*t, None);
+    receiver_cot.cot_gen_pre(&mut channel, None).expect("Failed to set up base COT");
+    let mut pre_ot = OTPre::new(LOG_BIN_SZ, T);
+    receiver_cot.cot_gen_preot(&mut channel, &mut pre_ot, LOG_BIN_SZ*T, None).expect("Failed to generate pre-OTs");
 
-    let mut mac = vec![FE::zero(); t+1];
-    let mut u = vec![FE::zero(); t+1];
+    let mut mac = vec![FE::zero(); T+1];
+    let mut u = vec![FE::zero(); T+1];
 
     // Base sVOLE first
-    let mut svole = BaseSvole::new_receiver(&mut channel);
+    let mut svole = BaseSvole::new_receiver(&mut channel).expect("Failed to set up base sVOLE");
     // mac = key + delta * u
-    svole.triple_gen_recv(&mut channel, &mut mac, &mut u, t+1);
+    svole.triple_gen_recv(&mut channel, &mut mac, &mut u, T+1).expect("Failed to generate base sVOLE");
 
-    let mut y = vec![FE::zero(); n];
-    let mut z = vec![FE::zero(); n];
-    let mut mpfss = MpfssReg::new(n, t, log_bin_sz, 1);
+    let mut y = vec![FE::zero(); N];
+    let mut z = vec![FE::zero(); N];
+    let mut mpfss = MpfssReg::new(N, T, LOG_BIN_SZ, 1);
     mpfss.set_malicious();
 
     mpfss.receiver_init();
-    mpfss.mpfss_receiver(&mut channel, &mut pre_ot, &mac, &u, &mut y, &mut z);
+    mpfss.mpfss_receiver(&mut channel, &mut pre_ot, &mac, &u, &mut y, &mut z).expect("Failed to run MPFSS");
 }
\ No newline at end of file
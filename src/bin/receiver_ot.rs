@@ -15,7 +15,7 @@ fn main() {
 
     // Initialize OTCO and receive
     let mut otco = OTCO::new();
-    otco.recv(&mut channel, &choices, &mut output);
+    otco.recv(&mut channel, &choices, &mut output).expect("Failed to receive OT");
 
     // Verify the output
     println!("Received output: {:?}", output);
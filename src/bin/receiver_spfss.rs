@@ -32,11 +32,11 @@ fn main() {
     let mut receiver_cot = BaseCot::new(1, false);
 
     // Set up the receiver's precomputation phase
-    receiver_cot.cot_gen_pre(&mut channel, None);
+    receiver_cot.cot_gen_pre(&mut channel, None).expect("Failed to set up base COT");
 
     // Original COT generation
-    const depth: usize = 4;
-    let size = depth - 1; // Number of COTs
+    const DEPTH: usize = 4;
+    let size = DEPTH - 1; // Number of COTs
     let times = 100;
     let mut choice_bits = vec![false; size*times];
     // Populate random choice bits
@@ -46,26 +46,26 @@ fn main() {
 
     // New COT generation using OTPre
     let mut receiver_pre_ot = OTPre::new(size, times);
-    receiver_cot.cot_gen_preot(&mut channel, &mut receiver_pre_ot, size*times, Some(&choice_bits));
+    receiver_cot.cot_gen_preot(&mut channel, &mut receiver_pre_ot, size*times, Some(&choice_bits)).expect("Failed to generate pre-OTs");
 
     let received_data = channel.receive_stark252(2).expect("Failed to receive delta and gamma");
     let delta = received_data[0];
     let gamma = received_data[1];
 
-    let mut ggm_tree_mem = [FE::zero(); 1 << (depth - 1)];
-    for i in 0..times {
-        receiver_pre_ot.choices_recver(&mut channel, &[false; depth - 1]);
+    let mut ggm_tree_mem = [FE::zero(); 1 << (DEPTH - 1)];
+    for _i in 0..times {
+        receiver_pre_ot.choices_recver(&mut channel, &[false; DEPTH - 1]).expect("Failed to send choice bits");
     }
-    channel.flush();
+    channel.flush().expect("Failed to flush channel");
     receiver_pre_ot.reset();
 
-    for i in 0..times {
+    for _i in 0..times {
         let beta = rand_field_element();
         let delta2 = gamma + delta * beta;
         // Initialize Spfss for the sender
-        let mut receiver_spfss = SpfssRecverFp::new(depth);
+        let mut receiver_spfss = SpfssRecverFp::new(DEPTH);
 
-        receiver_spfss.recv(&mut channel, &mut receiver_pre_ot, 0);
+        receiver_spfss.recv(&mut channel, &mut receiver_pre_ot, 0).expect("Failed to receive SPFSS");
         receiver_spfss.compute(&mut ggm_tree_mem, delta2);
         // receiver_spfss.consistency_check(&mut channel, delta2, beta);
     }
@@ -1,7 +1,7 @@
 extern crate vole_rust;
 extern crate lambdaworks_math;
 
-use std::net::{TcpListener, TcpStream};
+use std::net::TcpListener;
 use vole_rust::comm_channel::CommunicationChannel;
 use vole_rust::socket_channel::TcpChannel;
 use lambdaworks_math::field::fields::fft_friendly::stark_252_prime_field::Stark252PrimeField;
@@ -11,17 +11,16 @@ use std::time::Instant;
 pub type F = Stark252PrimeField;
 pub type FE = FieldElement<F>;
 
-fn bench_32byte(channel: &mut CommunicationChannel) {
-    const size: usize = 10000;
-    let elements = [[0u8; 32]; 4];
+fn bench_32byte(channel: &mut dyn CommunicationChannel) {
+    const SIZE: usize = 10000;
 
     let start = Instant::now();
-    for i in 0..size {
-        let x = channel.receive_32byte_block();
+    for _i in 0..SIZE {
+        channel.receive_32byte_block().expect("Failed to receive blocks");
     }
     let duration = start.elapsed();
 
-    println!("Receive {} elements in {:?}", size, duration);
+    println!("Receive {} elements in {:?}", SIZE, duration);
 }
 
 fn main() {
@@ -1,9 +1,8 @@
 extern crate vole_rust;
 extern crate lambdaworks_math;
 extern crate rand;
-extern crate rand_chacha;
 
-use std::net::{TcpListener, TcpStream};
+use std::net::TcpListener;
 use vole_rust::socket_channel::TcpChannel;
 use vole_rust::comm_channel::CommunicationChannel;
 use vole_rust::cope::Cope;
@@ -11,19 +10,17 @@ use lambdaworks_math::field::fields::fft_friendly::stark_252_prime_field::Stark2
 use lambdaworks_math::field::element::FieldElement;
 use lambdaworks_math::field::traits::IsPrimeField;
 use lambdaworks_math::unsigned_integer::element::UnsignedInteger;
-use rand::{RngCore, random, SeedableRng};
-use rand_chacha::ChaCha20Rng;
+use rand::random;
 
 pub type F = Stark252PrimeField;
 pub type FE = FieldElement<F>;
 
-pub fn rand_field_element(rng: &mut dyn rand::RngCore) -> FE {
+pub fn rand_field_element(_rng: &mut dyn rand::RngCore) -> FE {
     let rand_big = UnsignedInteger { limbs: random() };
     FE::new(rand_big)
 }
 
 fn main() {
-    let mut rng = ChaCha20Rng::seed_from_u64(42);
 
     // Listen for the sender
     let listener = TcpListener::bind("127.0.0.1:8080").expect("Failed to bind to port");
@@ -54,5 +51,5 @@ fn main() {
     // let c = b - a * delta;
 
     // Perform the check
-    receiver_cope.check_triple(&mut channel, &[a], &[b], 1);
+    receiver_cope.check_triple(&mut channel, &[a], &[b], 1).expect("Failed to check triples");
 }
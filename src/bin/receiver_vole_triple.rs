@@ -17,18 +17,18 @@ fn main() {
     let (stream, _) = listener.accept().expect("Failed to accept connection");
     let mut channel = TcpChannel::new(stream);
 
-    let mut vole = VoleTriple::new(1, false, &mut channel, PHUOC_LPN);
+    let mut vole = VoleTriple::new(1, false, &mut channel, PHUOC_LPN).expect("Failed to create VOLE");
     
     let start = Instant::now();
-    vole.setup_receiver(&mut channel);
+    vole.setup_receiver(&mut channel).expect("Failed to run VOLE setup");
     println!("Time taken for setup: {:?}", start.elapsed());
 
     vole.extend_initialization();
 
-    const size: usize = 1000;
-    let mut y = [FE::zero(); size];
-    let mut z = [FE::zero(); size];
+    const SIZE: usize = 1000;
+    let mut y = [FE::zero(); SIZE];
+    let mut z = [FE::zero(); SIZE];
     let start = Instant::now();
-    vole.extend(&mut channel, &mut y, &mut z, size);
+    vole.extend(&mut channel, &mut y, &mut z, SIZE).expect("Failed to extend VOLE");
     println!("Time taken for one extend: {:?}", start.elapsed());
 }
\ No newline at end of file
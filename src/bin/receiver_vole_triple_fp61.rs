@@ -0,0 +1,33 @@
+extern crate vole_rust;
+extern crate lambdaworks_math;
+
+use vole_rust::socket_channel::TcpChannel;
+use vole_rust::vole_triple::{VoleTriple, FP_DEFAULT};
+use vole_rust::fp61::Fp61;
+use std::net::TcpListener;
+use std::time::Instant;
+
+fn main() {
+    // Listen for the sender
+    let listener = TcpListener::bind("127.0.0.1:8080").expect("Failed to bind to port");
+    let (stream, _) = listener.accept().expect("Failed to accept connection");
+    let mut channel = TcpChannel::new(stream);
+
+    let mut vole = VoleTriple::new(1, false, &mut channel, FP_DEFAULT).expect("Failed to create VOLE");
+
+    let start = Instant::now();
+    vole.setup_receiver(&mut channel).expect("Failed to run VOLE setup");
+    println!("Time taken for setup: {:?}", start.elapsed());
+
+    vole.extend_initialization();
+
+    const SIZE: usize = 1000;
+    let mut y = vec![Fp61::zero(); SIZE];
+    let mut z = vec![Fp61::zero(); SIZE];
+    let start = Instant::now();
+    vole.extend(&mut channel, &mut y, &mut z, SIZE).expect("Failed to extend VOLE");
+    println!("Time taken for one extend: {:?}", start.elapsed());
+
+    vole.check_triple(&mut channel, Fp61::zero(), &y, &z, SIZE).expect("Failed to check triples");
+    println!("Triples are consistent");
+}
@@ -0,0 +1,39 @@
+extern crate vole_rust;
+extern crate lambdaworks_math;
+
+use vole_rust::socket_channel::TcpChannel;
+use vole_rust::vole_triple::{VoleTriple, FP_DEFAULT};
+use vole_rust::ext_field::{Ext2, Ext2Element};
+use lambdaworks_math::field::element::FieldElement;
+use lambdaworks_math::field::fields::mersenne31::field::Mersenne31Field;
+use std::net::TcpListener;
+use std::time::Instant;
+
+// u over the 31-bit Mersenne prime, MACs over its quadratic extension
+type F = Mersenne31Field;
+type K = Ext2<Mersenne31Field>;
+
+fn main() {
+    // Listen for the sender
+    let listener = TcpListener::bind("127.0.0.1:8080").expect("Failed to bind to port");
+    let (stream, _) = listener.accept().expect("Failed to accept connection");
+    let mut channel = TcpChannel::new(stream);
+
+    let mut vole = VoleTriple::<F, K>::new(1, false, &mut channel, FP_DEFAULT).expect("Failed to create VOLE");
+
+    let start = Instant::now();
+    vole.setup_receiver(&mut channel).expect("Failed to run VOLE setup");
+    println!("Time taken for setup: {:?}", start.elapsed());
+
+    vole.extend_initialization();
+
+    const SIZE: usize = 1000;
+    let mut y = vec![Ext2Element::<F>::zero(); SIZE];
+    let mut z = vec![FieldElement::<F>::zero(); SIZE];
+    let start = Instant::now();
+    vole.extend(&mut channel, &mut y, &mut z, SIZE).expect("Failed to extend VOLE");
+    println!("Time taken for one extend: {:?}", start.elapsed());
+
+    vole.check_triple(&mut channel, Ext2Element::<F>::zero(), &y, &z, SIZE).expect("Failed to check triples");
+    println!("Triples are consistent");
+}
@@ -15,19 +15,18 @@ fn main() {
     let mut sender_cot = BaseCot::new(0, false);
 
     // Set up the sender's precomputation phase
-    sender_cot.cot_gen_pre(&mut channel, None);
+    sender_cot.cot_gen_pre(&mut channel, None).expect("Failed to set up base COT");
 
     // Original COT generation
     let size = 60; // Number of COTs
     let times = 100;
-    let mut original_ot_data = vec![[0u8; 32]; size];
 
     let mut sender_pre_ot = OTPre::new(size, times);
-    sender_cot.cot_gen_preot(&mut channel, &mut sender_pre_ot, size*times, None);
-    for s in 0..times {
-        sender_pre_ot.choices_sender(&mut channel);
+    sender_cot.cot_gen_preot(&mut channel, &mut sender_pre_ot, size*times, None).expect("Failed to generate pre-OTs");
+    for _s in 0..times {
+        sender_pre_ot.choices_sender(&mut channel).expect("Failed to receive choice bits");
     }
-    channel.flush();
+    channel.flush().expect("Failed to flush channel");
     sender_pre_ot.reset();
 
     for s in 0..times {
@@ -38,6 +37,6 @@ fn main() {
             m0[i] = [i as u8; 32];
             m1[i] = [(i + 1) as u8; 32];
         }
-        sender_pre_ot.send(&mut channel, &m0, &m1, size, s);
+        sender_pre_ot.send(&mut channel, &m0, &m1, size, s).expect("Failed to send OT");
     }
 }
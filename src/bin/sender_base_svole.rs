@@ -11,7 +11,6 @@ use lambdaworks_math::field::fields::fft_friendly::stark_252_prime_field::Stark2
 use lambdaworks_math::field::element::FieldElement;
 use lambdaworks_math::unsigned_integer::element::UnsignedInteger;
 use rand::random;
-use rand_chacha::ChaCha20Rng;
 
 pub type F = Stark252PrimeField;
 pub type FE = FieldElement<F>;
@@ -30,7 +29,7 @@ fn main() {
     let delta = rand_field_element();
     println!("Sender Delta: {}", delta);
 
-    let mut sender_svole = BaseSvole::new_sender(&mut channel, delta);
+    let mut sender_svole: BaseSvole = BaseSvole::new_sender(&mut channel, delta).expect("Failed to set up base sVOLE");
 
     // Test triple generation
     let batch_size = 20000;
@@ -38,7 +37,7 @@ fn main() {
 
     let start = Instant::now();
 
-    sender_svole.triple_gen_send(&mut channel, &mut shares, batch_size);
+    sender_svole.triple_gen_send(&mut channel, &mut shares, batch_size).expect("Failed to generate base sVOLE");
 
     let duration = start.elapsed();
     println!("Triple generation (send) time: {:?}", duration);
@@ -11,13 +11,13 @@ use lambdaworks_math::field::fields::fft_friendly::stark_252_prime_field::Stark2
 use lambdaworks_math::field::element::FieldElement;
 use lambdaworks_math::field::traits::IsPrimeField;
 use lambdaworks_math::unsigned_integer::element::UnsignedInteger;
-use rand::{RngCore, random, SeedableRng};
+use rand::{random, SeedableRng};
 use rand_chacha::ChaCha20Rng;
 
 pub type F = Stark252PrimeField;
 pub type FE = FieldElement<F>;
 
-pub fn rand_field_element(rng: &mut dyn rand::RngCore) -> FE {
+pub fn rand_field_element(_rng: &mut dyn rand::RngCore) -> FE {
     let rand_big = UnsignedInteger { limbs: random() };
     FE::new(rand_big)
 }
@@ -38,11 +38,11 @@ fn main() {
     println!("Sender delta: {}", delta);
 
     // Sender initializes with delta
-    sender_cope.initialize_sender(&mut channel, delta);
+    sender_cope.initialize_sender(&mut channel, delta).expect("Failed to initialize COPE");
 
     // Test extend
-    let single_result = sender_cope.extend_sender(&mut channel);
-    sender_cope.check_triple(&mut channel, &[delta], &[single_result], 1);
+    let single_result = sender_cope.extend_sender(&mut channel).expect("Failed to extend COPE");
+    sender_cope.check_triple(&mut channel, &[delta], &[single_result], 1).expect("Failed to check triples");
 
     // // Test extend
     // let single_result = sender_cope.extend_sender();
@@ -53,11 +53,11 @@ fn main() {
     // Test extend_batch
     let batch_size = 20000;
     let mut batch_result = vec![FE::zero(); batch_size];
-    sender_cope.extend_sender_batch(&mut channel, &mut batch_result, batch_size);
+    sender_cope.extend_sender_batch(&mut channel, &mut batch_result, batch_size).expect("Failed to extend COPE");
 
     let duration = start.elapsed();
     println!("Time taken: {:?}", duration);
 
-    sender_cope.check_triple(&mut channel, &[delta], &batch_result, batch_size);
+    sender_cope.check_triple(&mut channel, &[delta], &batch_result, batch_size).expect("Failed to check triples");
 
 }
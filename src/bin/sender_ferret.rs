@@ -0,0 +1,26 @@
+extern crate vole_rust;
+
+use vole_rust::socket_channel::TcpChannel;
+use vole_rust::ferret_cot::{FerretCot, FERRET_B13};
+use std::net::TcpStream;
+use std::time::Instant;
+
+fn main() {
+    // Connect to the receiver
+    let stream = TcpStream::connect("127.0.0.1:8080").expect("Failed to connect to receiver");
+    let mut channel = TcpChannel::new(stream);
+
+    let mut ferret = FerretCot::new(0, &mut channel, FERRET_B13).expect("Failed to create Ferret COT");
+
+    let start = Instant::now();
+    ferret.setup(&mut channel).expect("Failed to run Ferret setup");
+    println!("Time taken for setup: {:?}", start.elapsed());
+
+    const SIZE: usize = 1000;
+    let mut data = vec![[0u8; 16]; SIZE];
+    let start = Instant::now();
+    ferret.extend(&mut channel, &mut data, SIZE).expect("Failed to extend COTs");
+    println!("Time taken for one extend: {:?}", start.elapsed());
+
+    ferret.check_cot(&mut channel, &data, SIZE).expect("Failed to check COTs");
+}
@@ -1,7 +1,6 @@
 extern crate vole_rust;
 
 use vole_rust::iknp::IKNP;
-use vole_rust::comm_channel::CommunicationChannel;
 use vole_rust::socket_channel::TcpChannel;
 use std::net::TcpStream;
 
@@ -11,11 +10,11 @@ fn main() {
     let mut io = TcpChannel::new(stream);
 
     let mut sender_iknp = IKNP::new(true);
-    sender_iknp.setup_send(&mut io, None, None);
+    sender_iknp.setup_send(&mut io, None, None).expect("Failed to set up IKNP");
 
     let length = 2048;
     let mut data = vec![[0u8; 32]; length];
-    sender_iknp.send_cot(&mut io, &mut data, length);
+    sender_iknp.send_cot(&mut io, &mut data, length).expect("Failed to run IKNP");
 
     println!("Sender COT data: {:?}", &data[..5]);
 }
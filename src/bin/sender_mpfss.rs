@@ -2,9 +2,7 @@ extern crate vole_rust;
 extern crate lambdaworks_math;
 extern crate rand;
 
-use vole_rust::comm_channel::CommunicationChannel;
 use vole_rust::socket_channel::TcpChannel;
-use vole_rust::spfss_sender::SpfssSenderFp;
 use vole_rust::preot::OTPre;
 use vole_rust::base_cot::BaseCot;
 use vole_rust::mpfss_reg::MpfssReg;
@@ -29,36 +27,35 @@ fn main() {
     let stream = TcpStream::connect("127.0.0.1:8080").expect("Failed to connect to receiver");
     let mut channel = TcpChannel::new(stream);
 
-    const log_bin_sz: usize = 4;
-    const t: usize = 100;
-    const n: usize = t * (1 << log_bin_sz);
-    const k: usize = 2;
+    const LOG_BIN_SZ: usize = 4;
+    const T: usize = 100;
+    const N: usize = T * (1 << LOG_BIN_SZ);
 
     // Initialize BaseCot for the sender (ALICE)
     let mut sender_cot = BaseCot::new(0, false);
 
     // Set up the sender's precomputation phase
-    sender_cot.cot_gen_pre(&mut channel, None);
-    let mut pre_ot = OTPre::new(log_bin_sz, t);
-    sender_cot.cot_gen_preot(&mut channel, &mut pre_ot, log_bin_sz*t, None);
+    sender_cot.cot_gen_pre(&mut channel, None).expect("Failed to set up base COT");
+    let mut pre_ot = OTPre::new(LOG_BIN_SZ, T);
+    sender_cot.cot_gen_preot(&mut channel, &mut pre_ot, LOG_BIN_SZ*T, None).expect("Failed to generate pre-OTs");
 
 
     let delta = rand_field_element();
-    let mut key = vec![FE::zero(); t+1];
+    let mut key = vec![FE::zero(); T+1];
 
     // Base sVOLE first
-    let mut svole = BaseSvole::new_sender(&mut channel, delta);
+    let mut svole: BaseSvole = BaseSvole::new_sender(&mut channel, delta).expect("Failed to set up base sVOLE");
     // mac = key + delta * u
-    svole.triple_gen_send(&mut channel, &mut key, t+1);
+    svole.triple_gen_send(&mut channel, &mut key, T+1).expect("Failed to generate base sVOLE");
 
-    let mut y = vec![FE::zero(); n];
-    let mut mpfss = MpfssReg::new(n, t, log_bin_sz, 0);
+    let mut y = vec![FE::zero(); N];
+    let mut mpfss: MpfssReg = MpfssReg::new(N, T, LOG_BIN_SZ, 0);
     mpfss.set_malicious();
 
     mpfss.sender_init(delta);
 
     let start = Instant::now();
-    mpfss.mpfss_sender(&mut channel, &mut pre_ot, &key, &mut y);
+    mpfss.mpfss_sender(&mut channel, &mut pre_ot, &key, &mut y).expect("Failed to run MPFSS");
     let duration = start.elapsed();
-    println!("Time taken to generate {} Spfss: {:?}", t, duration);
+    println!("Time taken to generate {} Spfss: {:?}", T, duration);
 }
\ No newline at end of file
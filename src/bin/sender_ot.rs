@@ -19,7 +19,7 @@ fn main() {
 
         // Initialize OTCO and send
         let mut otco = OTCO::new();
-        otco.send(&mut channel, &data0, &data1);
+        otco.send(&mut channel, &data0, &data1).expect("Failed to send OT");
 
         println!("Sender finished sending data");
     }
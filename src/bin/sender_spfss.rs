@@ -33,35 +33,35 @@ fn main() {
     let mut sender_cot = BaseCot::new(0, false);
 
     // Set up the sender's precomputation phase
-    sender_cot.cot_gen_pre(&mut channel, None);
+    sender_cot.cot_gen_pre(&mut channel, None).expect("Failed to set up base COT");
 
     // Original COT generation
-    const depth: usize = 4;
-    let size = depth - 1; // Number of COTs
+    const DEPTH: usize = 4;
+    let size = DEPTH - 1; // Number of COTs
     let times = 100;
     // New COT generation using OTPre
     let mut sender_pre_ot = OTPre::new(size, times);
-    sender_cot.cot_gen_preot(&mut channel, &mut sender_pre_ot, size*times, None);
+    sender_cot.cot_gen_preot(&mut channel, &mut sender_pre_ot, size*times, None).expect("Failed to generate pre-OTs");
 
     let delta = rand_field_element();
     let gamma = rand_field_element();
-    channel.send_stark252(&[delta.clone(), gamma.clone()]).expect("Failed to send delta and gamma");
-    let mut ggm_tree_mem = [FE::zero(); 1 << (depth - 1)];
+    channel.send_stark252(&[delta, gamma]).expect("Failed to send delta and gamma");
+    let mut ggm_tree_mem = [FE::zero(); 1 << (DEPTH - 1)];
 
     let start = Instant::now();
-    for i in 0..times {
-        sender_pre_ot.choices_sender(&mut channel);
+    for _i in 0..times {
+        sender_pre_ot.choices_sender(&mut channel).expect("Failed to receive choice bits");
     }
-    channel.flush();
+    channel.flush().expect("Failed to flush channel");
     sender_pre_ot.reset();
 
-    for i in 0..times {
+    for _i in 0..times {
         // Initialize Spfss for the sender
-        let mut sender_spfss = SpfssSenderFp::new(depth);
+        let mut sender_spfss = SpfssSenderFp::new(DEPTH);
 
         sender_spfss.compute(&mut ggm_tree_mem, delta, gamma);
-        sender_spfss.send(&mut channel, &mut sender_pre_ot, 0);
-        channel.flush();
+        sender_spfss.send(&mut channel, &mut sender_pre_ot, 0).expect("Failed to send SPFSS");
+        channel.flush().expect("Failed to flush channel");
 
         // sender_spfss.consistency_check(&mut channel, gamma);
     }
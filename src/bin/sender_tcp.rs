@@ -21,17 +21,17 @@ pub fn rand_field_element() -> FE {
     FE::new(rand_big)
 }
 
-fn bench_32byte(channel: &mut CommunicationChannel) {
-    const size: usize = 10000;
+fn bench_32byte(channel: &mut dyn CommunicationChannel) {
+    const SIZE: usize = 10000;
     let elements = [[0u8; 32]; 4];
 
     let start = Instant::now();
-    for i in 0..size {
-        channel.send_32byte_block(&elements);
+    for _i in 0..SIZE {
+        channel.send_32byte_block(&elements).expect("Failed to send blocks");
     }
     let duration = start.elapsed();
 
-    println!("Sent {} elements in {:?}", size, duration);
+    println!("Sent {} elements in {:?}", SIZE, duration);
 }
 
 fn main() {
@@ -3,11 +3,10 @@ extern crate lambdaworks_math;
 extern crate rand;
 
 use vole_rust::socket_channel::TcpChannel;
-use vole_rust::vole_triple::{FP_DEFAULT, VoleTriple, PHUOC_LPN};
+use vole_rust::vole_triple::{VoleTriple, PHUOC_LPN};
 use std::net::TcpStream;
 use lambdaworks_math::field::fields::fft_friendly::stark_252_prime_field::Stark252PrimeField;
 use lambdaworks_math::field::element::FieldElement;
-use lambdaworks_math::field::traits::IsPrimeField;
 use lambdaworks_math::unsigned_integer::element::UnsignedInteger;
 use rand::random;
 
@@ -24,15 +23,15 @@ fn main() {
     let stream = TcpStream::connect("127.0.0.1:8080").expect("Failed to connect to receiver");
     let mut channel = TcpChannel::new(stream);
 
-    let mut vole = VoleTriple::new(0, false, &mut channel, PHUOC_LPN);
+    let mut vole = VoleTriple::new(0, false, &mut channel, PHUOC_LPN).expect("Failed to create VOLE");
 
     let delta = rand_field_element();
-    vole.setup_sender(&mut channel, delta);
+    vole.setup_sender(&mut channel, delta).expect("Failed to run VOLE setup");
 
     vole.extend_initialization();
 
-    const size: usize = 1000;
-    let mut y = [FE::zero(); size];
-    let mut z = [FE::zero(); size];
-    vole.extend(&mut channel, &mut y, &mut z, size);
+    const SIZE: usize = 1000;
+    let mut y = [FE::zero(); SIZE];
+    let mut z = [FE::zero(); SIZE];
+    vole.extend(&mut channel, &mut y, &mut z, SIZE).expect("Failed to extend VOLE");
 }
\ No newline at end of file
@@ -0,0 +1,36 @@
+extern crate vole_rust;
+extern crate lambdaworks_math;
+
+use vole_rust::socket_channel::TcpChannel;
+use vole_rust::vole_triple::{VoleTriple, FP_DEFAULT};
+use vole_rust::fp61::Fp61;
+use vole_rust::prg::PRG;
+use std::net::TcpStream;
+use std::time::Instant;
+
+fn main() {
+    // Connect to the receiver
+    let stream = TcpStream::connect("127.0.0.1:8080").expect("Failed to connect to receiver");
+    let mut channel = TcpChannel::new(stream);
+
+    let mut vole = VoleTriple::new(0, false, &mut channel, FP_DEFAULT).expect("Failed to create VOLE");
+
+    let mut prg = PRG::new(None, 0);
+    let mut delta = [Fp61::zero(); 1];
+    prg.random_field_elements(&mut delta);
+
+    let start = Instant::now();
+    vole.setup_sender(&mut channel, delta[0]).expect("Failed to run VOLE setup");
+    println!("Time taken for setup: {:?}", start.elapsed());
+
+    vole.extend_initialization();
+
+    const SIZE: usize = 1000;
+    let mut y = vec![Fp61::zero(); SIZE];
+    let mut z = vec![Fp61::zero(); SIZE];
+    let start = Instant::now();
+    vole.extend(&mut channel, &mut y, &mut z, SIZE).expect("Failed to extend VOLE");
+    println!("Time taken for one extend: {:?}", start.elapsed());
+
+    vole.check_triple(&mut channel, delta[0], &y, &z, SIZE).expect("Failed to check triples");
+}
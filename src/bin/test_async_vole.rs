@@ -0,0 +1,112 @@
+// Runs VoleTriple over AsyncTcpChannel inside tokio tasks, then an async sender against a blocking TcpChannel receiver
+
+extern crate vole_rust;
+extern crate tokio;
+
+use vole_rust::async_comm_channel::AsyncCommunicationChannel;
+use vole_rust::async_socket_channel::AsyncTcpChannel;
+use vole_rust::socket_channel::TcpChannel;
+use vole_rust::vole_triple::{VoleTriple, PrimalLPNParameterFp61};
+use vole_rust::fp61::{Fp61, Mersenne61Field};
+use vole_rust::prg::PRG;
+use vole_rust::framing::{Framer, MessageKind, MAX_FRAME_LEN};
+use vole_rust::error::{Result, VoleError};
+use tokio::io::AsyncWriteExt;
+use tokio::net::{TcpListener, TcpStream};
+use std::thread;
+use std::time::Instant;
+
+const SIZE: usize = 1000;
+
+async fn run_sender(stream: TcpStream) -> Result<()> {
+    let mut io = AsyncTcpChannel::new(stream);
+    let mut prg = PRG::new(None, 0);
+    let mut delta = [Fp61::zero(); 1];
+    prg.random_field_elements(&mut delta);
+
+    let mut vole = VoleTriple::<Mersenne61Field>::new_async(0, true, &mut io, PrimalLPNParameterFp61::small()).await?;
+    vole.setup_sender_async(&mut io, delta[0]).await?;
+    vole.extend_initialization();
+
+    let mut y = vec![Fp61::zero(); SIZE];
+    let mut z = vec![Fp61::zero(); SIZE];
+    vole.extend_async(&mut io, &mut y, &mut z, SIZE).await?;
+    vole.check_triple_async(&mut io, delta[0], &y, &z, SIZE).await
+}
+
+async fn run_receiver(stream: TcpStream) -> Result<()> {
+    let mut io = AsyncTcpChannel::new(stream);
+    let mut vole = VoleTriple::<Mersenne61Field>::new_async(1, true, &mut io, PrimalLPNParameterFp61::small()).await?;
+    vole.setup_receiver_async(&mut io).await?;
+    vole.extend_initialization();
+
+    let mut y = vec![Fp61::zero(); SIZE];
+    let mut z = vec![Fp61::zero(); SIZE];
+    vole.extend_async(&mut io, &mut y, &mut z, SIZE).await?;
+    vole.check_triple_async(&mut io, Fp61::zero(), &y, &z, SIZE).await
+}
+
+async fn test_async_pair() {
+    let listener = TcpListener::bind("127.0.0.1:0").await.expect("Failed to bind");
+    let addr = listener.local_addr().unwrap();
+
+    // Both parties are tasks on the multi-threaded runtime, each holds a worker while it computes
+    let sender = tokio::spawn(async move {
+        let (stream, _) = listener.accept().await.expect("Failed to accept");
+        run_sender(stream).await
+    });
+    let receiver = tokio::spawn(async move {
+        let stream = TcpStream::connect(addr).await.expect("Failed to connect");
+        run_receiver(stream).await
+    });
+
+    sender.await.unwrap().expect("Async sender failed");
+    receiver.await.unwrap().expect("Async receiver failed");
+    println!("Async pair ok");
+}
+
+async fn test_mixed_pair() {
+    let listener = TcpListener::bind("127.0.0.1:0").await.expect("Failed to bind");
+    let addr = listener.local_addr().unwrap();
+
+    // The two channels share a wire format, so a blocking peer can talk to an async one
+    let receiver = thread::spawn(move || {
+        let mut io = TcpChannel::new(std::net::TcpStream::connect(addr).expect("Failed to connect"));
+        let mut vole = VoleTriple::<Mersenne61Field>::new(1, true, &mut io, PrimalLPNParameterFp61::small())?;
+        vole.setup_receiver(&mut io)?;
+        vole.extend_initialization();
+
+        let mut y = vec![Fp61::zero(); SIZE];
+        let mut z = vec![Fp61::zero(); SIZE];
+        vole.extend(&mut io, &mut y, &mut z, SIZE)?;
+        vole.check_triple(&mut io, Fp61::zero(), &y, &z, SIZE)
+    });
+
+    let (stream, _) = listener.accept().await.expect("Failed to accept");
+    run_sender(stream).await.expect("Async sender failed");
+    receiver.join().unwrap().expect("Blocking receiver failed");
+    println!("Mixed pair ok");
+}
+
+// A header announcing more than MAX_FRAME_LEN bytes is refused before anything is allocated
+async fn test_oversized_frame() {
+    let listener = TcpListener::bind("127.0.0.1:0").await.expect("Failed to bind");
+    let mut raw = TcpStream::connect(listener.local_addr().unwrap()).await.expect("Failed to connect");
+    let (stream, _) = listener.accept().await.expect("Failed to accept");
+    let mut io = AsyncTcpChannel::new(stream);
+
+    raw.write_all(&Framer::default().header(MessageKind::Data, MAX_FRAME_LEN + 1)).await.expect("Failed to send");
+    match io.receive_data().await {
+        Err(VoleError::MalformedMessage(_)) => println!("Oversized frame ok"),
+        other => panic!("Expected a malformed message error, got {:?}", other.map(|data| data.len())),
+    }
+}
+
+#[tokio::main]
+async fn main() {
+    let start = Instant::now();
+    test_async_pair().await;
+    test_mixed_pair().await;
+    test_oversized_frame().await;
+    println!("All tests passed in {:?}", start.elapsed());
+}
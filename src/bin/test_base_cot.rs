@@ -0,0 +1,57 @@
+// Runs BaseCot with 128-bit and 256-bit blocks in two threads over a MemChannel
+// Checks the correlation with check_cot and that the 128-bit mode sends half the bytes
+// Usage: test_base_cot
+
+extern crate vole_rust;
+
+use vole_rust::base_cot::BaseCot;
+use vole_rust::counting_channel::{CommStats, CountingChannel};
+use vole_rust::mem_channel::MemChannel;
+use std::thread;
+
+const LENGTH: usize = 10000;
+
+// Returns the receiver's traffic for setup and extension
+fn run<const N: usize>(malicious: bool) -> CommStats {
+    let (alice, bob) = MemChannel::pair();
+
+    let sender = thread::spawn(move || {
+        let mut io = CountingChannel::new(alice);
+        let mut cot = BaseCot::<N>::new(0, malicious);
+        cot.cot_gen_pre(&mut io, None).expect("Failed to set up base COT");
+        let delta = cot.delta().expect("The sender has no delta");
+        assert_eq!(delta[0] & 1, 1, "The LSB of delta must be set");
+
+        let mut data = vec![[0u8; N]; LENGTH];
+        cot.cot_gen(&mut io, &mut data, LENGTH, None).expect("Failed to send COTs");
+        cot.check_cot(&mut io, &data, LENGTH).expect("Failed to send the COT check");
+    });
+
+    let mut io = CountingChannel::new(bob);
+    let mut cot = BaseCot::<N>::new(1, malicious);
+    cot.cot_gen_pre(&mut io, None).expect("Failed to set up base COT");
+    let mut data = vec![[0u8; N]; LENGTH];
+    cot.cot_gen(&mut io, &mut data, LENGTH, None).expect("Failed to receive COTs");
+    let stats = io.snapshot();
+    assert!(cot.check_cot(&mut io, &data, LENGTH).expect("Failed to check COTs"), "{}-bit COTs are not correlated", 8 * N);
+    sender.join().unwrap();
+
+    println!(
+        "{} {}-bit base COT ok, {} bytes sent by the receiver",
+        if malicious { "Malicious" } else { "Semi-honest" }, 8 * N, stats.bytes_sent
+    );
+    stats
+}
+
+fn main() {
+    for &malicious in [false, true].iter() {
+        let narrow = run::<16>(malicious);
+        let wide = run::<32>(malicious);
+        // The matrix sent by the receiver dominates, it has one row per base OT
+        assert!(
+            2 * narrow.bytes_sent <= wide.bytes_sent + 4096,
+            "128-bit mode sent {} bytes against {} for 256-bit", narrow.bytes_sent, wide.bytes_sent
+        );
+    }
+    println!("All tests passed");
+}
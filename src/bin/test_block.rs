@@ -52,7 +52,7 @@ fn bench_xor() {
 
     let start = Instant::now();
     for i in 0..num_blocks {
-        let c = xor_block(&a[i], &b[i]);
+        let _c = xor_block(&a[i], &b[i]);
     }
     println!("Time taken to xor blocks: {:?}", start.elapsed());
 
@@ -73,7 +73,7 @@ fn bench_xor() {
 
     let start = Instant::now();
     for i in 0..num_blocks {
-        let c = xor_u128(&a_u128[i], &b_u128[i]);
+        let _c = xor_u128(&a_u128[i], &b_u128[i]);
     }
     println!("Time taken to xor u128: {:?}", start.elapsed());
 
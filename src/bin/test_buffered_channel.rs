@@ -0,0 +1,167 @@
+// Runs OT, base sVOLE, VoleTriple and Ferret over a BufferedTcpChannel on localhost
+// The buffers are larger than any round, so a missing flush shows up as a read timeout
+// Also checks that a receive of the wrong message kind or session is reported as a desync
+
+extern crate vole_rust;
+
+use vole_rust::socket_channel::BufferedTcpChannel;
+use vole_rust::comm_channel::CommunicationChannel;
+use vole_rust::ot::OTCO;
+use vole_rust::base_svole::BaseSvole;
+use vole_rust::vole_triple::{VoleTriple, PrimalLPNParameterFp61};
+use vole_rust::ferret_cot::{FerretCot, PrimalLPNParameterF2};
+use vole_rust::fp61::{Fp61, Mersenne61Field};
+use vole_rust::prg::PRG;
+use vole_rust::error::VoleError;
+use std::net::{TcpListener, TcpStream};
+use std::thread;
+use std::time::{Duration, Instant};
+
+fn ferret_param() -> PrimalLPNParameterF2 {
+    PrimalLPNParameterF2::with_params(16384, 64, 2000, 8, 4096, 32, 1000, 7)
+        .expect("Invalid Ferret parameters")
+}
+
+fn channel_pair() -> (BufferedTcpChannel, BufferedTcpChannel) {
+    let listener = TcpListener::bind("127.0.0.1:0").expect("Failed to bind");
+    let client = TcpStream::connect(listener.local_addr().unwrap()).expect("Failed to connect");
+    let (server, _) = listener.accept().expect("Failed to accept");
+    for stream in [&client, &server] {
+        stream.set_read_timeout(Some(Duration::from_secs(30))).unwrap();
+        stream.set_nodelay(true).unwrap();
+    }
+    (
+        BufferedTcpChannel::buffered_tcp(server, 1 << 24).expect("Failed to create channel"),
+        BufferedTcpChannel::buffered_tcp(client, 1 << 24).expect("Failed to create channel"),
+    )
+}
+
+fn test_ot() {
+    let (mut alice, mut bob) = channel_pair();
+    let data0 = vec![[0u8; 16]; 4];
+    let data1 = vec![[1u8; 16]; 4];
+    let choices = vec![false, true, true, false];
+
+    let sender = thread::spawn(move || {
+        let mut otco = OTCO::new();
+        otco.send(&mut alice, &data0, &data1).expect("Failed to send OT");
+    });
+
+    let mut output = Vec::new();
+    let mut otco = OTCO::new();
+    otco.recv(&mut bob, &choices, &mut output).expect("Failed to receive OT");
+    sender.join().unwrap();
+
+    for (i, &b) in choices.iter().enumerate() {
+        assert_eq!(output[i], [b as u8; 16], "OT output mismatch at index {}", i);
+    }
+    println!("OT ok");
+}
+
+fn test_base_svole() {
+    let (mut alice, mut bob) = channel_pair();
+    const SIZE: usize = 1000;
+
+    let sender = thread::spawn(move || {
+        let mut prg = PRG::new(None, 0);
+        let mut delta = [Fp61::zero(); 1];
+        prg.random_field_elements(&mut delta);
+        let mut svole = BaseSvole::<Mersenne61Field>::new_sender(&mut alice, delta[0]).expect("Failed to set up base sVOLE");
+        let mut key = vec![Fp61::zero(); SIZE];
+        svole.triple_gen_send(&mut alice, &mut key, SIZE).expect("Failed to generate base sVOLE");
+    });
+
+    let mut svole = BaseSvole::<Mersenne61Field>::new_receiver(&mut bob).expect("Failed to set up base sVOLE");
+    let mut mac = vec![Fp61::zero(); SIZE];
+    let mut u = vec![Fp61::zero(); SIZE];
+    svole.triple_gen_recv(&mut bob, &mut mac, &mut u, SIZE).expect("Failed to generate base sVOLE");
+    sender.join().unwrap();
+    println!("Base sVOLE ok");
+}
+
+fn test_vole_triple(malicious: bool) {
+    let (mut alice, mut bob) = channel_pair();
+    const SIZE: usize = 1000;
+
+    let sender = thread::spawn(move || {
+        let mut prg = PRG::new(None, 0);
+        let mut delta = [Fp61::zero(); 1];
+        prg.random_field_elements(&mut delta);
+
+        let mut vole = VoleTriple::new(0, malicious, &mut alice, PrimalLPNParameterFp61::small()).expect("Failed to create VOLE");
+        vole.setup_sender(&mut alice, delta[0]).expect("Failed to run VOLE setup");
+        vole.extend_initialization();
+
+        let mut y = vec![Fp61::zero(); SIZE];
+        let mut z = vec![Fp61::zero(); SIZE];
+        vole.extend(&mut alice, &mut y, &mut z, SIZE).expect("Failed to extend VOLE");
+        vole.check_triple(&mut alice, delta[0], &y, &z, SIZE).expect("Failed to check triples");
+    });
+
+    let mut vole = VoleTriple::new(1, malicious, &mut bob, PrimalLPNParameterFp61::small()).expect("Failed to create VOLE");
+    vole.setup_receiver(&mut bob).expect("Failed to run VOLE setup");
+    vole.extend_initialization();
+
+    let mut y = vec![Fp61::zero(); SIZE];
+    let mut z = vec![Fp61::zero(); SIZE];
+    vole.extend(&mut bob, &mut y, &mut z, SIZE).expect("Failed to extend VOLE");
+    vole.check_triple(&mut bob, Fp61::zero(), &y, &z, SIZE).expect("Failed to check triples");
+    sender.join().unwrap();
+    println!("VoleTriple (malicious: {}) ok", malicious);
+}
+
+fn test_ferret() {
+    let (mut alice, mut bob) = channel_pair();
+    const SIZE: usize = 40000;
+
+    let sender = thread::spawn(move || {
+        let mut ferret = FerretCot::new(0, &mut alice, ferret_param()).expect("Failed to create Ferret COT");
+        ferret.setup(&mut alice).expect("Failed to run Ferret setup");
+        let mut data = vec![[0u8; 16]; SIZE];
+        ferret.extend(&mut alice, &mut data, SIZE).expect("Failed to extend COTs");
+        ferret.check_cot(&mut alice, &data, SIZE).expect("Failed to check COTs");
+    });
+
+    let mut ferret = FerretCot::new(1, &mut bob, ferret_param()).expect("Failed to create Ferret COT");
+    ferret.setup(&mut bob).expect("Failed to run Ferret setup");
+    let mut data = vec![[0u8; 16]; SIZE];
+    ferret.extend(&mut bob, &mut data, SIZE).expect("Failed to extend COTs");
+    ferret.check_cot(&mut bob, &data, SIZE).expect("Ferret COT check failed");
+    sender.join().unwrap();
+    println!("Ferret ok");
+}
+
+fn test_desync() {
+    let (alice, bob) = channel_pair();
+    let mut alice = alice.with_session(1);
+    let mut bob = bob.with_session(1);
+
+    alice.send_32byte_block(&[[1u8; 32]; 2]).expect("Failed to send");
+    alice.flush().expect("Failed to flush");
+    match bob.receive_data() {
+        Err(VoleError::Desync(_)) => {}
+        other => panic!("Expected a desync error, got {:?}", other),
+    }
+
+    // A peer of another session is caught at its first frame
+    let (alice, bob) = channel_pair();
+    let mut alice = alice.with_session(1);
+    let mut bob = bob.with_session(2);
+    alice.send_data(&[[1u8; 16]]).expect("Failed to send");
+    alice.flush().expect("Failed to flush");
+    match bob.receive_data() {
+        Err(VoleError::Desync(_)) => println!("Desync ok"),
+        other => panic!("Expected a desync error, got {:?}", other),
+    }
+}
+
+fn main() {
+    let start = Instant::now();
+    test_ot();
+    test_base_svole();
+    test_vole_triple(false);
+    test_vole_triple(true);
+    test_ferret();
+    test_desync();
+    println!("All tests passed in {:?}", start.elapsed());
+}
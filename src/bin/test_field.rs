@@ -8,7 +8,6 @@ extern crate rand;
 use lambdaworks_math::field::fields::fft_friendly::stark_252_prime_field::Stark252PrimeField;
 use lambdaworks_math::field::element::FieldElement;
 use lambdaworks_math::unsigned_integer::element::UnsignedInteger;
-use lambdaworks_math::traits::ByteConversion;
 use rand::random;
 use std::time::Instant;
 
@@ -21,56 +20,56 @@ pub fn rand_field_element() -> FE {
 }
 
 fn main() {
-    const size: usize = 100000;
-    let mut x = [FE::zero(); size];
-    for i in 0..size {
-        x[i] = rand_field_element();
+    const SIZE: usize = 100000;
+    let mut x = [FE::zero(); SIZE];
+    for e in x.iter_mut() {
+        *e = rand_field_element();
     }
 
 
-    let mut y = [[0u8; 32]; size];
+    let mut y = [[0u8; 32]; SIZE];
 
     let start = Instant::now();
 
-    for i in 0..size {
-        y[i] = x[i].to_bytes_le();
+    for (bytes, e) in y.iter_mut().zip(x.iter()) {
+        *bytes = e.to_bytes_le();
     }
 
     let duration = start.elapsed();
-    println!("Time taken for {} iterations: {:?}", size, duration);
+    println!("Time taken for {} iterations: {:?}", SIZE, duration);
 
     let mut u = FE::zero();
     let start = Instant::now();
 
-    for i in 0..size {
-        u += x[i];
+    for e in x.iter() {
+        u += *e;
     }
 
     let duration = start.elapsed();
-    println!("Time taken for {} iterations: {:?}", size, duration);
+    println!("Time taken for {} iterations: {:?}", SIZE, duration);
 
     let mut v = FE::one();
     let start = Instant::now();
 
-    for i in 0..size {
-        v *= x[i];
+    for e in x.iter() {
+        v *= *e;
     }
 
     let duration = start.elapsed();
-    println!("Time taken for {} iterations: {:?}", size, duration);
+    println!("Time taken for {} iterations: {:?}", SIZE, duration);
 
     let mut mem = [[FE::zero(); 16]; 600];
-    for i in 0..600 {
-        for j in 0..16 {
-            mem[i][j] = rand_field_element();
+    for row in mem.iter_mut() {
+        for e in row.iter_mut() {
+            *e = rand_field_element();
         }
     }
 
     let mut dest = [FE::zero(); 16];
     let start = Instant::now();
 
-    for i in 0..600 {
-        dest.copy_from_slice(&mem[i]);
+    for row in mem.iter() {
+        dest.copy_from_slice(row);
     }
 
     let duration = start.elapsed();
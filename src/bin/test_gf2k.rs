@@ -0,0 +1,95 @@
+// Checks GF(2^128) and GF(2^256) arithmetic: PCLMULQDQ against the bit loop, and field laws after reduction
+// Then times the IKNP check inner product both ways
+// Usage: test_gf2k
+
+extern crate vole_rust;
+extern crate rand;
+
+use vole_rust::gf2k::{clmul64, clmul64_portable, inner_product_no_red, inner_product_portable, reduce, gf_mul};
+use rand::Rng;
+use std::time::Instant;
+
+fn random_element<const N: usize>(rng: &mut impl Rng) -> [u8; N] {
+    let mut x = [0u8; N];
+    rng.fill(&mut x[..]);
+    x
+}
+
+fn xor<const N: usize>(a: &[u8; N], b: &[u8; N]) -> [u8; N] {
+    let mut out = *a;
+    for (x, y) in out.iter_mut().zip(b.iter()) {
+        *x ^= y;
+    }
+    out
+}
+
+fn test_clmul() {
+    let mut rng = rand::thread_rng();
+    for _ in 0..10000 {
+        let (a, b): (u64, u64) = (rng.gen(), rng.gen());
+        assert_eq!(clmul64(a, b), clmul64_portable(a, b), "clmul64({:#x}, {:#x})", a, b);
+    }
+    assert_eq!(clmul64(u64::MAX, u64::MAX), clmul64_portable(u64::MAX, u64::MAX));
+    println!("clmul64 ok");
+}
+
+fn test_inner_product<const N: usize>() {
+    let mut rng = rand::thread_rng();
+    let a: Vec<[u8; N]> = (0..100).map(|_| random_element(&mut rng)).collect();
+    let b: Vec<[u8; N]> = (0..100).map(|_| random_element(&mut rng)).collect();
+    assert_eq!(inner_product_no_red(&a, &b), inner_product_portable(&a, &b), "Inner products differ");
+    println!("GF(2^{}) inner product ok", 8 * N);
+}
+
+// `folded` is x^8N reduced, i.e. the reduction polynomial without its top term
+fn test_field_laws<const N: usize>(folded: [u8; N]) {
+    let mut rng = rand::thread_rng();
+    let mut one = [0u8; N];
+    one[0] = 1;
+    let mut top = [0u8; N];
+    top[N - 1] = 0x80;
+    let mut x = [0u8; N];
+    x[0] = 2;
+    assert_eq!(gf_mul(&top, &x), folded, "x^{} is not reduced", 8 * N);
+
+    for _ in 0..1000 {
+        let (a, b, c) = (random_element::<N>(&mut rng), random_element(&mut rng), random_element(&mut rng));
+        assert_eq!(reduce(&[a, [0u8; N]]), a, "Reducing a reduced element changed it");
+        assert_eq!(gf_mul(&a, &one), a, "One is not the identity");
+        assert_eq!(gf_mul(&a, &b), gf_mul(&b, &a), "Multiplication is not commutative");
+        assert_eq!(gf_mul(&gf_mul(&a, &b), &c), gf_mul(&a, &gf_mul(&b, &c)), "Multiplication is not associative");
+        assert_eq!(gf_mul(&a, &xor(&b, &c)), xor(&gf_mul(&a, &b), &gf_mul(&a, &c)), "Multiplication is not distributive");
+    }
+    println!("GF(2^{}) field laws ok", 8 * N);
+}
+
+fn bench() {
+    let mut rng = rand::thread_rng();
+    let a: Vec<[u8; 32]> = (0..1 << 16).map(|_| random_element(&mut rng)).collect();
+    let b: Vec<[u8; 32]> = (0..1 << 16).map(|_| random_element(&mut rng)).collect();
+
+    let start = Instant::now();
+    let fast = inner_product_no_red(&a, &b);
+    println!("inner_product_no_red: {} products in {:?}", a.len(), start.elapsed());
+    let start = Instant::now();
+    let slow = inner_product_portable(&a, &b);
+    println!("inner_product_portable: {} products in {:?}", a.len(), start.elapsed());
+    assert_eq!(fast, slow);
+}
+
+fn main() {
+    test_clmul();
+    test_inner_product::<16>();
+    test_inner_product::<32>();
+    // x^128 = x^7 + x^2 + x + 1
+    let mut folded128 = [0u8; 16];
+    folded128[0] = 0b1000_0111;
+    test_field_laws(folded128);
+    // x^256 = x^10 + x^5 + x^2 + 1
+    let mut folded256 = [0u8; 32];
+    folded256[0] = 0b0010_0101;
+    folded256[1] = 0b0000_0100;
+    test_field_laws(folded256);
+    bench();
+    println!("All tests passed");
+}
@@ -0,0 +1,159 @@
+// Runs malicious MPFSS over an in-memory channel with each GGM expansion, for a wide field and small ones
+// Then checks that a sender and a receiver expanding differently are caught, by the MPFSS check
+// and by the VoleTriple handshake, and that VoleTriple runs on the expansion it is built with
+// Usage: test_ggm_expansion
+
+extern crate vole_rust;
+extern crate lambdaworks_math;
+
+use vole_rust::mem_channel::MemChannel;
+use vole_rust::base_cot::BaseCot;
+use vole_rust::base_svole::BaseSvole;
+use vole_rust::preot::OTPre;
+use vole_rust::mpfss_reg::MpfssReg;
+use vole_rust::two_key_prp::GgmExpansion;
+use vole_rust::field::{VoleSubfield, DefaultField};
+use vole_rust::fp61::{Fp61, Mersenne61Field};
+use lambdaworks_math::field::fields::u64_goldilocks_field::Goldilocks64Field;
+use lambdaworks_math::field::fields::mersenne31::field::Mersenne31Field;
+use vole_rust::iknp::IKNP;
+use vole_rust::vole_triple::{PrimalLPNParameterFp61, VoleTriple};
+use vole_rust::prg::PRG;
+use vole_rust::error::{Result, VoleError};
+use lambdaworks_math::field::element::FieldElement;
+use std::thread;
+use std::time::Instant;
+
+const LOG_BIN_SZ: usize = 10;
+const T: usize = 50;
+const N: usize = T * (1 << LOG_BIN_SZ);
+
+// The sender's y, the receiver's y and z, and Δ
+type MpfssOutput<F> = (Vec<FieldElement<F>>, Vec<FieldElement<F>>, Vec<FieldElement<F>>, FieldElement<F>);
+
+fn run<F: VoleSubfield<F>>(sender_expansion: GgmExpansion, receiver_expansion: GgmExpansion) -> Result<MpfssOutput<F>> {
+    let (mut alice, mut bob) = MemChannel::pair();
+    let mut delta = [FieldElement::<F>::zero(); 1];
+    PRG::new(None, 0).random_field_elements(&mut delta);
+    let delta = delta[0];
+
+    let sender = thread::spawn(move || -> Result<Vec<FieldElement<F>>> {
+        let mut cot = BaseCot::new(0, true);
+        cot.cot_gen_pre(&mut alice, None)?;
+        let mut pre_ot = OTPre::new(LOG_BIN_SZ, T);
+        cot.cot_gen_preot(&mut alice, &mut pre_ot, LOG_BIN_SZ * T, None)?;
+
+        let mut key = vec![FieldElement::zero(); T + 1];
+        let mut svole = BaseSvole::<F>::new_sender(&mut alice, delta)?;
+        svole.triple_gen_send(&mut alice, &mut key, T + 1)?;
+
+        let mut y = vec![FieldElement::zero(); N];
+        let mut mpfss = MpfssReg::<F>::new(N, T, LOG_BIN_SZ, 0);
+        mpfss.set_malicious();
+        mpfss.set_ggm_expansion(sender_expansion);
+        mpfss.sender_init(delta);
+        mpfss.mpfss_sender(&mut alice, &mut pre_ot, &key, &mut y)?;
+        Ok(y)
+    });
+
+    let mut cot = BaseCot::new(1, true);
+    cot.cot_gen_pre(&mut bob, None)?;
+    let mut pre_ot = OTPre::new(LOG_BIN_SZ, T);
+    cot.cot_gen_preot(&mut bob, &mut pre_ot, LOG_BIN_SZ * T, None)?;
+
+    let mut mac = vec![FieldElement::zero(); T + 1];
+    let mut u = vec![FieldElement::zero(); T + 1];
+    let mut svole = BaseSvole::<F>::new_receiver(&mut bob)?;
+    svole.triple_gen_recv(&mut bob, &mut mac, &mut u, T + 1)?;
+
+    let mut y = vec![FieldElement::zero(); N];
+    let mut z = vec![FieldElement::zero(); N];
+    let mut mpfss = MpfssReg::<F>::new(N, T, LOG_BIN_SZ, 1);
+    mpfss.set_malicious();
+    mpfss.set_ggm_expansion(receiver_expansion);
+    mpfss.receiver_init();
+    let received = mpfss.mpfss_receiver(&mut bob, &mut pre_ot, &mac, &u, &mut y, &mut z);
+    // The receiver hangs up on a failed check, the sender's error is not the interesting one
+    drop(bob);
+    let sent = sender.join().unwrap();
+    received?;
+    Ok((sent?, y, z, delta))
+}
+
+fn test_expansion<F: VoleSubfield<F>>(expansion: GgmExpansion) {
+    let start = Instant::now();
+    let (sender_y, y, z, delta) = run::<F>(expansion, expansion).expect("MPFSS failed");
+    let elapsed = start.elapsed();
+
+    let mut points = 0;
+    for i in 0..N {
+        assert_eq!(y[i], sender_y[i] + delta * z[i], "{:?} MPFSS over {} broke the correlation at {}", expansion, F::NAME, i);
+        if z[i] != FieldElement::zero() {
+            points += 1;
+        }
+    }
+    assert_eq!(points, T, "{:?} MPFSS over {} has {} noise points", expansion, F::NAME, points);
+    println!("{:?} over {} ok in {:?}", expansion, F::NAME, elapsed);
+}
+
+fn test_mismatch() {
+    match run::<Mersenne61Field>(GgmExpansion::FixedKey, GgmExpansion::ReKeyed) {
+        Err(VoleError::ConsistencyCheck(_)) => println!("Mismatched expansions ok"),
+        Err(e) => panic!("Mismatched expansions gave {}", e),
+        Ok(_) => panic!("Mismatched expansions went unnoticed"),
+    }
+}
+
+// Builds both VoleTriples on their expansions, then extends and checks the triples if that worked
+fn run_vole(sender_expansion: GgmExpansion, receiver_expansion: GgmExpansion, size: usize) -> Result<()> {
+    let (mut alice, mut bob) = MemChannel::pair();
+    let delta = Fp61::from(7);
+
+    let sender = thread::spawn(move || -> Result<()> {
+        let mut vole = VoleTriple::<Mersenne61Field>::with_expansion(0, &mut alice, PrimalLPNParameterFp61::small(), IKNP::new(true), sender_expansion)?;
+        vole.setup_sender(&mut alice, delta)?;
+        vole.extend_initialization();
+        let mut y = vec![Fp61::zero(); size];
+        let mut z = vec![Fp61::zero(); size];
+        vole.extend(&mut alice, &mut y, &mut z, size)?;
+        vole.check_triple(&mut alice, delta, &y, &z, size)
+    });
+
+    let received = (|| -> Result<()> {
+        let mut vole = VoleTriple::<Mersenne61Field>::with_expansion(1, &mut bob, PrimalLPNParameterFp61::small(), IKNP::new(true), receiver_expansion)?;
+        vole.setup_receiver(&mut bob)?;
+        vole.extend_initialization();
+        let mut y = vec![Fp61::zero(); size];
+        let mut z = vec![Fp61::zero(); size];
+        vole.extend(&mut bob, &mut y, &mut z, size)?;
+        vole.check_triple(&mut bob, Fp61::zero(), &y, &z, size)
+    })();
+    drop(bob);
+    let sent = sender.join().unwrap();
+    received.and(sent)
+}
+
+fn test_vole_expansion() {
+    for expansion in [GgmExpansion::ReKeyed, GgmExpansion::FixedKey] {
+        run_vole(expansion, expansion, 8000).expect("VoleTriple failed");
+        println!("VoleTriple on {:?} ok", expansion);
+    }
+    match run_vole(GgmExpansion::ReKeyed, GgmExpansion::FixedKey, 8000) {
+        Err(VoleError::Handshake(msg)) => assert!(msg.contains("GGM expansion"), "Unexpected handshake error: {}", msg),
+        other => panic!("Expected a handshake error, got {:?}", other),
+    }
+    println!("VoleTriple handshake on mismatched expansions ok");
+}
+
+fn main() {
+    let start = Instant::now();
+    for expansion in [GgmExpansion::ReKeyed, GgmExpansion::FixedKey].iter() {
+        test_expansion::<DefaultField>(*expansion);
+        test_expansion::<Mersenne61Field>(*expansion);
+        test_expansion::<Goldilocks64Field>(*expansion);
+        test_expansion::<Mersenne31Field>(*expansion);
+    }
+    test_mismatch();
+    test_vole_expansion();
+    println!("All tests passed in {:?}", start.elapsed());
+}
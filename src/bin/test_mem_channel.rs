@@ -0,0 +1,345 @@
+// Runs both parties of OT, base sVOLE, VoleTriple and Ferret in two threads over a MemChannel
+// Also checks that a dropped, cheating, mismatched or out-of-step peer gives an error instead of a panic
+
+extern crate vole_rust;
+extern crate lambdaworks_math;
+extern crate p256;
+
+use vole_rust::mem_channel::MemChannel;
+use vole_rust::counting_channel::CountingChannel;
+use vole_rust::comm_channel::{CommunicationChannel, FE};
+use vole_rust::field::VoleField;
+use vole_rust::ot::OTCO;
+use vole_rust::base_svole::BaseSvole;
+use vole_rust::vole_triple::{VoleTriple, PrimalLPNParameterFp61, CommBreakdown};
+use vole_rust::ferret_cot::{FerretCot, PrimalLPNParameterF2};
+use vole_rust::fp61::{Fp61, Mersenne61Field};
+use vole_rust::prg::PRG;
+use vole_rust::error::{Result, VoleError, CheckFailure};
+use lambdaworks_math::field::element::FieldElement;
+use p256::EncodedPoint;
+use std::thread;
+use std::time::Instant;
+
+fn ferret_param() -> PrimalLPNParameterF2 {
+    PrimalLPNParameterF2::with_params(16384, 64, 2000, 8, 4096, 32, 1000, 7)
+        .expect("Invalid Ferret parameters")
+}
+
+// Adds one to the first element of every two-element message, i.e. the receiver's base sVOLE check values
+struct TamperChannel {
+    inner: MemChannel,
+}
+
+impl CommunicationChannel for TamperChannel {
+    fn send_32byte_block(&mut self, data: &[[u8; 32]]) -> Result<()> { self.inner.send_32byte_block(data) }
+    fn receive_32byte_block(&mut self) -> Result<Vec<[u8; 32]>> { self.inner.receive_32byte_block() }
+    fn send_bits(&mut self, bits: &[bool]) -> Result<()> { self.inner.send_bits(bits) }
+    fn receive_bits(&mut self) -> Result<Vec<bool>> { self.inner.receive_bits() }
+    fn send_stark252(&mut self, elements: &[FE]) -> Result<()> { self.inner.send_stark252(elements) }
+    fn receive_stark252(&mut self, count: usize) -> Result<Vec<FE>> { self.inner.receive_stark252(count) }
+    fn send_field_elements<F: VoleField>(&mut self, elements: &[FieldElement<F>]) -> Result<()> {
+        let mut elements = elements.to_vec();
+        if elements.len() == 2 {
+            elements[0] += FieldElement::<F>::one();
+        }
+        self.inner.send_field_elements(&elements)
+    }
+    fn receive_field_elements<F: VoleField>(&mut self, count: usize) -> Result<Vec<FieldElement<F>>> {
+        self.inner.receive_field_elements(count)
+    }
+    fn send_point(&mut self, point: &EncodedPoint) -> Result<()> { self.inner.send_point(point) }
+    fn receive_point(&mut self) -> Result<EncodedPoint> { self.inner.receive_point() }
+    fn send_data(&mut self, data: &[[u8; 16]]) -> Result<()> { self.inner.send_data(data) }
+    fn receive_data(&mut self) -> Result<Vec<[u8; 16]>> { self.inner.receive_data() }
+    fn flush(&mut self) -> Result<()> { self.inner.flush() }
+}
+
+fn test_ot() {
+    let (mut alice, mut bob) = MemChannel::pair();
+    let data0 = vec![[0u8; 16]; 4];
+    let data1 = vec![[1u8; 16]; 4];
+    let choices = vec![false, true, true, false];
+
+    let sender = thread::spawn(move || {
+        let mut otco = OTCO::new();
+        otco.send(&mut alice, &data0, &data1).expect("Failed to send OT");
+    });
+
+    let mut output = Vec::new();
+    let mut otco = OTCO::new();
+    otco.recv(&mut bob, &choices, &mut output).expect("Failed to receive OT");
+    sender.join().unwrap();
+
+    for (i, &b) in choices.iter().enumerate() {
+        assert_eq!(output[i], [b as u8; 16], "OT output mismatch at index {}", i);
+    }
+    println!("OT ok");
+}
+
+fn test_base_svole() {
+    let (mut alice, mut bob) = MemChannel::pair();
+    const SIZE: usize = 1000;
+
+    let sender = thread::spawn(move || {
+        let mut prg = PRG::new(None, 0);
+        let mut delta = [Fp61::zero(); 1];
+        prg.random_field_elements(&mut delta);
+        let mut svole = BaseSvole::<Mersenne61Field>::new_sender(&mut alice, delta[0]).expect("Failed to set up base sVOLE");
+        let mut key = vec![Fp61::zero(); SIZE];
+        svole.triple_gen_send(&mut alice, &mut key, SIZE).expect("Failed to generate base sVOLE");
+    });
+
+    let mut svole = BaseSvole::<Mersenne61Field>::new_receiver(&mut bob).expect("Failed to set up base sVOLE");
+    let mut mac = vec![Fp61::zero(); SIZE];
+    let mut u = vec![Fp61::zero(); SIZE];
+    svole.triple_gen_recv(&mut bob, &mut mac, &mut u, SIZE).expect("Failed to generate base sVOLE");
+    sender.join().unwrap();
+    println!("Base sVOLE ok");
+}
+
+// What one VOLE round yields, n - (k + t + 1) of PrimalLPNParameterFp61::small()
+const OT_LIMIT: usize = 6400 - (1000 + 100 + 1);
+
+// Runs both parties through one extend per entry of `sizes`, into buffers `slack` elements
+// longer than asked, checks each batch and returns the receiver's y of each
+fn extend_batches(sizes: Vec<usize>, slack: usize) -> Vec<Vec<Fp61>> {
+    let (mut alice, mut bob) = MemChannel::pair();
+    let sender_sizes = sizes.clone();
+
+    let sender = thread::spawn(move || {
+        let mut prg = PRG::new(None, 0);
+        let mut delta = [Fp61::zero(); 1];
+        prg.random_field_elements(&mut delta);
+
+        let mut vole = VoleTriple::new(0, false, &mut alice, PrimalLPNParameterFp61::small()).expect("Failed to create VOLE");
+        vole.setup_sender(&mut alice, delta[0]).expect("Failed to run VOLE setup");
+        vole.extend_initialization();
+
+        for &size in sender_sizes.iter() {
+            let mut y = vec![Fp61::zero(); size + slack];
+            let mut z = vec![Fp61::zero(); size + slack];
+            vole.extend(&mut alice, &mut y, &mut z, size).expect("Failed to extend VOLE");
+            vole.check_triple(&mut alice, delta[0], &y, &z, size).expect("Failed to check triples");
+        }
+    });
+
+    let mut vole = VoleTriple::new(1, false, &mut bob, PrimalLPNParameterFp61::small()).expect("Failed to create VOLE");
+    vole.setup_receiver(&mut bob).expect("Failed to run VOLE setup");
+    vole.extend_initialization();
+
+    let mut batches = Vec::new();
+    for &size in sizes.iter() {
+        let mut y = vec![Fp61::zero(); size + slack];
+        let mut z = vec![Fp61::zero(); size + slack];
+        vole.extend(&mut bob, &mut y, &mut z, size).expect("Failed to extend VOLE");
+        vole.check_triple(&mut bob, Fp61::zero(), &y, &z, size).expect("Failed to check triples");
+        batches.push(y);
+    }
+    sender.join().unwrap();
+    batches
+}
+
+fn test_vole_triple() {
+    // Within one batch, over one batch, then several
+    extend_batches(vec![1000, 6000, 20000], 0);
+    println!("VoleTriple ok");
+}
+
+// Each round keeps its last m outputs as the next round's pre-VOLEs, so a round only
+// hands out OT_LIMIT of its n outputs, including when a call spans several rounds
+fn test_extend_rounds() {
+    extend_batches(vec![OT_LIMIT - 1, 2, OT_LIMIT, 3 * OT_LIMIT + 7, 2 * OT_LIMIT], 0);
+    println!("Extend over round boundaries ok");
+}
+
+// Calls served from the current round's outputs must not hand them out twice,
+// and only the first `num` entries of a longer buffer are written
+fn test_extend_from_buffer() {
+    let sizes = vec![OT_LIMIT - 300, 100, 100, 100, 500];
+    let batches = extend_batches(sizes.clone(), 3);
+    for i in 1..4 {
+        assert!(batches[i][..100] != batches[i + 1][..100], "Extend {} repeats the previous correlations", i + 1);
+    }
+    for (y, &size) in batches.iter().zip(sizes.iter()) {
+        assert!(y[size..].iter().all(|e| *e == Fp61::zero()), "Extend wrote past num");
+    }
+    println!("Extend from the buffer ok");
+}
+
+fn test_comm_stats() {
+    let (alice, bob) = MemChannel::pair();
+    let mut alice = CountingChannel::new(alice);
+    let mut bob = CountingChannel::new(bob);
+    const SIZE: usize = 1000;
+
+    let sender = thread::spawn(move || -> Result<(CommBreakdown, CommBreakdown)> {
+        let mut vole = VoleTriple::<Mersenne61Field>::new(0, true, &mut alice, PrimalLPNParameterFp61::small())?;
+        vole.setup_sender(&mut alice, Fp61::from(7))?;
+        vole.extend_initialization();
+        let mut y = vec![Fp61::zero(); SIZE];
+        let mut z = vec![Fp61::zero(); SIZE];
+        vole.extend(&mut alice, &mut y, &mut z, SIZE)?;
+        Ok((*vole.setup_comm(), *vole.extend_comm()))
+    });
+
+    let mut vole = VoleTriple::<Mersenne61Field>::new(1, true, &mut bob, PrimalLPNParameterFp61::small()).expect("Failed to create VOLE");
+    vole.setup_receiver(&mut bob).expect("Failed to run VOLE setup");
+    vole.extend_initialization();
+    let mut y = vec![Fp61::zero(); SIZE];
+    let mut z = vec![Fp61::zero(); SIZE];
+    vole.extend(&mut bob, &mut y, &mut z, SIZE).expect("Failed to extend VOLE");
+    let (sender_setup, sender_extend) = sender.join().unwrap().expect("VOLE sender failed");
+
+    // What one side sends in a phase the other receives in the same phase
+    for (s, r) in [(sender_setup, *vole.setup_comm()), (sender_extend, *vole.extend_comm())] {
+        for (a, b) in [(s.base_ot, r.base_ot), (s.iknp, r.iknp), (s.base_svole, r.base_svole), (s.mpfss, r.mpfss)] {
+            assert_eq!(a.bytes_sent, b.bytes_received);
+            assert_eq!(a.bytes_received, b.bytes_sent);
+            assert_eq!(a.messages_sent, b.messages_received);
+        }
+    }
+
+    // The extensions only run IKNP and MPFSS, and every byte lands in some phase
+    assert!(sender_setup.base_ot.total_bytes() > 0 && sender_setup.base_svole.total_bytes() > 0);
+    assert!(sender_extend.iknp.total_bytes() > 0 && sender_extend.mpfss.total_bytes() > 0);
+    assert_eq!(sender_extend.base_ot.total_bytes() + sender_extend.base_svole.total_bytes(), 0);
+    let sent = sender_setup.total().bytes_sent + sender_extend.total().bytes_sent;
+    assert_eq!(sent, bob.snapshot().bytes_received);
+
+    println!("Setup:\n{}\nExtend:\n{}", sender_setup, sender_extend);
+
+    // Swapped snapshots, or a reset between them, have no difference instead of a bogus one
+    let (channel, _peer) = MemChannel::pair();
+    let mut channel = CountingChannel::new(channel);
+    channel.send_data(&[[0u8; 16]]).expect("Failed to send");
+    let earlier = channel.snapshot();
+    channel.send_data(&[[0u8; 16]]).expect("Failed to send");
+    let later = channel.snapshot();
+    assert_eq!(later.since(&earlier).map(|d| d.messages_sent), Some(1));
+    assert_eq!(earlier.since(&later), None);
+    channel.reset();
+    assert_eq!(channel.snapshot().since(&earlier), None);
+    println!("Comm stats ok");
+}
+
+fn test_ferret() {
+    let (mut alice, mut bob) = MemChannel::pair();
+    const SIZE: usize = 40000;
+
+    let sender = thread::spawn(move || {
+        let mut ferret = FerretCot::new(0, &mut alice, ferret_param()).expect("Failed to create Ferret COT");
+        ferret.setup(&mut alice).expect("Failed to run Ferret setup");
+        let mut data = vec![[0u8; 16]; SIZE];
+        ferret.extend(&mut alice, &mut data, SIZE).expect("Failed to extend COTs");
+        ferret.check_cot(&mut alice, &data, SIZE).expect("Failed to check COTs");
+    });
+
+    let mut ferret = FerretCot::new(1, &mut bob, ferret_param()).expect("Failed to create Ferret COT");
+    ferret.setup(&mut bob).expect("Failed to run Ferret setup");
+    let mut data = vec![[0u8; 16]; SIZE];
+    ferret.extend(&mut bob, &mut data, SIZE).expect("Failed to extend COTs");
+    ferret.check_cot(&mut bob, &data, SIZE).expect("Ferret COT check failed");
+    sender.join().unwrap();
+    println!("Ferret ok");
+}
+
+fn test_dropped_peer() {
+    let (alice, mut bob) = MemChannel::pair();
+    drop(alice);
+
+    // The receiver must get an error, not a panic
+    let mut output = Vec::new();
+    let mut otco = OTCO::new();
+    match otco.recv(&mut bob, &[true, false], &mut output) {
+        Err(VoleError::Io(_)) => println!("Dropped peer ok"),
+        other => panic!("Expected an I/O error, got {:?}", other),
+    }
+}
+
+fn test_cheating_peer() {
+    let (mut alice, bob) = MemChannel::pair();
+    let mut bob = TamperChannel { inner: bob };
+
+    let receiver = thread::spawn(move || {
+        let mut vole = VoleTriple::<Mersenne61Field>::new(1, true, &mut bob, PrimalLPNParameterFp61::small())?;
+        vole.setup_receiver(&mut bob)
+    });
+
+    let mut prg = PRG::new(None, 0);
+    let mut delta = [Fp61::zero(); 1];
+    prg.random_field_elements(&mut delta);
+    let mut vole = VoleTriple::<Mersenne61Field>::new(0, true, &mut alice, PrimalLPNParameterFp61::small())
+        .expect("Failed to create VOLE");
+    match vole.setup_sender(&mut alice, delta[0]) {
+        Err(VoleError::ConsistencyCheck(CheckFailure::BaseSvole)) => {}
+        other => panic!("Expected the base sVOLE check to fail, got {:?}", other),
+    }
+
+    // No correlation is handed out after the failed check
+    vole.extend_initialization();
+    let mut y = vec![Fp61::zero(); 10];
+    let mut z = vec![Fp61::zero(); 10];
+    match vole.extend(&mut alice, &mut y, &mut z, 10) {
+        Err(VoleError::Aborted(CheckFailure::BaseSvole)) => {}
+        other => panic!("Expected the VOLE to refuse, got {:?}", other),
+    }
+
+    // The receiver only sees the sender hang up
+    drop(alice);
+    assert!(receiver.join().unwrap().is_err());
+    println!("Cheating peer ok");
+}
+
+// Each side of a mismatched pair gets a handshake error right away
+fn expect_handshake_error(
+    sender_malicious: bool, sender_param: PrimalLPNParameterFp61,
+    receiver_malicious: bool, receiver_param: PrimalLPNParameterFp61,
+) {
+    let (mut alice, mut bob) = MemChannel::pair();
+    let sender = thread::spawn(move || {
+        VoleTriple::<Mersenne61Field>::new(0, sender_malicious, &mut alice, sender_param).map(|_| ())
+    });
+    let receiver = VoleTriple::<Mersenne61Field>::new(1, receiver_malicious, &mut bob, receiver_param).map(|_| ());
+    for res in [sender.join().unwrap(), receiver] {
+        match res {
+            Err(VoleError::Handshake(_)) => {}
+            other => panic!("Expected a handshake error, got {:?}", other),
+        }
+    }
+}
+
+fn test_handshake() {
+    let other_param = PrimalLPNParameterFp61::with_params(6400, 100, 900, 6, 1280, 40, 500, 5, 1600, 100, 300, 4)
+        .expect("Invalid VOLE parameters");
+    expect_handshake_error(false, PrimalLPNParameterFp61::small(), false, other_param);
+    expect_handshake_error(true, PrimalLPNParameterFp61::small(), false, PrimalLPNParameterFp61::small());
+    println!("Handshake ok");
+}
+
+fn test_desync() {
+    let (mut alice, mut bob) = MemChannel::pair();
+
+    // Blocks read as 16-byte data would decode fine without the tag
+    alice.send_32byte_block(&[[1u8; 32]; 2]).expect("Failed to send");
+    match bob.receive_data() {
+        Err(VoleError::Desync(_)) => println!("Desync ok"),
+        other => panic!("Expected a desync error, got {:?}", other),
+    }
+}
+
+fn main() {
+    let start = Instant::now();
+    test_ot();
+    test_base_svole();
+    test_vole_triple();
+    test_extend_rounds();
+    test_extend_from_buffer();
+    test_comm_stats();
+    test_ferret();
+    test_dropped_peer();
+    test_cheating_peer();
+    test_handshake();
+    test_desync();
+    println!("All tests passed in {:?}", start.elapsed());
+}
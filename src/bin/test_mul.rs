@@ -42,25 +42,6 @@ fn clmul64(a: u64, b: u64) -> u128 {
     result
 }
 
-fn vector_inn_prdt_sum_no_red(res: &mut [[u8; 16]; 2], a: &[[u8; 16]], b: &[[u8; 16]]) {
-    // let mut r1 = [0u8; 16]; // Accumulator for first half
-    // let mut r2 = [0u8; 16]; // Accumulator for second half
-    let mut r1 = [0u8; 16];
-    let mut r2 = [0u8; 16];
-    let mut r11 = [[0u8; 16]; 2];
-
-    for i in 0..a.len() {
-        mul128(&a[i], &b[i], &mut r11); // Perform 128-bit multiplication
-        for byt in 0..16 {
-            r1[byt] = r1[byt] ^ r11[0][byt];
-            r2[byt] = r2[byt] ^ r11[1][byt];
-        }
-    }
-
-    res[0] = r1;
-    res[1] = r2;
-}
-
 fn main() {
     let mut a = vec![3u8, 0u8, 0u8, 0u8, 0u8, 0u8, 0u8, 0u8];
     a.extend(vec![0u8; 8]);
@@ -0,0 +1,88 @@
+// Runs several VoleTriple sessions at once, each on its own thread, over one TCP connection
+// Also checks that the sub-channels get an error once the peer is gone
+
+extern crate vole_rust;
+
+use vole_rust::mux_channel::MuxChannel;
+use vole_rust::comm_channel::CommunicationChannel;
+use vole_rust::vole_triple::{VoleTriple, PrimalLPNParameterFp61};
+use vole_rust::fp61::{Fp61, Mersenne61Field};
+use vole_rust::prg::PRG;
+use vole_rust::error::{Result, VoleError};
+use std::net::{TcpListener, TcpStream};
+use std::thread;
+use std::time::Instant;
+
+const SESSIONS: usize = 4;
+
+fn tcp_pair() -> (TcpStream, TcpStream) {
+    let listener = TcpListener::bind("127.0.0.1:0").expect("Failed to bind");
+    let client = TcpStream::connect(listener.local_addr().unwrap()).expect("Failed to connect");
+    let (server, _) = listener.accept().expect("Failed to accept");
+    (server, client)
+}
+
+// One session, odd ones malicious so both kinds share the connection
+fn run_session(party: usize, mut io: MuxChannel, size: usize) -> Result<()> {
+    let malicious = io.id() % 2 == 1;
+    let mut vole = VoleTriple::<Mersenne61Field>::new(party, malicious, &mut io, PrimalLPNParameterFp61::small())?;
+    let mut delta = Fp61::zero();
+    if party == 0 {
+        let mut prg = PRG::new(None, 0);
+        let mut random = [Fp61::zero(); 1];
+        prg.random_field_elements(&mut random);
+        delta = random[0];
+        vole.setup_sender(&mut io, delta)?;
+    } else {
+        vole.setup_receiver(&mut io)?;
+    }
+    vole.extend_initialization();
+
+    let mut y = vec![Fp61::zero(); size];
+    let mut z = vec![Fp61::zero(); size];
+    vole.extend(&mut io, &mut y, &mut z, size)?;
+    vole.check_triple(&mut io, delta, &y, &z, size)
+}
+
+fn run_party(party: usize, stream: TcpStream) -> Vec<thread::JoinHandle<Result<()>>> {
+    MuxChannel::split_tcp(stream, SESSIONS)
+        .expect("Failed to split the connection")
+        .into_iter()
+        .map(|io| {
+            // Different sizes, so the sessions finish at different times
+            let size = 1000 * (io.id() + 1);
+            thread::spawn(move || run_session(party, io, size))
+        })
+        .collect()
+}
+
+fn test_sessions() {
+    let (alice, bob) = tcp_pair();
+    let senders = run_party(0, alice);
+    let receivers = run_party(1, bob);
+    for handle in senders.into_iter().chain(receivers) {
+        handle.join().unwrap().expect("VOLE session failed");
+    }
+    println!("{} concurrent sessions ok", SESSIONS);
+}
+
+fn test_closed_peer() {
+    let (alice, bob) = tcp_pair();
+    drop(MuxChannel::split_tcp(alice, 2).expect("Failed to split the connection"));
+
+    let mut bob = MuxChannel::split_tcp(bob, 2).expect("Failed to split the connection");
+    for io in bob.iter_mut() {
+        match io.receive_data() {
+            Err(VoleError::Io(_)) => {}
+            other => panic!("Expected an I/O error, got {:?}", other),
+        }
+    }
+    println!("Closed peer ok");
+}
+
+fn main() {
+    let start = Instant::now();
+    test_sessions();
+    test_closed_peer();
+    println!("All tests passed in {:?}", start.elapsed());
+}
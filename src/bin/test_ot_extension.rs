@@ -0,0 +1,142 @@
+// Runs VoleTriple in two threads over a MemChannel on OT extensions other than IKNP: SoftSpokenOT,
+// and an extension defined here through the CotSender/CotReceiver traits alone.
+// Also checks that parties running different extensions fail the handshake.
+
+extern crate vole_rust;
+
+use vole_rust::async_comm_channel::AsyncCommunicationChannel;
+use vole_rust::error::{Result, VoleError};
+use vole_rust::fp61::{Fp61, Mersenne61Field};
+use vole_rust::iknp::IKNP;
+use vole_rust::mem_channel::MemChannel;
+use vole_rust::ot_extension::{CotReceiver, CotSender, OtExtension};
+use vole_rust::softspoken::SoftSpokenOT;
+use vole_rust::vole_triple::{PrimalLPNParameterFp61, VoleTriple};
+use std::thread;
+use std::time::Instant;
+
+// IKNP behind a type of this binary, standing in for an extension from outside the crate
+struct ForwardingIknp {
+    inner: IKNP,
+}
+
+impl ForwardingIknp {
+    fn new(malicious: bool) -> Self {
+        ForwardingIknp { inner: IKNP::new(malicious) }
+    }
+}
+
+impl CotSender<32> for ForwardingIknp {
+    async fn setup_sender<IO: AsyncCommunicationChannel>(&mut self, io: &mut IO, delta: &[u8; 32]) -> Result<()> {
+        self.inner.setup_sender(io, delta).await
+    }
+
+    async fn extend_send<IO: AsyncCommunicationChannel>(&mut self, io: &mut IO, data: &mut [[u8; 32]], length: usize) -> Result<()> {
+        self.inner.extend_send(io, data, length).await
+    }
+
+    async fn check_send<IO: AsyncCommunicationChannel>(&mut self, io: &mut IO, data: &[[u8; 32]], length: usize) -> Result<()> {
+        self.inner.check_send(io, data, length).await
+    }
+}
+
+impl CotReceiver<32> for ForwardingIknp {
+    async fn setup_receiver<IO: AsyncCommunicationChannel>(&mut self, io: &mut IO) -> Result<()> {
+        self.inner.setup_receiver(io).await
+    }
+
+    async fn extend_recv<IO: AsyncCommunicationChannel>(&mut self, io: &mut IO, data: &mut [[u8; 32]], r: &[bool], length: usize) -> Result<()> {
+        self.inner.extend_recv(io, data, r, length).await
+    }
+
+    async fn check_recv<IO: AsyncCommunicationChannel>(&mut self, io: &mut IO, data: &[[u8; 32]], r: &[bool], length: usize) -> Result<()> {
+        self.inner.check_recv(io, data, r, length).await
+    }
+}
+
+impl OtExtension<32> for ForwardingIknp {
+    fn name(&self) -> String {
+        "Forwarding IKNP".to_string()
+    }
+
+    fn malicious(&self) -> bool {
+        self.inner.malicious()
+    }
+}
+
+// Sets up both parties on the extensions `make` builds for each party, extends and checks the triples
+fn run_vole<E, M>(make: M, size: usize)
+where
+    E: OtExtension<32> + Send + 'static,
+    M: Fn(usize) -> E + Send + Copy + 'static,
+{
+    let (mut alice, mut bob) = MemChannel::pair();
+    let delta = Fp61::from(7);
+
+    let sender = thread::spawn(move || {
+        let mut vole = VoleTriple::<Mersenne61Field, Mersenne61Field, E>::with_extension(0, &mut alice, PrimalLPNParameterFp61::small(), make(0))
+            .expect("Failed to create VOLE");
+        vole.setup_sender(&mut alice, delta).expect("Failed to run VOLE setup");
+        vole.extend_initialization();
+        let mut y = vec![Fp61::zero(); size];
+        let mut z = vec![Fp61::zero(); size];
+        vole.extend(&mut alice, &mut y, &mut z, size).expect("Failed to extend VOLE");
+        vole.check_triple(&mut alice, delta, &y, &z, size).expect("Failed to check triples");
+    });
+
+    let mut vole = VoleTriple::<Mersenne61Field, Mersenne61Field, E>::with_extension(1, &mut bob, PrimalLPNParameterFp61::small(), make(1))
+        .expect("Failed to create VOLE");
+    vole.setup_receiver(&mut bob).expect("Failed to run VOLE setup");
+    vole.extend_initialization();
+    let mut y = vec![Fp61::zero(); size];
+    let mut z = vec![Fp61::zero(); size];
+    vole.extend(&mut bob, &mut y, &mut z, size).expect("Failed to extend VOLE");
+    vole.check_triple(&mut bob, Fp61::zero(), &y, &z, size).expect("Failed to check triples");
+    sender.join().unwrap();
+}
+
+fn test_softspoken() {
+    for malicious in [false, true] {
+        run_vole(move |_| SoftSpokenOT::new(4, malicious).expect("Invalid k"), 8000);
+        println!("VoleTriple over SoftSpokenOT, {} ok", if malicious { "malicious" } else { "semi-honest" });
+    }
+}
+
+fn test_custom_extension() {
+    run_vole(|_| ForwardingIknp::new(true), 8000);
+    println!("VoleTriple over a custom extension ok");
+}
+
+// Each side of a pair running different extensions gets a handshake error right away
+fn expect_handshake_error<S, R>(sender_extension: S, receiver_extension: R)
+where
+    S: OtExtension<32> + Send + 'static,
+    R: OtExtension<32>,
+{
+    let (mut alice, mut bob) = MemChannel::pair();
+    let sender = thread::spawn(move || {
+        VoleTriple::<Mersenne61Field, Mersenne61Field, S>::with_extension(0, &mut alice, PrimalLPNParameterFp61::small(), sender_extension).map(|_| ())
+    });
+    let receiver = VoleTriple::<Mersenne61Field, Mersenne61Field, R>::with_extension(1, &mut bob, PrimalLPNParameterFp61::small(), receiver_extension).map(|_| ());
+    for res in [sender.join().unwrap(), receiver] {
+        match res {
+            Err(VoleError::Handshake(msg)) => assert!(msg.contains("OT extension"), "Unexpected handshake error: {}", msg),
+            other => panic!("Expected a handshake error, got {:?}", other),
+        }
+    }
+}
+
+fn test_handshake() {
+    expect_handshake_error(IKNP::new(false), SoftSpokenOT::new(4, false).expect("Invalid k"));
+    expect_handshake_error(SoftSpokenOT::new(2, true).expect("Invalid k"), SoftSpokenOT::new(4, true).expect("Invalid k"));
+    expect_handshake_error(ForwardingIknp::new(true), IKNP::new(true));
+    println!("Handshake ok");
+}
+
+fn main() {
+    let start = Instant::now();
+    test_softspoken();
+    test_custom_extension();
+    test_handshake();
+    println!("All OT extension tests passed in {:?}", start.elapsed());
+}
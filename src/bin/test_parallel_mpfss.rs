@@ -0,0 +1,68 @@
+// Runs VoleTriple with the MPFSS trees on one thread and on several, from the same seeds
+// The outputs must be identical, only the time may differ
+// Usage: cargo run --release --features replay --bin test_parallel_mpfss [threads]
+
+extern crate vole_rust;
+
+use vole_rust::mem_channel::MemChannel;
+use vole_rust::vole_triple::{VoleTriple, PrimalLPNParameterFp61};
+use vole_rust::fp61::{Fp61, Mersenne61Field};
+use vole_rust::prg::set_thread_seed;
+use vole_rust::parallel::default_threads;
+use std::env;
+use std::thread;
+use std::time::{Duration, Instant};
+
+const SIZE: usize = 20000;
+
+// Trees large enough for the threads to matter
+fn vole_param() -> PrimalLPNParameterFp61 {
+    PrimalLPNParameterFp61::with_params(65536, 256, 2000, 8, 12800, 100, 1000, 7, 2000, 125, 400, 4)
+        .expect("Invalid VOLE parameters")
+}
+
+// Returns the receiver's y and z, the sender's y, and the time both took to extend
+fn run(threads: usize) -> (Vec<Fp61>, Vec<Fp61>, Vec<Fp61>, Duration) {
+    let (mut alice, mut bob) = MemChannel::pair();
+    let delta = Fp61::from(123456789);
+
+    let sender = thread::spawn(move || {
+        set_thread_seed(Some([1u8; 32]));
+        let mut vole = VoleTriple::<Mersenne61Field>::new(0, true, &mut alice, vole_param()).expect("Failed to create VOLE");
+        vole.set_threads(threads);
+        vole.setup_sender(&mut alice, delta).expect("Failed to run VOLE setup");
+        vole.extend_initialization();
+        let mut y = vec![Fp61::zero(); SIZE];
+        let mut z = vec![Fp61::zero(); SIZE];
+        vole.extend(&mut alice, &mut y, &mut z, SIZE).expect("Failed to extend VOLE");
+        vole.check_triple(&mut alice, delta, &y, &z, SIZE).expect("Failed to check triples");
+        y
+    });
+
+    set_thread_seed(Some([2u8; 32]));
+    let mut vole = VoleTriple::<Mersenne61Field>::new(1, true, &mut bob, vole_param()).expect("Failed to create VOLE");
+    vole.set_threads(threads);
+    vole.setup_receiver(&mut bob).expect("Failed to run VOLE setup");
+    vole.extend_initialization();
+    let mut y = vec![Fp61::zero(); SIZE];
+    let mut z = vec![Fp61::zero(); SIZE];
+    let start = Instant::now();
+    vole.extend(&mut bob, &mut y, &mut z, SIZE).expect("Failed to extend VOLE");
+    let elapsed = start.elapsed();
+    vole.check_triple(&mut bob, Fp61::zero(), &y, &z, SIZE).expect("Failed to check triples");
+    set_thread_seed(None);
+
+    (y, z, sender.join().unwrap(), elapsed)
+}
+
+fn main() {
+    let threads: usize = env::args().nth(1).and_then(|s| s.parse().ok()).unwrap_or(default_threads().max(4));
+
+    let (y1, z1, sender_y1, time1) = run(1);
+    let (yn, zn, sender_yn, timen) = run(threads);
+    assert!(y1 == yn && z1 == zn, "Receiver outputs depend on the number of threads");
+    assert!(sender_y1 == sender_yn, "Sender outputs depend on the number of threads");
+
+    println!("Extend of {} VOLEs: {:?} on 1 thread, {:?} on {} threads", SIZE, time1, timen, threads);
+    println!("All tests passed");
+}
@@ -0,0 +1,155 @@
+// Runs a malicious VoleTriple through a SecureChannel over TCP on localhost
+// Also checks that a wrong long-term key or a modified ciphertext is caught
+
+extern crate vole_rust;
+extern crate p256;
+extern crate rand;
+extern crate lambdaworks_math;
+
+use vole_rust::socket_channel::TcpChannel;
+use vole_rust::mem_channel::MemChannel;
+use vole_rust::counting_channel::CountingChannel;
+use vole_rust::secure_channel::SecureChannel;
+use vole_rust::comm_channel::{CommunicationChannel, FE};
+use vole_rust::field::VoleField;
+use vole_rust::vole_triple::{VoleTriple, PrimalLPNParameterFp61};
+use vole_rust::fp61::Fp61;
+use vole_rust::prg::PRG;
+use vole_rust::error::{Result, VoleError};
+use lambdaworks_math::field::element::FieldElement;
+use p256::{EncodedPoint, SecretKey};
+use rand::rngs::OsRng;
+use std::net::{TcpListener, TcpStream};
+use std::thread;
+use std::time::Instant;
+
+fn tcp_pair() -> (TcpChannel, TcpChannel) {
+    let listener = TcpListener::bind("127.0.0.1:0").expect("Failed to bind");
+    let client = TcpStream::connect(listener.local_addr().unwrap()).expect("Failed to connect");
+    let (server, _) = listener.accept().expect("Failed to accept");
+    (TcpChannel::new(server), TcpChannel::new(client))
+}
+
+// Flips a bit in the `target`-th 16-byte message sent, counting the handshake's
+struct FlipChannel {
+    inner: MemChannel,
+    sent: usize,
+    target: usize,
+}
+
+impl CommunicationChannel for FlipChannel {
+    fn send_32byte_block(&mut self, data: &[[u8; 32]]) -> Result<()> { self.inner.send_32byte_block(data) }
+    fn receive_32byte_block(&mut self) -> Result<Vec<[u8; 32]>> { self.inner.receive_32byte_block() }
+    fn send_bits(&mut self, bits: &[bool]) -> Result<()> { self.inner.send_bits(bits) }
+    fn receive_bits(&mut self) -> Result<Vec<bool>> { self.inner.receive_bits() }
+    fn send_stark252(&mut self, elements: &[FE]) -> Result<()> { self.inner.send_stark252(elements) }
+    fn receive_stark252(&mut self, count: usize) -> Result<Vec<FE>> { self.inner.receive_stark252(count) }
+    fn send_field_elements<F: VoleField>(&mut self, elements: &[FieldElement<F>]) -> Result<()> {
+        self.inner.send_field_elements(elements)
+    }
+    fn receive_field_elements<F: VoleField>(&mut self, count: usize) -> Result<Vec<FieldElement<F>>> {
+        self.inner.receive_field_elements(count)
+    }
+    fn send_point(&mut self, point: &EncodedPoint) -> Result<()> { self.inner.send_point(point) }
+    fn receive_point(&mut self) -> Result<EncodedPoint> { self.inner.receive_point() }
+    fn send_data(&mut self, data: &[[u8; 16]]) -> Result<()> {
+        let mut data = data.to_vec();
+        if self.sent == self.target {
+            data[0][0] ^= 1;
+        }
+        self.sent += 1;
+        self.inner.send_data(&data)
+    }
+    fn receive_data(&mut self) -> Result<Vec<[u8; 16]>> { self.inner.receive_data() }
+    fn flush(&mut self) -> Result<()> { self.inner.flush() }
+}
+
+fn test_vole_triple() {
+    let (alice, bob) = tcp_pair();
+    const SIZE: usize = 1000;
+
+    let sender = thread::spawn(move || {
+        let mut alice = SecureChannel::handshake(alice).expect("Failed to run the handshake");
+        let mut prg = PRG::new(None, 0);
+        let mut delta = [Fp61::zero(); 1];
+        prg.random_field_elements(&mut delta);
+
+        let mut vole = VoleTriple::new(0, true, &mut alice, PrimalLPNParameterFp61::small()).expect("Failed to create VOLE");
+        vole.setup_sender(&mut alice, delta[0]).expect("Failed to run VOLE setup");
+        vole.extend_initialization();
+        let mut y = vec![Fp61::zero(); SIZE];
+        let mut z = vec![Fp61::zero(); SIZE];
+        vole.extend(&mut alice, &mut y, &mut z, SIZE).expect("Failed to extend VOLE");
+        vole.check_triple(&mut alice, delta[0], &y, &z, SIZE).expect("Failed to check triples");
+    });
+
+    // The traffic counts of a CountingChannel underneath reach the VOLE's breakdown
+    let mut bob = SecureChannel::handshake(CountingChannel::new(bob)).expect("Failed to run the handshake");
+    let mut vole = VoleTriple::new(1, true, &mut bob, PrimalLPNParameterFp61::small()).expect("Failed to create VOLE");
+    vole.setup_receiver(&mut bob).expect("Failed to run VOLE setup");
+    vole.extend_initialization();
+    let mut y = vec![Fp61::zero(); SIZE];
+    let mut z = vec![Fp61::zero(); SIZE];
+    vole.extend(&mut bob, &mut y, &mut z, SIZE).expect("Failed to extend VOLE");
+    vole.check_triple(&mut bob, Fp61::zero(), &y, &z, SIZE).expect("Failed to check triples");
+    assert!(vole.setup_comm().total().total_bytes() > 0, "No setup traffic counted through the SecureChannel");
+    assert!(vole.extend_comm().total().total_bytes() > 0, "No extend traffic counted through the SecureChannel");
+    sender.join().unwrap();
+    println!("VoleTriple ok");
+}
+
+fn test_long_term_keys() {
+    let alice_key = SecretKey::random(&mut OsRng);
+    let bob_key = SecretKey::random(&mut OsRng);
+    let mallory_key = SecretKey::random(&mut OsRng);
+
+    // Right keys on both sides
+    let (alice, bob) = tcp_pair();
+    let (a, b) = (alice_key.clone(), bob_key.public_key());
+    let sender = thread::spawn(move || {
+        let mut alice = SecureChannel::handshake_with_keys(alice, &a, &b)?;
+        alice.send_data(&[[7u8; 16]; 3])?;
+        alice.flush()
+    });
+    let mut bob = SecureChannel::handshake_with_keys(bob, &bob_key, &alice_key.public_key())
+        .expect("Failed to run the handshake");
+    assert_eq!(bob.receive_data().expect("Failed to receive"), vec![[7u8; 16]; 3]);
+    sender.join().unwrap().expect("Sender failed");
+
+    // Bob expects alice but talks to mallory
+    let (mallory, bob) = tcp_pair();
+    let b = bob_key.public_key();
+    let sender = thread::spawn(move || SecureChannel::handshake_with_keys(mallory, &mallory_key, &b).map(|_| ()));
+    for res in [SecureChannel::handshake_with_keys(bob, &bob_key, &alice_key.public_key()).map(|_| ()), sender.join().unwrap()] {
+        match res {
+            Err(VoleError::Handshake(_)) => {}
+            other => panic!("Expected a handshake error, got {:?}", other),
+        }
+    }
+    println!("Long-term keys ok");
+}
+
+fn test_tampering() {
+    let (alice, bob) = MemChannel::pair();
+    // The first 16-byte message is the key confirmation, the second the payload below
+    let alice = FlipChannel { inner: alice, sent: 0, target: 1 };
+
+    let sender = thread::spawn(move || {
+        let mut alice = SecureChannel::handshake(alice)?;
+        alice.send_data(&[[7u8; 16]; 3])
+    });
+    let mut bob = SecureChannel::handshake(bob).expect("Failed to run the handshake");
+    match bob.receive_data() {
+        Err(VoleError::MalformedMessage(_)) => println!("Tampering ok"),
+        other => panic!("Expected an authentication failure, got {:?}", other),
+    }
+    sender.join().unwrap().expect("Sender failed");
+}
+
+fn main() {
+    let start = Instant::now();
+    test_vole_triple();
+    test_long_term_keys();
+    test_tampering();
+    println!("All tests passed in {:?}", start.elapsed());
+}
@@ -0,0 +1,217 @@
+// Runs SoftSpokenOT in two threads over a MemChannel for several k, both block widths and both
+// security models, checks the correlation and compares the receiver's traffic with IKNP's.
+// Also checks that a receiver with inconsistent choice bits fails the malicious check.
+// Usage: test_softspoken [number of COTs to time]
+
+extern crate vole_rust;
+extern crate lambdaworks_math;
+extern crate p256;
+extern crate rand;
+
+use vole_rust::base_cot::BaseCot;
+use vole_rust::comm_channel::{CommunicationChannel, FE};
+use vole_rust::counting_channel::CountingChannel;
+use vole_rust::error::{CheckFailure, Result, VoleError};
+use vole_rust::field::VoleField;
+use vole_rust::iknp::IKNP;
+use vole_rust::mem_channel::MemChannel;
+use vole_rust::softspoken::SoftSpokenOT;
+use lambdaworks_math::field::element::FieldElement;
+use p256::EncodedPoint;
+use rand::Rng;
+use std::env;
+use std::thread;
+use std::time::{Duration, Instant};
+
+const LENGTH: usize = 5000;
+
+// Flips a bit of the first message of `corrections` blocks, i.e. of the receiver's first corrections
+struct TamperChannel {
+    inner: MemChannel,
+    corrections: usize,
+    done: bool,
+}
+
+impl CommunicationChannel for TamperChannel {
+    fn send_32byte_block(&mut self, data: &[[u8; 32]]) -> Result<()> { self.inner.send_32byte_block(data) }
+    fn receive_32byte_block(&mut self) -> Result<Vec<[u8; 32]>> { self.inner.receive_32byte_block() }
+    fn send_bits(&mut self, bits: &[bool]) -> Result<()> { self.inner.send_bits(bits) }
+    fn receive_bits(&mut self) -> Result<Vec<bool>> { self.inner.receive_bits() }
+    fn send_stark252(&mut self, elements: &[FE]) -> Result<()> { self.inner.send_stark252(elements) }
+    fn receive_stark252(&mut self, count: usize) -> Result<Vec<FE>> { self.inner.receive_stark252(count) }
+    fn send_field_elements<F: VoleField>(&mut self, elements: &[FieldElement<F>]) -> Result<()> {
+        self.inner.send_field_elements(elements)
+    }
+    fn receive_field_elements<F: VoleField>(&mut self, count: usize) -> Result<Vec<FieldElement<F>>> {
+        self.inner.receive_field_elements(count)
+    }
+    fn send_point(&mut self, point: &EncodedPoint) -> Result<()> { self.inner.send_point(point) }
+    fn receive_point(&mut self) -> Result<EncodedPoint> { self.inner.receive_point() }
+    fn send_data(&mut self, data: &[[u8; 16]]) -> Result<()> {
+        let mut data = data.to_vec();
+        if !self.done && data.len() == self.corrections {
+            data[0][0] ^= 1;
+            self.done = true;
+        }
+        self.inner.send_data(&data)
+    }
+    fn receive_data(&mut self) -> Result<Vec<[u8; 16]>> { self.inner.receive_data() }
+    fn flush(&mut self) -> Result<()> { self.inner.flush() }
+}
+
+fn random_delta<const N: usize>() -> (Vec<bool>, [u8; N]) {
+    let mut rng = rand::thread_rng();
+    let s: Vec<bool> = (0..8 * N).map(|_| rng.gen()).collect();
+    let mut delta = [0u8; N];
+    for (i, &bit) in s.iter().enumerate() {
+        delta[i / 8] |= (bit as u8) << (i % 8);
+    }
+    (s, delta)
+}
+
+fn check_correlation<const N: usize>(sent: &[[u8; N]], received: &[[u8; N]], r: &[bool], delta: &[u8; N]) {
+    for i in 0..sent.len() {
+        let mut expected = sent[i];
+        if r[i] {
+            for (byte, d) in expected.iter_mut().zip(delta.iter()) {
+                *byte ^= d;
+            }
+        }
+        assert_eq!(received[i], expected, "COT {} is not correlated", i);
+    }
+}
+
+// Returns the bytes the receiver sent for setup and extension, and the time of the extension
+fn run_softspoken<const N: usize>(k: usize, malicious: bool, length: usize) -> (u64, Duration) {
+    let (alice, bob) = MemChannel::pair();
+    let (s, delta) = random_delta::<N>();
+    let r: Vec<bool> = (0..length).map(|_| rand::thread_rng().gen()).collect();
+
+    let sender = thread::spawn(move || {
+        let mut io = alice;
+        let mut ot = SoftSpokenOT::<N>::new(k, malicious).expect("Invalid k");
+        ot.setup_send(&mut io, Some(&s)).expect("Failed to set up SoftSpokenOT");
+        let mut data = vec![[0u8; N]; length];
+        ot.send_cot(&mut io, &mut data, length).expect("Failed to send COTs");
+        data
+    });
+
+    let mut io = CountingChannel::new(bob);
+    let mut ot = SoftSpokenOT::<N>::new(k, malicious).expect("Invalid k");
+    ot.setup_recv(&mut io).expect("Failed to set up SoftSpokenOT");
+    let mut data = vec![[0u8; N]; length];
+    let start = Instant::now();
+    ot.recv_cot(&mut io, &mut data, &r, length).expect("Failed to receive COTs");
+    let elapsed = start.elapsed();
+    let sent = sender.join().unwrap();
+
+    check_correlation(&sent, &data, &r, &delta);
+    (io.snapshot().bytes_sent, elapsed)
+}
+
+fn run_iknp<const N: usize>(malicious: bool, length: usize) -> u64 {
+    let (alice, bob) = MemChannel::pair();
+    let sender = thread::spawn(move || {
+        let mut io = alice;
+        let mut ot = IKNP::<N>::new(malicious);
+        ot.setup_send(&mut io, None, None).expect("Failed to set up IKNP");
+        let mut data = vec![[0u8; N]; length];
+        ot.send_cot(&mut io, &mut data, length).expect("Failed to send COTs");
+    });
+
+    let mut io = CountingChannel::new(bob);
+    let mut ot = IKNP::<N>::new(malicious);
+    ot.setup_recv(&mut io, None, None).expect("Failed to set up IKNP");
+    let r = vec![false; length];
+    let mut data = vec![[0u8; N]; length];
+    ot.recv_cot(&mut io, &mut data, &r, length).expect("Failed to receive COTs");
+    sender.join().unwrap();
+    io.snapshot().bytes_sent
+}
+
+fn test_correlation<const N: usize>() {
+    for &malicious in [false, true].iter() {
+        let iknp = run_iknp::<N>(malicious, LENGTH);
+        for &k in [1, 2, 3, 4, 8].iter() {
+            let (bytes, _) = run_softspoken::<N>(k, malicious, LENGTH);
+            println!(
+                "{} {}-bit SoftSpokenOT k = {} ok, receiver sent {} bytes, {:.2}x less than IKNP",
+                if malicious { "Malicious" } else { "Semi-honest" }, 8 * N, k, bytes, iknp as f64 / bytes as f64
+            );
+            if k >= 2 {
+                assert!(bytes < iknp, "k = {} sent {} bytes, IKNP {}", k, bytes, iknp);
+            }
+        }
+    }
+}
+
+fn test_base_cot() {
+    let (mut alice, mut bob) = MemChannel::pair();
+    let sender = thread::spawn(move || {
+        let mut cot = BaseCot::with_extension(0, SoftSpokenOT::<16>::new(4, true).expect("Invalid k"));
+        cot.cot_gen_pre(&mut alice, None).expect("Failed to set up base COT");
+        let mut data = vec![[0u8; 16]; LENGTH];
+        cot.cot_gen(&mut alice, &mut data, LENGTH, None).expect("Failed to send COTs");
+        cot.check_cot(&mut alice, &data, LENGTH).expect("Failed to send the COT check");
+    });
+
+    let mut cot = BaseCot::with_extension(1, SoftSpokenOT::<16>::new(4, true).expect("Invalid k"));
+    cot.cot_gen_pre(&mut bob, None).expect("Failed to set up base COT");
+    let mut data = vec![[0u8; 16]; LENGTH];
+    cot.cot_gen(&mut bob, &mut data, LENGTH, None).expect("Failed to receive COTs");
+    assert!(cot.check_cot(&mut bob, &data, LENGTH).expect("Failed to check COTs"), "Base COTs are not correlated");
+    sender.join().unwrap();
+    println!("BaseCot over SoftSpokenOT ok");
+}
+
+fn test_cheating_receiver() {
+    let (mut alice, bob) = MemChannel::pair();
+    // 128 bits of Δ in chunks of 4, a row of 2048 bits each
+    let mut bob = TamperChannel { inner: bob, corrections: 32 * 16, done: false };
+    let receiver = thread::spawn(move || {
+        let mut ot = SoftSpokenOT::<16>::new(4, true).expect("Invalid k");
+        ot.setup_recv(&mut bob).expect("Failed to set up SoftSpokenOT");
+        let r = vec![false; LENGTH];
+        let mut data = vec![[0u8; 16]; LENGTH];
+        ot.recv_cot(&mut bob, &mut data, &r, LENGTH).expect("Failed to receive COTs");
+        assert!(bob.done, "No corrections were tampered with");
+    });
+
+    let mut ot = SoftSpokenOT::<16>::new(4, true).expect("Invalid k");
+    ot.setup_send(&mut alice, None).expect("Failed to set up SoftSpokenOT");
+    let mut data = vec![[0u8; 16]; LENGTH];
+    match ot.send_cot(&mut alice, &mut data, LENGTH) {
+        Err(VoleError::ConsistencyCheck(CheckFailure::SoftSpoken)) => {}
+        other => panic!("Inconsistent choice bits were not caught: {:?}", other.map(|_| ())),
+    }
+    receiver.join().unwrap();
+    println!("Cheating receiver caught");
+}
+
+fn test_parameters() {
+    assert!(matches!(SoftSpokenOT::<16>::new(0, false), Err(VoleError::Parameter(_))));
+    assert!(matches!(SoftSpokenOT::<16>::new(11, false), Err(VoleError::Parameter(_))));
+    println!("Parameters ok");
+}
+
+fn bench(length: usize) {
+    for &k in [2, 4, 8].iter() {
+        let (bytes, elapsed) = run_softspoken::<16>(k, false, length);
+        println!(
+            "Semi-honest 128-bit SoftSpokenOT k = {}: {} COTs in {:?}, {:.2} million COTs/s, {:.2} bits per COT",
+            k, length, elapsed, length as f64 / elapsed.as_secs_f64() / 1e6, 8.0 * bytes as f64 / length as f64
+        );
+    }
+}
+
+fn main() {
+    let args: Vec<String> = env::args().collect();
+    let length: usize = args.get(1).and_then(|s| s.parse().ok()).unwrap_or(1 << 20);
+    test_parameters();
+    test_correlation::<16>();
+    test_correlation::<32>();
+    test_base_cot();
+    test_cheating_receiver();
+    bench(length);
+    println!("All tests passed");
+}
@@ -0,0 +1,122 @@
+// Runs VoleTriple over a Unix socket pair, a named Unix socket, and the stdio pipes of a child process
+// Usage: test_stream_channel (the child is started as test_stream_channel stdio-child)
+
+extern crate vole_rust;
+
+use vole_rust::socket_channel::{StreamChannel, UnixChannel, StdioChannel};
+use vole_rust::comm_channel::CommunicationChannel;
+use vole_rust::framing::{Framer, MessageKind, MAX_FRAME_LEN};
+use vole_rust::vole_triple::{VoleTriple, PrimalLPNParameterFp61};
+use vole_rust::fp61::{Fp61, Mersenne61Field};
+use vole_rust::prg::PRG;
+use vole_rust::error::{Result, VoleError};
+use std::env;
+use std::io::Write;
+use std::os::unix::net::UnixStream;
+use std::process::{Command, Stdio};
+use std::thread;
+use std::time::{Duration, Instant};
+
+fn run_party<IO: CommunicationChannel>(party: usize, io: &mut IO) -> Result<()> {
+    const SIZE: usize = 1000;
+    let mut vole = VoleTriple::<Mersenne61Field>::new(party, true, io, PrimalLPNParameterFp61::small())?;
+    let mut delta = Fp61::zero();
+    if party == 0 {
+        let mut prg = PRG::new(None, 0);
+        let mut random = [Fp61::zero(); 1];
+        prg.random_field_elements(&mut random);
+        delta = random[0];
+        vole.setup_sender(io, delta)?;
+    } else {
+        vole.setup_receiver(io)?;
+    }
+    vole.extend_initialization();
+
+    let mut y = vec![Fp61::zero(); SIZE];
+    let mut z = vec![Fp61::zero(); SIZE];
+    vole.extend(io, &mut y, &mut z, SIZE)?;
+    vole.check_triple(io, delta, &y, &z, SIZE)
+}
+
+fn test_unix_pair() {
+    let (mut alice, mut bob) = UnixChannel::unix_pair().expect("Failed to create socket pair");
+    let sender = thread::spawn(move || run_party(0, &mut alice));
+    run_party(1, &mut bob).expect("VOLE receiver failed");
+    sender.join().unwrap().expect("VOLE sender failed");
+    println!("Unix socket pair ok");
+}
+
+fn test_unix_path() {
+    let path = env::temp_dir().join(format!("vole_test_{}.sock", std::process::id()));
+    let accept_path = path.clone();
+    let sender = thread::spawn(move || {
+        let mut alice = UnixChannel::accept_unix(&accept_path)?;
+        run_party(0, &mut alice)
+    });
+
+    // The listener may not be bound yet
+    let mut bob = loop {
+        match UnixChannel::connect_unix(&path) {
+            Ok(bob) => break bob,
+            Err(_) => thread::sleep(Duration::from_millis(10)),
+        }
+    };
+    run_party(1, &mut bob).expect("VOLE receiver failed");
+    sender.join().unwrap().expect("VOLE sender failed");
+    assert!(!path.exists(), "Socket file left behind");
+    println!("Unix socket path ok");
+}
+
+fn test_stdio() {
+    let mut child = Command::new(env::current_exe().unwrap())
+        .arg("stdio-child")
+        .stdin(Stdio::piped())
+        .stdout(Stdio::piped())
+        .stderr(Stdio::null())
+        .spawn()
+        .expect("Failed to start the child");
+
+    let mut alice = StreamChannel::from_pair(child.stdout.take().unwrap(), child.stdin.take().unwrap());
+    run_party(0, &mut alice).expect("VOLE sender failed");
+    drop(alice);
+    assert!(child.wait().expect("Failed to wait for the child").success(), "VOLE receiver failed");
+    println!("Stdio pipes ok");
+}
+
+// A header announcing more than MAX_FRAME_LEN bytes is refused before anything is allocated
+fn expect_oversized_frame_refused<IO: CommunicationChannel>(mut raw: UnixStream, mut io: IO, len: usize) {
+    raw.write_all(&Framer::default().header(MessageKind::Data, len)).expect("Failed to send");
+    match io.receive_data() {
+        Err(VoleError::MalformedMessage(_)) => {}
+        other => panic!("Expected a malformed message error, got {:?}", other.map(|data| data.len())),
+    }
+}
+
+fn test_oversized_frame() {
+    for len in [MAX_FRAME_LEN + 1, usize::MAX] {
+        let (raw, stream) = UnixStream::pair().expect("Failed to create socket pair");
+        expect_oversized_frame_refused(raw, StreamChannel::new(stream), len);
+
+        let (raw, stream) = UnixStream::pair().expect("Failed to create socket pair");
+        let read_half = stream.try_clone().expect("Failed to clone the socket");
+        expect_oversized_frame_refused(raw, StreamChannel::buffered(read_half, stream), len);
+    }
+    println!("Oversized frame ok");
+}
+
+fn main() {
+    if env::args().nth(1).as_deref() == Some("stdio-child") {
+        let mut bob = StdioChannel::stdio();
+        if run_party(1, &mut bob).is_err() {
+            std::process::exit(1);
+        }
+        return;
+    }
+
+    let start = Instant::now();
+    test_unix_pair();
+    test_unix_path();
+    test_stdio();
+    test_oversized_frame();
+    println!("All tests passed in {:?}", start.elapsed());
+}
@@ -0,0 +1,105 @@
+// Records the receiver of a malicious VoleTriple run, then replays it alone and checks it does the same
+// Also checks that a replay with different randomness is caught at the first diverging message,
+// and that a truncated or oversized transcript entry is refused
+// Usage: cargo run --features replay --bin test_transcript
+
+extern crate vole_rust;
+
+use vole_rust::mem_channel::MemChannel;
+use vole_rust::comm_channel::CommunicationChannel;
+use vole_rust::transcript::{RecordingChannel, ReplayChannel, TranscriptEntry};
+use vole_rust::framing::{MessageKind, MAX_FRAME_LEN};
+use vole_rust::vole_triple::{VoleTriple, PrimalLPNParameterFp61};
+use vole_rust::fp61::{Fp61, Mersenne61Field};
+use vole_rust::prg::{PRG, set_thread_seed};
+use vole_rust::error::{Result, VoleError};
+use std::env;
+use std::thread;
+
+const SEED: [u8; 32] = [42u8; 32];
+const SIZE: usize = 6000;
+
+fn run_receiver<IO: CommunicationChannel>(io: &mut IO) -> Result<(Vec<Fp61>, Vec<Fp61>)> {
+    let mut vole = VoleTriple::<Mersenne61Field>::new(1, true, io, PrimalLPNParameterFp61::small())?;
+    vole.setup_receiver(io)?;
+    vole.extend_initialization();
+    let mut y = vec![Fp61::zero(); SIZE];
+    let mut z = vec![Fp61::zero(); SIZE];
+    vole.extend(io, &mut y, &mut z, SIZE)?;
+    io.flush()?;
+    Ok((y, z))
+}
+
+fn expect_malformed(bytes: &[u8]) {
+    match TranscriptEntry::read_from(&mut &bytes[..]) {
+        Err(VoleError::MalformedMessage(_)) => {}
+        other => panic!("Expected a malformed transcript error, got {:?}", other),
+    }
+}
+
+fn test_bad_entries() {
+    let mut bytes = Vec::new();
+    TranscriptEntry { sent: false, kind: MessageKind::Data, payload: vec![1u8; 32] }
+        .write_to(&mut bytes).expect("Failed to write entry");
+    assert!(TranscriptEntry::read_from(&mut &bytes[..]).expect("Failed to read entry").is_some());
+    assert!(TranscriptEntry::read_from(&mut &bytes[..0]).expect("Failed to read entry").is_none());
+
+    // Cut in the header, then in the payload
+    expect_malformed(&bytes[..5]);
+    expect_malformed(&bytes[..bytes.len() - 1]);
+
+    // A length beyond MAX_FRAME_LEN is refused before anything is allocated
+    bytes[2..10].copy_from_slice(&(MAX_FRAME_LEN as u64 + 1).to_le_bytes());
+    expect_malformed(&bytes);
+    bytes[2..10].copy_from_slice(&u64::MAX.to_le_bytes());
+    expect_malformed(&bytes);
+    println!("Bad entries ok");
+}
+
+fn main() {
+    let path = env::temp_dir().join(format!("vole_transcript_{}.bin", std::process::id()));
+    let (mut alice, bob) = MemChannel::pair();
+
+    let sender = thread::spawn(move || {
+        let mut prg = PRG::new(None, 0);
+        let mut delta = [Fp61::zero(); 1];
+        prg.random_field_elements(&mut delta);
+
+        let mut vole = VoleTriple::<Mersenne61Field>::new(0, true, &mut alice, PrimalLPNParameterFp61::small()).expect("Failed to create VOLE");
+        vole.setup_sender(&mut alice, delta[0]).expect("Failed to run VOLE setup");
+        vole.extend_initialization();
+        let mut y = vec![Fp61::zero(); SIZE];
+        let mut z = vec![Fp61::zero(); SIZE];
+        vole.extend(&mut alice, &mut y, &mut z, SIZE).expect("Failed to extend VOLE");
+    });
+
+    let recorder_path = path.clone();
+    let recorded = thread::spawn(move || {
+        set_thread_seed(Some(SEED));
+        let mut bob = RecordingChannel::create(bob, &recorder_path).expect("Failed to create transcript");
+        run_receiver(&mut bob).expect("Recorded receiver failed")
+    }).join().unwrap();
+    sender.join().unwrap();
+    println!("Recorded to {}", path.display());
+
+    // Same seed, no peer: the replay has to send exactly what was recorded
+    set_thread_seed(Some(SEED));
+    let mut replay = ReplayChannel::open(&path).expect("Failed to open transcript");
+    let replayed = run_receiver(&mut replay).expect("Replay failed");
+    assert!(recorded == replayed, "Replay gave different VOLE outputs");
+    println!("Replay ok after {} messages", replay.position());
+
+    // Another seed goes wrong at the first message that depends on it
+    set_thread_seed(Some([7u8; 32]));
+    let mut replay = ReplayChannel::open(&path).expect("Failed to open transcript");
+    match run_receiver(&mut replay) {
+        Err(VoleError::Desync(msg)) => println!("Divergence ok: {}", msg),
+        Err(e) => panic!("Unexpected error on divergence: {}", e),
+        Ok(_) => panic!("Replay with another seed went through"),
+    }
+    set_thread_seed(None);
+
+    std::fs::remove_file(&path).ok();
+    test_bad_entries();
+    println!("All tests passed");
+}
@@ -0,0 +1,64 @@
+// Checks the bit matrix transposes against a bit by bit one, then times them on an IKNP block
+// Usage: test_transpose
+
+extern crate vole_rust;
+extern crate rand;
+
+use vole_rust::transpose::{transpose, transpose_portable};
+use rand::Rng;
+use std::time::Instant;
+
+fn transpose_reference(input: &[u8], rows: usize, cols: usize) -> Vec<u8> {
+    let mut out = vec![0u8; input.len()];
+    for r in 0..rows {
+        for c in 0..cols {
+            let idx = r * cols + c;
+            let bit = (input[idx / 8] >> (idx % 8)) & 1;
+            let new_idx = c * rows + r;
+            out[new_idx / 8] |= bit << (new_idx % 8);
+        }
+    }
+    out
+}
+
+fn test_shapes() {
+    let mut rng = rand::thread_rng();
+    for &(rows, cols) in [(8, 8), (16, 8), (24, 40), (32, 64), (128, 2048), (256, 2048), (256, 8), (64, 1024)].iter() {
+        let input: Vec<u8> = (0..rows * cols / 8).map(|_| rng.gen()).collect();
+        let expected = transpose_reference(&input, rows, cols);
+
+        // Garbage in the output must not leak through
+        let mut out = vec![0xA5u8; input.len()];
+        transpose(&mut out, &input, rows, cols);
+        assert_eq!(out, expected, "transpose of a {} x {} matrix is wrong", rows, cols);
+
+        let mut out = vec![0x5Au8; input.len()];
+        transpose_portable(&mut out, &input, rows, cols);
+        assert_eq!(out, expected, "transpose_portable of a {} x {} matrix is wrong", rows, cols);
+
+        // Transposing twice gives the input back
+        let mut back = vec![0u8; input.len()];
+        transpose(&mut back, &out, cols, rows);
+        assert_eq!(back, input, "transpose of a {} x {} matrix does not invert", cols, rows);
+    }
+    println!("Shapes ok");
+}
+
+fn bench(name: &str, f: impl Fn(&mut [u8], &[u8], usize, usize)) {
+    let (rows, cols, iterations) = (256, 2048, 1000);
+    let input: Vec<u8> = (0..rows * cols / 8).map(|i| (i * 131 + 7) as u8).collect();
+    let mut out = vec![0u8; input.len()];
+
+    let start = Instant::now();
+    for _ in 0..iterations {
+        f(&mut out, &input, rows, cols);
+    }
+    println!("{}: {} transposes of {} x {} bits in {:?}", name, iterations, rows, cols, start.elapsed());
+}
+
+fn main() {
+    test_shapes();
+    bench("transpose", transpose);
+    bench("transpose_portable", transpose_portable);
+    println!("All tests passed");
+}
@@ -1,38 +1,60 @@
-// Running 1 million expand_1to2: 250 ms
+// Times 1 million node_expand_1to2 and 500k node_expand_2to4 with each GGM expansion,
+// and mapping 1 million leaves to field elements
+// Re-keyed AES pays a key schedule per node, fixed-key AES does not
 
 extern crate vole_rust;
-extern crate aes;
 extern crate lambdaworks_math;
 
-use vole_rust::two_key_prp::TwoKeyPRP;
-use aes::Aes256;
-use aes::cipher::{BlockEncrypt, KeyInit, generic_array::GenericArray};
+use vole_rust::two_key_prp::{TwoKeyPRP, GgmExpansion};
+use vole_rust::field::VoleField;
+use vole_rust::fp61::Mersenne61Field;
 use lambdaworks_math::field::fields::fft_friendly::stark_252_prime_field::Stark252PrimeField;
 use lambdaworks_math::field::element::FieldElement;
-use lambdaworks_math::traits::ByteConversion;
 use std::time::Instant;
 
-pub type F = Stark252PrimeField;
-pub type FE = FieldElement<F>;
+const ITERATIONS: usize = 1_000_000;
 
-fn benchmark_node_expand_2to4() {
+fn benchmark_node_expand_2to4(expansion: GgmExpansion) {
+    let prp = TwoKeyPRP::with_expansion(expansion);
+    let mut parents = [[1u8; 16], [2u8; 16]];
+    let mut children = [[0u8; 16]; 4];
+
+    let start = Instant::now();
+    for _ in 0..ITERATIONS / 2 {
+        prp.node_expand_2to4(&mut children, &parents);
+        parents.copy_from_slice(&children[1..3]);
+    }
+    println!("{:?}: {} node_expand_2to4 in {:?}", expansion, ITERATIONS / 2, start.elapsed());
 }
 
-fn benchmark_node_expand_1to2() {
-    let prp = TwoKeyPRP {};
-    let parent = FE::from(12345u64); // Example parent field element
-    let mut children = [FE::zero(), FE::zero()];
-    let iterations = 1_000_000;
+fn benchmark_node_expand_1to2(expansion: GgmExpansion) {
+    let prp = TwoKeyPRP::with_expansion(expansion);
+    let mut parent = [1u8; 16];
+    let mut children = [[0u8; 16]; 2];
 
     let start = Instant::now();
-    for _ in 0..iterations {
+    for _ in 0..ITERATIONS {
         prp.node_expand_1to2(&mut children, &parent);
+        parent = children[0];
     }
-    let duration = start.elapsed();
+    println!("{:?}: {} node_expand_1to2 in {:?}", expansion, ITERATIONS, start.elapsed());
+}
 
-    println!("Time taken for {} iterations: {:?}", iterations, duration);
+fn benchmark_leaves_to_field<F: VoleField>() {
+    let prp = TwoKeyPRP::with_expansion(GgmExpansion::FixedKey);
+    let leaves: Vec<[u8; 16]> = (0..ITERATIONS).map(|i| (i as u128).to_le_bytes()).collect();
+    let mut out = vec![FieldElement::<F>::zero(); ITERATIONS];
+
+    let start = Instant::now();
+    prp.leaves_to_field(&mut out, &leaves);
+    println!("{}: {} leaves_to_field in {:?}", F::NAME, ITERATIONS, start.elapsed());
 }
 
 fn main() {
-    benchmark_node_expand_1to2();
+    for expansion in [GgmExpansion::ReKeyed, GgmExpansion::FixedKey].iter() {
+        benchmark_node_expand_1to2(*expansion);
+        benchmark_node_expand_2to4(*expansion);
+    }
+    benchmark_leaves_to_field::<Stark252PrimeField>();
+    benchmark_leaves_to_field::<Mersenne61Field>();
 }
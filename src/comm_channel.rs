@@ -2,20 +2,29 @@ use p256::EncodedPoint;
 
 use lambdaworks_math::field::fields::fft_friendly::stark_252_prime_field::Stark252PrimeField;
 use lambdaworks_math::field::element::FieldElement;
+use crate::field::VoleField;
+use crate::error::Result;
+use crate::counting_channel::CommStats;
 
-pub type F = Stark252PrimeField;
-pub type FE = FieldElement<F>;
+pub type FE = FieldElement<Stark252PrimeField>;
 
 pub trait CommunicationChannel {
-    fn send_32byte_block(&mut self, data: &[[u8; 32]]);
-    fn receive_32byte_block(&mut self) -> Vec<[u8; 32]>;
-    fn send_bits(&mut self, bits: &[bool]) -> std::io::Result<()>;
-    fn receive_bits(&mut self) -> std::io::Result<Vec<bool>>;
-    fn send_stark252(&mut self, elements: &[FE]) -> std::io::Result<()>;
-    fn receive_stark252(&mut self, count: usize) -> std::io::Result<Vec<FE>>;
-    fn send_point(&mut self, point: &EncodedPoint);
-    fn receive_point(&mut self) -> EncodedPoint;
-    fn send_data(&mut self, data: &[[u8; 16]]);
-    fn receive_data(&mut self) -> Vec<[u8; 16]>;
-    fn flush(&mut self);
-}
\ No newline at end of file
+    fn send_32byte_block(&mut self, data: &[[u8; 32]]) -> Result<()>;
+    fn receive_32byte_block(&mut self) -> Result<Vec<[u8; 32]>>;
+    fn send_bits(&mut self, bits: &[bool]) -> Result<()>;
+    fn receive_bits(&mut self) -> Result<Vec<bool>>;
+    fn send_stark252(&mut self, elements: &[FE]) -> Result<()>;
+    fn receive_stark252(&mut self, count: usize) -> Result<Vec<FE>>;
+    fn send_field_elements<F: VoleField>(&mut self, elements: &[FieldElement<F>]) -> Result<()> where Self: Sized;
+    fn receive_field_elements<F: VoleField>(&mut self, count: usize) -> Result<Vec<FieldElement<F>>> where Self: Sized;
+    fn send_point(&mut self, point: &EncodedPoint) -> Result<()>;
+    fn receive_point(&mut self) -> Result<EncodedPoint>;
+    fn send_data(&mut self, data: &[[u8; 16]]) -> Result<()>;
+    fn receive_data(&mut self) -> Result<Vec<[u8; 16]>>;
+    fn flush(&mut self) -> Result<()>;
+
+    /// Traffic counters, only counting channels keep them.
+    fn stats(&self) -> Option<CommStats> {
+        None
+    }
+}
@@ -1,25 +1,26 @@
 use crate::ot::OTCO;
 use crate::prg::PRG;
 use crate::comm_channel::CommunicationChannel;
-use lambdaworks_math::field::fields::fft_friendly::stark_252_prime_field::Stark252PrimeField;
+use crate::async_comm_channel::{AsyncCommunicationChannel, SyncChannel, block_on};
+use crate::error::{Result, VoleError, CheckFailure};
+use crate::field::{VoleField, VoleSubfield, DefaultField};
 use lambdaworks_math::field::element::FieldElement;
-use std::time::Instant;
+use std::marker::PhantomData;
 
-pub type F = Stark252PrimeField;
-pub type FE = FieldElement<F>;
-
-pub struct Cope {
+/// COPE with u in `F` and Δ, outputs in `K`, `K = F` being the plain case.
+pub struct Cope<F: VoleSubfield<K> = DefaultField, K: VoleField = F> {
     party: u8,                     // 0 for sender, 1 for receiver
     m: usize,                      // Number of field elements
-    delta: Option<FE>,             // Delta value for the sender
+    delta: Option<FieldElement<K>>,             // Delta value for the sender
     delta_bool: Vec<bool>,         // Boolean representation of delta
     prg_g0: Option<Vec<PRG>>,      // PRGs for the 0-choice
     prg_g1: Option<Vec<PRG>>,      // PRGs for the 1-choice (receiver)
     mask: u128,                    // Mask for modular reduction
-    powers_of_two: Vec<FE>,        // Precomputed powers of two
+    powers_of_two: Vec<FieldElement<K>>,        // Precomputed powers of two times the basis of K
+    subfield: PhantomData<F>,      // u and the OT'd values live in F
 }
 
-impl Cope {
+impl<F: VoleSubfield<K>, K: VoleField> Cope<F, K> {
     /// Create a new COPE instance.
     pub fn new(party: u8, m: usize) -> Self {
         Self {
@@ -31,35 +32,47 @@ impl Cope {
             prg_g1: None,
             mask: u128::MAX,
             powers_of_two: vec![], // Initialize empty, will be filled in `initialize_*`
+            subfield: PhantomData,
         }
     }
 
-    /// Convert delta to a boolean array.
-    fn delta_to_bool(delta: &FE, m: usize) -> Vec<bool> {
-        let delta_bytes = delta.to_bytes_le();
+    /// Convert delta to a boolean array, the bits of each of its coordinates in turn.
+    fn delta_to_bool(delta: &FieldElement<K>, m: usize) -> Vec<bool> {
+        let bits_per_coord = m / F::DEGREE;
         let mut delta_bool = vec![false; m];
 
-        for i in 0..m {
-            let byte_index = i / 8;
-            let bit_index = i % 8;
-            if byte_index < delta_bytes.len() {
-                delta_bool[i] = (delta_bytes[byte_index] & (1 << bit_index)) != 0;
+        for (j, coord) in F::coordinates(delta).iter().enumerate() {
+            let delta_bytes = F::to_bytes_le(coord);
+            for i in 0..bits_per_coord {
+                let byte_index = i / 8;
+                let bit_index = i % 8;
+                if byte_index < delta_bytes.len() {
+                    delta_bool[j * bits_per_coord + i] = (delta_bytes[byte_index] & (1 << bit_index)) != 0;
+                }
             }
         }
         delta_bool
     }
 
-    /// Precompute powers of two in the field
+    /// Precompute powers of two in the field, times each basis element of K
     fn precompute_powers_of_two(&mut self) {
-        let mut powers = vec![FE::one(); self.m];
-        let base = FE::from(2);
-        for i in 1..self.m {
+        let bits_per_coord = self.m / F::DEGREE;
+        let mut powers = vec![FieldElement::one(); bits_per_coord];
+        let base = FieldElement::<K>::from(2);
+        for i in 1..bits_per_coord {
             powers[i] = powers[i - 1] * base;
         }
-        self.powers_of_two = powers;
+        self.powers_of_two = (0..F::DEGREE)
+            .flat_map(|j| powers.iter().map(move |p| F::basis(j) * *p))
+            .collect();
     }
 
-    pub fn initialize_sender<IO: CommunicationChannel>(&mut self, io: &mut IO, delta: FE) {
+    pub fn initialize_sender<IO: CommunicationChannel>(&mut self, io: &mut IO, delta: FieldElement<K>) -> Result<()> {
+        block_on(self.initialize_sender_async(&mut SyncChannel(io), delta))
+    }
+
+    /// Async variant of `initialize_sender`.
+    pub async fn initialize_sender_async<IO: AsyncCommunicationChannel>(&mut self, io: &mut IO, delta: FieldElement<K>) -> Result<()> {
         self.delta = Some(delta);
         self.delta_bool = Self::delta_to_bool(&delta, self.m);
         self.precompute_powers_of_two(); // Precompute powers of two
@@ -67,7 +80,7 @@ impl Cope {
         // Prepare keys using OTCO
         let mut k = Vec::new();
         let mut otco = OTCO::new();
-        otco.recv(io, &self.delta_bool, &mut k);
+        otco.recv_async(io, &self.delta_bool, &mut k).await?;
 
         // Initialize PRGs
         self.prg_g0 = Some(
@@ -83,9 +96,15 @@ impl Cope {
 
         assert_eq!(k.len(), self.m, "Mismatch in key length during initialization");
         assert_eq!(self.prg_g0.as_ref().unwrap().len(), self.m, "Mismatch in prg_g0 length after initialization");
+        Ok(())
+    }
+
+    pub fn initialize_receiver<IO: CommunicationChannel>(&mut self, io: &mut IO) -> Result<()> {
+        block_on(self.initialize_receiver_async(&mut SyncChannel(io)))
     }
 
-    pub fn initialize_receiver<IO: CommunicationChannel>(&mut self, io: &mut IO) {
+    /// Async variant of `initialize_receiver`.
+    pub async fn initialize_receiver_async<IO: AsyncCommunicationChannel>(&mut self, io: &mut IO) -> Result<()> {
         self.precompute_powers_of_two(); // Precompute powers of two
 
         let mut k0 = vec![[0u8; 16]; self.m];
@@ -98,7 +117,7 @@ impl Cope {
 
         // Use OTCO to send keys
         let mut otco = OTCO::new();
-        otco.send(io, &k0, &k1);
+        otco.send_async(io, &k0, &k1).await?;
 
         // Initialize PRGs
         self.prg_g0 = Some(
@@ -121,24 +140,30 @@ impl Cope {
                 })
                 .collect(),
         );
+        Ok(())
+    }
+
+    pub fn extend_sender<IO: CommunicationChannel>(&mut self, io: &mut IO) -> Result<FieldElement<K>> {
+        block_on(self.extend_sender_async(&mut SyncChannel(io)))
     }
 
-    pub fn extend_sender<IO: CommunicationChannel>(&mut self, io: &mut IO) -> FE {
-        let mut w = vec![FE::zero(); self.m];
+    /// Async variant of `extend_sender`.
+    pub async fn extend_sender_async<IO: AsyncCommunicationChannel>(&mut self, io: &mut IO) -> Result<FieldElement<K>> {
+        let mut w = vec![FieldElement::zero(); self.m];
 
         if let Some(prgs) = &mut self.prg_g0 {
             assert_eq!(prgs.len(), self.m, "prg_g0 length does not match self.m");
-            let mut w = vec![FE::zero(); self.m];
+            let mut w = vec![FieldElement::<F>::zero(); self.m];
             assert_eq!(w.len(), self.m, "w length does not match self.m");
 
             for (i, prg) in prgs.iter_mut().enumerate() {
                 assert!(i < self.m, "Index out of bounds: i = {}, self.m = {}", i, self.m);
-                prg.random_stark252_elements(&mut [w[i]]);
+                prg.random_field_elements(&mut [w[i]]);
             }
         }
 
         // Receive v from the receiver
-        let mut v = io.receive_stark252(self.m).expect("Failed to receive v");
+        let mut v = io.receive_field_elements::<F>(self.m).await?;
 
         // Adjust v based on delta_bool
         for i in 0..self.m {
@@ -150,24 +175,29 @@ impl Cope {
         }
 
         // Aggregate v into a single field element
-        self.prm2pr(&v)
+        Ok(self.prm2pr(&v))
+    }
+
+    pub fn extend_sender_batch<IO: CommunicationChannel>(&mut self, io: &mut IO, ret: &mut [FieldElement<K>], size: usize) -> Result<()> {
+        block_on(self.extend_sender_batch_async(&mut SyncChannel(io), ret, size))
     }
 
-    pub fn extend_sender_batch<IO: CommunicationChannel>(&mut self, io: &mut IO, ret: &mut [FE], size: usize) {
+    /// Async variant of `extend_sender_batch`.
+    pub async fn extend_sender_batch_async<IO: AsyncCommunicationChannel>(&mut self, io: &mut IO, ret: &mut [FieldElement<K>], size: usize) -> Result<()> {
         // Generate ret_recv = ret_send + delta * u_recv
 
-        let mut w = vec![vec![FE::zero(); size]; self.m];
-        let mut v = vec![vec![FE::zero(); size]; self.m];
+        let mut w = vec![vec![FieldElement::<F>::zero(); size]; self.m];
+        let mut v = vec![vec![FieldElement::<F>::zero(); size]; self.m];
 
         // Generate random w values for the batch
         if let Some(prgs) = &mut self.prg_g0 {
             for (i, prg) in prgs.iter_mut().enumerate() {
-                prg.random_stark252_elements(&mut w[i]);
+                prg.random_field_elements(&mut w[i]);
             }
         }
 
         // Receive v values from the receiver
-        let received_data = io.receive_stark252(self.m * size).expect("Failed to receive v");
+        let received_data = io.receive_field_elements::<F>(self.m * size).await?;
         for i in 0..self.m {
             for j in 0..size {
                 v[i][j] = received_data[i * size + j];
@@ -187,18 +217,24 @@ impl Cope {
 
         // Aggregate batch results into ret
         self.prm2pr_batch(ret, &v);
+        Ok(())
     }
 
-    pub fn extend_receiver<IO: CommunicationChannel>(&mut self, io: &mut IO, u: FE) -> FE {
-        let mut w0 = vec![FE::zero(); self.m];
-        let mut w1 = vec![FE::zero(); self.m];
-        let mut tau = vec![FE::zero(); self.m];
+    pub fn extend_receiver<IO: CommunicationChannel>(&mut self, io: &mut IO, u: FieldElement<F>) -> Result<FieldElement<K>> {
+        block_on(self.extend_receiver_async(&mut SyncChannel(io), u))
+    }
+
+    /// Async variant of `extend_receiver`.
+    pub async fn extend_receiver_async<IO: AsyncCommunicationChannel>(&mut self, io: &mut IO, u: FieldElement<F>) -> Result<FieldElement<K>> {
+        let mut w0 = vec![FieldElement::<F>::zero(); self.m];
+        let mut w1 = vec![FieldElement::<F>::zero(); self.m];
+        let mut tau = vec![FieldElement::<F>::zero(); self.m];
 
         // Generate random w0 and w1 values
         if let (Some(prgs_g0), Some(prgs_g1)) = (&mut self.prg_g0, &mut self.prg_g1) {
             for i in 0..self.m {
-                prgs_g0[i].random_stark252_elements(&mut [w0[i]]);
-                prgs_g1[i].random_stark252_elements(&mut [w1[i]]);
+                prgs_g0[i].random_field_elements(&mut [w0[i]]);
+                prgs_g1[i].random_field_elements(&mut [w1[i]]);
 
                 w1[i] = w1[i] + u;
                 tau[i] = w0[i] - w1[i];
@@ -206,27 +242,30 @@ impl Cope {
         }
 
         // Send tau to the sender
-        io.send_stark252(&tau).expect("Failed to send tau");
+        io.send_field_elements(&tau).await?;
+        io.flush().await?;
 
         // Aggregate w0 into a single field element
-        self.prm2pr(&w0)
+        Ok(self.prm2pr(&w0))
     }
 
-    pub fn extend_receiver_batch<IO: CommunicationChannel>(&mut self, io: &mut IO, ret: &mut [FE], u: &[FE], size: usize) {
-        // Generate ret_recv = ret_send + delta * u_recv
-
-        let mut w0 = vec![vec![FE::zero(); size]; self.m];
-        let mut w1 = vec![vec![FE::zero(); size]; self.m];
-        let mut tau = vec![vec![FE::zero(); size]; self.m];
+    pub fn extend_receiver_batch<IO: CommunicationChannel>(&mut self, io: &mut IO, ret: &mut [FieldElement<K>], u: &[FieldElement<F>], size: usize) -> Result<()> {
+        block_on(self.extend_receiver_batch_async(&mut SyncChannel(io), ret, u, size))
+    }
 
-        let start = Instant::now();
+    /// Async variant of `extend_receiver_batch`.
+    pub async fn extend_receiver_batch_async<IO: AsyncCommunicationChannel>(&mut self, io: &mut IO, ret: &mut [FieldElement<K>], u: &[FieldElement<F>], size: usize) -> Result<()> {
+        // Generate ret_recv = ret_send + delta * u_recv
 
+        let mut w0 = vec![vec![FieldElement::<F>::zero(); size]; self.m];
+        let mut w1 = vec![vec![FieldElement::<F>::zero(); size]; self.m];
+        let mut tau = vec![vec![FieldElement::<F>::zero(); size]; self.m];
 
         // Generate random w0 and w1 values
         if let (Some(prgs_g0), Some(prgs_g1)) = (&mut self.prg_g0, &mut self.prg_g1) {
             for i in 0..self.m {
-                prgs_g0[i].random_stark252_elements(&mut w0[i]);
-                prgs_g1[i].random_stark252_elements(&mut w1[i]);
+                prgs_g0[i].random_field_elements(&mut w0[i]);
+                prgs_g1[i].random_field_elements(&mut w1[i]);
 
                 for j in 0..size {
                     w1[i][j] = w1[i][j] + u[j];
@@ -235,61 +274,67 @@ impl Cope {
             }
         }
 
-        let duration = start.elapsed();
-        println!("Time to generate {} random elements: {:?}", self.m * size * 2, duration);
 
 
         // Send tau to the sender
-        let tau_flat: Vec<FE> = tau.iter().flat_map(|row| row.iter().cloned()).collect();
+        let tau_flat: Vec<FieldElement<F>> = tau.iter().flat_map(|row| row.iter().cloned()).collect();
         // println!("The number of elements in tau_flat sent: {:?}", tau_flat.clone().len());
 
         // assert_eq!(tau_flat.clone().len(), self.m * size, "tau_flat mismatch type");
 
-        io.send_stark252(&tau_flat).expect("Failed to send tau");
-        io.flush();
+        io.send_field_elements(&tau_flat).await?;
+        io.flush().await?;
 
         // Aggregate w0 batch results into ret
         self.prm2pr_batch(ret, &w0);
+        Ok(())
     }
 
 
     /// Aggregates a vector of field elements into a single field element using precomputed powers of two.
-    fn prm2pr(&self, elements: &[FE]) -> FE {
+    fn prm2pr(&self, elements: &[FieldElement<F>]) -> FieldElement<K> {
         elements
             .iter()
             .zip(&self.powers_of_two)
-            .fold(FE::zero(), |acc, (e, power)| acc + (*e * *power))
+            .fold(FieldElement::zero(), |acc, (e, power)| acc + F::scale(e, power))
     }
 
     /// Aggregates a batch of vectors of field elements into a result array using precomputed powers of two.
-    fn prm2pr_batch(&self, ret: &mut [FE], elements: &[Vec<FE>]) {
+    fn prm2pr_batch(&self, ret: &mut [FieldElement<K>], elements: &[Vec<FieldElement<F>>]) {
         for (j, result) in ret.iter_mut().enumerate() {
-            *result = elements.iter().zip(&self.powers_of_two).fold(FE::zero(), |acc, (row, power)| {
-                acc + (row[j] * *power)
+            *result = elements.iter().zip(&self.powers_of_two).fold(FieldElement::zero(), |acc, (row, power)| {
+                acc + F::scale(&row[j], power)
             });
         }
     }
+}
 
+impl<F: VoleSubfield<F>> Cope<F, F> {
     // Debug
-    pub fn check_triple<IO: CommunicationChannel>(&mut self, io: &mut IO, a: &[FE], b: &[FE], sz: usize) {
+    pub fn check_triple<IO: CommunicationChannel>(&mut self, io: &mut IO, a: &[FieldElement<F>], b: &[FieldElement<F>], sz: usize) -> Result<()> {
+        block_on(self.check_triple_async(&mut SyncChannel(io), a, b, sz))
+    }
+
+    /// Async variant of `check_triple`.
+    pub async fn check_triple_async<IO: AsyncCommunicationChannel>(&mut self, io: &mut IO, a: &[FieldElement<F>], b: &[FieldElement<F>], sz: usize) -> Result<()> {
         if self.party == 0 {
             // Sender's role
-            io.send_stark252(a).expect("Failed to send `a` in check_triple");
-            io.send_stark252(b).expect("Failed to send `b` in check_triple");
+            io.send_field_elements(a).await?;
+            io.send_field_elements(b).await?;
+            io.flush().await?;
         } else {
             // Receiver's role
-            let delta = io.receive_stark252(1).expect("Failed to receive `delta` in check_triple")[0];
-            let c = io.receive_stark252(sz).expect("Failed to receive `c` in check_triple");
+            let delta = io.receive_field_elements::<F>(1).await?[0];
+            let c = io.receive_field_elements::<F>(sz).await?;
 
             // Perform the consistency check
             for i in 0..sz {
                 // let tmp = b[i] - (delta * c[i]); // Rearranged: b[i] == delta * c[i]
                 if b[i] != a[i] * delta + c[i] {
-                    eprintln!("Consistency check failed at index {}", i);
-                    panic!("Consistency check failed");
+                    return Err(VoleError::ConsistencyCheck(CheckFailure::Triple(i)));
                 }
             }
-            println!("Consistency check passed");
         }
+        Ok(())
     }
 }
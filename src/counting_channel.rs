@@ -0,0 +1,277 @@
+use crate::comm_channel::{CommunicationChannel, FE};
+use crate::async_comm_channel::AsyncCommunicationChannel;
+use crate::field::VoleField;
+use crate::error::Result;
+
+use p256::EncodedPoint;
+use lambdaworks_math::field::element::FieldElement;
+use std::fmt;
+use std::ops::{Add, AddAssign};
+
+/// Traffic seen by one party.
+///
+/// Bytes are payload bytes, the framing of the underlying channel is not counted,
+/// so the numbers compare directly with the communication reported in papers.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub struct CommStats {
+    pub bytes_sent: u64,
+    pub bytes_received: u64,
+    pub messages_sent: u64,
+    pub messages_received: u64,
+    /// Switches between sending and receiving, the first message counts as one.
+    pub direction_changes: u64,
+}
+
+impl CommStats {
+    pub fn total_bytes(&self) -> u64 {
+        self.bytes_sent + self.bytes_received
+    }
+
+    /// Traffic since the `earlier` snapshot, `None` when a counter went back, after a `reset` say.
+    pub fn since(&self, earlier: &CommStats) -> Option<CommStats> {
+        Some(CommStats {
+            bytes_sent: self.bytes_sent.checked_sub(earlier.bytes_sent)?,
+            bytes_received: self.bytes_received.checked_sub(earlier.bytes_received)?,
+            messages_sent: self.messages_sent.checked_sub(earlier.messages_sent)?,
+            messages_received: self.messages_received.checked_sub(earlier.messages_received)?,
+            direction_changes: self.direction_changes.checked_sub(earlier.direction_changes)?,
+        })
+    }
+}
+
+impl Add for CommStats {
+    type Output = CommStats;
+
+    fn add(self, other: CommStats) -> CommStats {
+        CommStats {
+            bytes_sent: self.bytes_sent + other.bytes_sent,
+            bytes_received: self.bytes_received + other.bytes_received,
+            messages_sent: self.messages_sent + other.messages_sent,
+            messages_received: self.messages_received + other.messages_received,
+            direction_changes: self.direction_changes + other.direction_changes,
+        }
+    }
+}
+
+impl AddAssign for CommStats {
+    fn add_assign(&mut self, other: CommStats) {
+        *self = *self + other;
+    }
+}
+
+impl fmt::Display for CommStats {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(
+            f,
+            "sent {} B in {} msgs, received {} B in {} msgs, {} direction changes",
+            self.bytes_sent, self.messages_sent, self.bytes_received, self.messages_received, self.direction_changes
+        )
+    }
+}
+
+#[derive(Clone, Copy, PartialEq)]
+enum Direction {
+    Sending,
+    Receiving,
+}
+
+/// Wraps any channel, sync or async, and counts what goes through it.
+///
+/// Take a `snapshot` before a phase and subtract it from the one after, or
+/// `reset` between phases.
+pub struct CountingChannel<C> {
+    inner: C,
+    stats: CommStats,
+    last: Option<Direction>,
+}
+
+impl<C> CountingChannel<C> {
+    pub fn new(inner: C) -> Self {
+        Self { inner, stats: CommStats::default(), last: None }
+    }
+
+    /// The counters so far
+    pub fn snapshot(&self) -> CommStats {
+        self.stats
+    }
+
+    /// Returns the counters so far and starts again from zero
+    pub fn reset(&mut self) -> CommStats {
+        self.last = None;
+        std::mem::take(&mut self.stats)
+    }
+
+    pub fn inner(&self) -> &C {
+        &self.inner
+    }
+
+    pub fn inner_mut(&mut self) -> &mut C {
+        &mut self.inner
+    }
+
+    pub fn into_inner(self) -> C {
+        self.inner
+    }
+
+    fn turn(&mut self, direction: Direction) {
+        if self.last != Some(direction) {
+            self.stats.direction_changes += 1;
+            self.last = Some(direction);
+        }
+    }
+
+    fn count_sent<T>(&mut self, res: Result<T>, bytes: usize) -> Result<T> {
+        if res.is_ok() {
+            self.turn(Direction::Sending);
+            self.stats.bytes_sent += bytes as u64;
+            self.stats.messages_sent += 1;
+        }
+        res
+    }
+
+    fn count_received<T>(&mut self, res: Result<T>, bytes: impl FnOnce(&T) -> usize) -> Result<T> {
+        if let Ok(value) = &res {
+            let bytes = bytes(value);
+            self.turn(Direction::Receiving);
+            self.stats.bytes_received += bytes as u64;
+            self.stats.messages_received += 1;
+        }
+        res
+    }
+}
+
+impl<C: CommunicationChannel> CommunicationChannel for CountingChannel<C> {
+    fn send_32byte_block(&mut self, data: &[[u8; 32]]) -> Result<()> {
+        let res = self.inner.send_32byte_block(data);
+        self.count_sent(res, data.len() * 32)
+    }
+
+    fn receive_32byte_block(&mut self) -> Result<Vec<[u8; 32]>> {
+        let res = self.inner.receive_32byte_block();
+        self.count_received(res, |data| data.len() * 32)
+    }
+
+    fn send_bits(&mut self, bits: &[bool]) -> Result<()> {
+        let res = self.inner.send_bits(bits);
+        self.count_sent(res, (bits.len() + 7) / 8)
+    }
+
+    fn receive_bits(&mut self) -> Result<Vec<bool>> {
+        let res = self.inner.receive_bits();
+        self.count_received(res, |bits| (bits.len() + 7) / 8)
+    }
+
+    fn send_stark252(&mut self, elements: &[FE]) -> Result<()> {
+        CommunicationChannel::send_field_elements(self, elements)
+    }
+
+    fn receive_stark252(&mut self, count: usize) -> Result<Vec<FE>> {
+        CommunicationChannel::receive_field_elements(self, count)
+    }
+
+    fn send_field_elements<F: VoleField>(&mut self, elements: &[FieldElement<F>]) -> Result<()> {
+        let res = self.inner.send_field_elements(elements);
+        self.count_sent(res, elements.len() * F::BYTE_LEN)
+    }
+
+    fn receive_field_elements<F: VoleField>(&mut self, count: usize) -> Result<Vec<FieldElement<F>>> {
+        let res = self.inner.receive_field_elements::<F>(count);
+        self.count_received(res, |elements| elements.len() * F::BYTE_LEN)
+    }
+
+    fn send_point(&mut self, point: &EncodedPoint) -> Result<()> {
+        let res = self.inner.send_point(point);
+        self.count_sent(res, point.as_bytes().len())
+    }
+
+    fn receive_point(&mut self) -> Result<EncodedPoint> {
+        let res = self.inner.receive_point();
+        self.count_received(res, |point| point.as_bytes().len())
+    }
+
+    fn send_data(&mut self, data: &[[u8; 16]]) -> Result<()> {
+        let res = self.inner.send_data(data);
+        self.count_sent(res, data.len() * 16)
+    }
+
+    fn receive_data(&mut self) -> Result<Vec<[u8; 16]>> {
+        let res = self.inner.receive_data();
+        self.count_received(res, |data| data.len() * 16)
+    }
+
+    fn flush(&mut self) -> Result<()> {
+        self.inner.flush()
+    }
+
+    fn stats(&self) -> Option<CommStats> {
+        Some(self.stats)
+    }
+}
+
+impl<C: AsyncCommunicationChannel> AsyncCommunicationChannel for CountingChannel<C> {
+    async fn send_32byte_block(&mut self, data: &[[u8; 32]]) -> Result<()> {
+        let res = self.inner.send_32byte_block(data).await;
+        self.count_sent(res, data.len() * 32)
+    }
+
+    async fn receive_32byte_block(&mut self) -> Result<Vec<[u8; 32]>> {
+        let res = self.inner.receive_32byte_block().await;
+        self.count_received(res, |data| data.len() * 32)
+    }
+
+    async fn send_bits(&mut self, bits: &[bool]) -> Result<()> {
+        let res = self.inner.send_bits(bits).await;
+        self.count_sent(res, (bits.len() + 7) / 8)
+    }
+
+    async fn receive_bits(&mut self) -> Result<Vec<bool>> {
+        let res = self.inner.receive_bits().await;
+        self.count_received(res, |bits| (bits.len() + 7) / 8)
+    }
+
+    async fn send_stark252(&mut self, elements: &[FE]) -> Result<()> {
+        AsyncCommunicationChannel::send_field_elements(self, elements).await
+    }
+
+    async fn receive_stark252(&mut self, count: usize) -> Result<Vec<FE>> {
+        AsyncCommunicationChannel::receive_field_elements(self, count).await
+    }
+
+    async fn send_field_elements<F: VoleField>(&mut self, elements: &[FieldElement<F>]) -> Result<()> {
+        let res = self.inner.send_field_elements(elements).await;
+        self.count_sent(res, elements.len() * F::BYTE_LEN)
+    }
+
+    async fn receive_field_elements<F: VoleField>(&mut self, count: usize) -> Result<Vec<FieldElement<F>>> {
+        let res = self.inner.receive_field_elements::<F>(count).await;
+        self.count_received(res, |elements| elements.len() * F::BYTE_LEN)
+    }
+
+    async fn send_point(&mut self, point: &EncodedPoint) -> Result<()> {
+        let res = self.inner.send_point(point).await;
+        self.count_sent(res, point.as_bytes().len())
+    }
+
+    async fn receive_point(&mut self) -> Result<EncodedPoint> {
+        let res = self.inner.receive_point().await;
+        self.count_received(res, |point| point.as_bytes().len())
+    }
+
+    async fn send_data(&mut self, data: &[[u8; 16]]) -> Result<()> {
+        let res = self.inner.send_data(data).await;
+        self.count_sent(res, data.len() * 16)
+    }
+
+    async fn receive_data(&mut self) -> Result<Vec<[u8; 16]>> {
+        let res = self.inner.receive_data().await;
+        self.count_received(res, |data| data.len() * 16)
+    }
+
+    async fn flush(&mut self) -> Result<()> {
+        self.inner.flush().await
+    }
+
+    fn stats(&self) -> Option<CommStats> {
+        Some(self.stats)
+    }
+}
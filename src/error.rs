@@ -0,0 +1,84 @@
+use std::fmt;
+
+/// Errors returned by the channels and every protocol of the crate.
+#[derive(Debug)]
+pub enum VoleError {
+    /// The underlying transport failed, e.g. the peer hung up.
+    Io(std::io::Error),
+    /// The peer sent something that does not decode to what the protocol expects.
+    MalformedMessage(String),
+    /// The peer sent another message than the one being received, the parties are out of step.
+    Desync(String),
+    /// A consistency check failed, the peer is cheating or out of sync.
+    ConsistencyCheck(CheckFailure),
+    /// A check failed earlier, the instance refuses to produce more correlations.
+    Aborted(CheckFailure),
+    /// Invalid parameters or a call out of order.
+    Parameter(String),
+    /// The peer runs another protocol version, field, parameter set or security model.
+    Handshake(String),
+}
+
+/// Which check caught the peer.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum CheckFailure {
+    /// The IKNP correlation check of the OT extension.
+    Iknp,
+    /// The correlation check of the SoftSpokenOT extension.
+    SoftSpoken,
+    /// The random linear combination check of the base sVOLE.
+    BaseSvole,
+    /// The check of a single SPFSS instance.
+    Spfss,
+    /// The batched check over all SPFSS instances of an MPFSS call.
+    SpfssBatch,
+    /// The debug check of the output correlations, at this index.
+    Triple(usize),
+    /// The debug check of the output COTs, at this index.
+    Cot(usize),
+}
+
+pub type Result<T> = std::result::Result<T, VoleError>;
+
+impl fmt::Display for VoleError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            VoleError::Io(e) => write!(f, "I/O error: {}", e),
+            VoleError::MalformedMessage(msg) => write!(f, "malformed message: {}", msg),
+            VoleError::Desync(msg) => write!(f, "channel out of sync: {}", msg),
+            VoleError::ConsistencyCheck(check) => write!(f, "consistency check failed: {}", check),
+            VoleError::Aborted(check) => write!(f, "aborted after a failed check: {}", check),
+            VoleError::Parameter(msg) => write!(f, "parameter error: {}", msg),
+            VoleError::Handshake(msg) => write!(f, "handshake failed: {}", msg),
+        }
+    }
+}
+
+impl fmt::Display for CheckFailure {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            CheckFailure::Iknp => write!(f, "IKNP OT extension"),
+            CheckFailure::SoftSpoken => write!(f, "SoftSpokenOT extension"),
+            CheckFailure::BaseSvole => write!(f, "base sVOLE"),
+            CheckFailure::Spfss => write!(f, "SPFSS"),
+            CheckFailure::SpfssBatch => write!(f, "SPFSS batch check"),
+            CheckFailure::Triple(i) => write!(f, "triple at index {}", i),
+            CheckFailure::Cot(i) => write!(f, "COT at index {}", i),
+        }
+    }
+}
+
+impl std::error::Error for VoleError {
+    fn source(&self) -> Option<&(dyn std::error::Error + 'static)> {
+        match self {
+            VoleError::Io(e) => Some(e),
+            _ => None,
+        }
+    }
+}
+
+impl From<std::io::Error> for VoleError {
+    fn from(e: std::io::Error) -> Self {
+        VoleError::Io(e)
+    }
+}
@@ -0,0 +1,152 @@
+use lambdaworks_math::errors::ByteConversionError;
+use lambdaworks_math::field::element::FieldElement;
+use lambdaworks_math::field::errors::FieldError;
+use lambdaworks_math::field::traits::{IsField, IsPrimeField};
+use lambdaworks_math::field::fields::u64_goldilocks_field::Goldilocks64Field;
+use lambdaworks_math::field::fields::mersenne31::field::Mersenne31Field;
+use crate::field::{VoleField, VoleSubfield};
+use crate::fp61::Mersenne61Field;
+use std::marker::PhantomData;
+
+/// A base field with a fixed quadratic non-residue r, so that X^2 - r is irreducible.
+pub trait QuadraticNonResidue: VoleField + IsPrimeField {
+    fn non_residue() -> FieldElement<Self>;
+}
+
+/// Quadratic extension F[X]/(X^2 - r), the MAC field of subfield VOLE over small primes.
+///
+/// Elements are [a0, a1] for a0 + a1 X. The encoding is the two coordinates
+/// side by side, so `F::BYTE_LEN` must be at most 16.
+#[derive(Debug, Clone, Copy, Hash, PartialOrd, Ord, PartialEq, Eq)]
+pub struct Ext2<F>(PhantomData<F>);
+
+pub type Ext2Element<F> = FieldElement<Ext2<F>>;
+
+impl<F: QuadraticNonResidue> IsField for Ext2<F> {
+    type BaseType = [FieldElement<F>; 2];
+
+    fn add(a: &Self::BaseType, b: &Self::BaseType) -> Self::BaseType {
+        [a[0] + b[0], a[1] + b[1]]
+    }
+
+    /// (a0 + a1 X)(b0 + b1 X) = (a0 b0 + r a1 b1) + (a0 b1 + a1 b0) X
+    fn mul(a: &Self::BaseType, b: &Self::BaseType) -> Self::BaseType {
+        let a0b0 = a[0] * b[0];
+        let a1b1 = a[1] * b[1];
+        let z = (a[0] + a[1]) * (b[0] + b[1]);
+        [a0b0 + F::non_residue() * a1b1, z - a0b0 - a1b1]
+    }
+
+    fn sub(a: &Self::BaseType, b: &Self::BaseType) -> Self::BaseType {
+        [a[0] - b[0], a[1] - b[1]]
+    }
+
+    fn neg(a: &Self::BaseType) -> Self::BaseType {
+        [-a[0], -a[1]]
+    }
+
+    /// (a0 + a1 X)^-1 = (a0 - a1 X) / (a0^2 - r a1^2)
+    fn inv(a: &Self::BaseType) -> Result<Self::BaseType, FieldError> {
+        let norm = a[0] * a[0] - F::non_residue() * a[1] * a[1];
+        let norm_inv = norm.inv()?;
+        Ok([a[0] * norm_inv, -a[1] * norm_inv])
+    }
+
+    fn div(a: &Self::BaseType, b: &Self::BaseType) -> Self::BaseType {
+        let b_inv = Self::inv(b).expect("InvZeroError");
+        Self::mul(a, &b_inv)
+    }
+
+    fn eq(a: &Self::BaseType, b: &Self::BaseType) -> bool {
+        a[0] == b[0] && a[1] == b[1]
+    }
+
+    fn zero() -> Self::BaseType {
+        [FieldElement::zero(), FieldElement::zero()]
+    }
+
+    fn one() -> Self::BaseType {
+        [FieldElement::one(), FieldElement::zero()]
+    }
+
+    fn from_u64(x: u64) -> Self::BaseType {
+        [FieldElement::from(x), FieldElement::zero()]
+    }
+
+    fn from_base_type(x: Self::BaseType) -> Self::BaseType {
+        x
+    }
+}
+
+impl<F: QuadraticNonResidue> VoleField for Ext2<F> {
+    const NAME: &'static str = "ext2";
+    const BYTE_LEN: usize = 2 * F::BYTE_LEN;
+
+    fn to_bytes_le(x: &Ext2Element<F>) -> Vec<u8> {
+        let [a0, a1] = x.value();
+        let mut bytes = F::to_bytes_le(a0);
+        bytes.extend_from_slice(&F::to_bytes_le(a1));
+        bytes
+    }
+
+    fn from_bytes_le(bytes: &[u8]) -> Result<Ext2Element<F>, ByteConversionError> {
+        if bytes.len() != Self::BYTE_LEN {
+            return Err(ByteConversionError::FromLEBytesError);
+        }
+        let a0 = F::from_bytes_le(&bytes[..F::BYTE_LEN])?;
+        let a1 = F::from_bytes_le(&bytes[F::BYTE_LEN..])?;
+        Ok(FieldElement::new([a0, a1]))
+    }
+
+    fn from_random_bytes(bytes: &[u8; 32]) -> Ext2Element<F> {
+        // Each coordinate takes one half, small fields only read the first 16 bytes
+        let mut swapped = [0u8; 32];
+        swapped[..16].copy_from_slice(&bytes[16..]);
+        swapped[16..].copy_from_slice(&bytes[..16]);
+        FieldElement::new([F::from_random_bytes(bytes), F::from_random_bytes(&swapped)])
+    }
+}
+
+impl<F: QuadraticNonResidue> VoleSubfield<Ext2<F>> for F {
+    const DEGREE: usize = 2;
+
+    fn embed(x: &FieldElement<F>) -> Ext2Element<F> {
+        FieldElement::new([*x, FieldElement::zero()])
+    }
+
+    fn basis(i: usize) -> Ext2Element<F> {
+        let mut value = [FieldElement::zero(), FieldElement::zero()];
+        value[i] = FieldElement::one();
+        FieldElement::new(value)
+    }
+
+    fn coordinates(y: &Ext2Element<F>) -> Vec<FieldElement<F>> {
+        y.value().to_vec()
+    }
+
+    fn scale(x: &FieldElement<F>, y: &Ext2Element<F>) -> Ext2Element<F> {
+        let [y0, y1] = y.value();
+        FieldElement::new([*x * *y0, *x * *y1])
+    }
+}
+
+// p = 3 mod 4, so -1 is a non-residue
+impl QuadraticNonResidue for Mersenne31Field {
+    fn non_residue() -> FieldElement<Self> {
+        -FieldElement::one()
+    }
+}
+
+// p = 3 mod 4, so -1 is a non-residue
+impl QuadraticNonResidue for Mersenne61Field {
+    fn non_residue() -> FieldElement<Self> {
+        -FieldElement::one()
+    }
+}
+
+// 7 generates the multiplicative group, so it is a non-residue
+impl QuadraticNonResidue for Goldilocks64Field {
+    fn non_residue() -> FieldElement<Self> {
+        FieldElement::from(7)
+    }
+}
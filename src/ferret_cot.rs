@@ -0,0 +1,253 @@
+use crate::preot::OTPre;
+use crate::comm_channel::CommunicationChannel;
+use crate::base_cot::BaseCot;
+use crate::lpn_f2::LpnF2;
+use crate::mpcot_reg::MpcotReg;
+use crate::hash::Block;
+use crate::spcot_sender::xor_block_inplace;
+use crate::error::{CheckFailure, Result, VoleError};
+
+pub struct PrimalLPNParameterF2 {
+    n: usize,
+    t: usize,
+    k: usize,
+    log_bin_sz: usize,
+    n_pre: usize,
+    t_pre: usize,
+    k_pre: usize,
+    log_bin_sz_pre: usize,
+}
+
+impl PrimalLPNParameterF2 {
+    // Default constructor
+    pub fn new() -> Self {
+        Self {
+            n: 0,
+            t: 0,
+            k: 0,
+            log_bin_sz: 0,
+            n_pre: 0,
+            t_pre: 0,
+            k_pre: 0,
+            log_bin_sz_pre: 0,
+        }
+    }
+
+    // Parameterized constructor
+    pub fn with_params(
+        n: usize,
+        t: usize,
+        k: usize,
+        log_bin_sz: usize,
+        n_pre: usize,
+        t_pre: usize,
+        k_pre: usize,
+        log_bin_sz_pre: usize,
+    ) -> Result<Self> {
+        // Ensure parameters are valid
+        if n != t * (1 << log_bin_sz)
+            || n_pre != t_pre * (1 << log_bin_sz_pre)
+            || n_pre < k
+            || n <= k
+        {
+            return Err(VoleError::Parameter("LPN parameter not matched".to_string()));
+        }
+
+        Ok(Self {
+            n,
+            t,
+            k,
+            log_bin_sz,
+            n_pre,
+            t_pre,
+            k_pre,
+            log_bin_sz_pre,
+        })
+    }
+
+    // Compute buffer size
+    pub fn buf_sz(&self) -> usize {
+        self.n - self.k
+    }
+}
+
+// Ferret instances, from emp-ot
+pub const FERRET_B13: PrimalLPNParameterF2 = PrimalLPNParameterF2 {
+    n: 10485760,
+    t: 1280,
+    k: 452000,
+    log_bin_sz: 13,
+    n_pre: 470016,
+    t_pre: 918,
+    k_pre: 32768,
+    log_bin_sz_pre: 9,
+};
+
+pub const FERRET_B12: PrimalLPNParameterF2 = PrimalLPNParameterF2 {
+    n: 10268672,
+    t: 2507,
+    k: 238000,
+    log_bin_sz: 12,
+    n_pre: 268800,
+    t_pre: 1050,
+    k_pre: 17384,
+    log_bin_sz_pre: 8,
+};
+
+pub const FERRET_B11: PrimalLPNParameterF2 = PrimalLPNParameterF2 {
+    n: 10180608,
+    t: 4971,
+    k: 124000,
+    log_bin_sz: 11,
+    n_pre: 178944,
+    t_pre: 699,
+    k_pre: 17384,
+    log_bin_sz_pre: 8,
+};
+
+/// Ferret-style silent COT over GF(2^128), semi-honest only.
+///
+/// The sender (party 0) holds Δ, the correlation of its 128-bit IKNP COTs,
+/// and gets v; the receiver gets w = v ⊕ b·Δ, its choice bit b being the LSB of w.
+pub struct FerretCot {
+    party: usize,
+    param: PrimalLPNParameterF2,
+    ot_used: usize,
+    ot_limit: usize,
+    setup_done: bool,
+
+    ot_pre_data: Vec<Block>,
+    ot_data: Vec<Block>,
+
+    cot: BaseCot<16>,
+    pre_ot: OTPre<16>,
+    mpcot: MpcotReg,
+    lpn: LpnF2,
+
+    delta: Block,
+}
+
+impl FerretCot {
+    pub fn new<IO: CommunicationChannel>(party: usize, io: &mut IO, param: PrimalLPNParameterF2) -> Result<Self> {
+        let mut cot = BaseCot::<16>::new(party, false);
+        cot.cot_gen_pre(io, None)?;
+        let delta = cot.delta().unwrap_or([0u8; 16]);
+
+        let seed = [0u8; 16];
+        Ok(FerretCot {
+            party: party,
+            ot_used: 0,
+            ot_limit: param.n - param.k,
+            setup_done: false,
+
+            ot_pre_data: vec![[0u8; 16]; param.k],
+            ot_data: vec![[0u8; 16]; param.n],
+
+            cot: cot,
+            pre_ot: OTPre::new(param.log_bin_sz, param.t),
+            mpcot: MpcotReg::new(param.n, param.t, param.log_bin_sz, party),
+            lpn: LpnF2::new(param.k, param.n, &seed),
+
+            delta: delta,
+            param: param,
+        })
+    }
+
+    /// The sender's Δ, zero on the receiver side.
+    pub fn delta(&self) -> Block {
+        self.delta
+    }
+
+    /// Bootstrap the k pre-COTs of the main LPN instance from k_pre IKNP COTs.
+    pub fn setup<IO: CommunicationChannel>(&mut self, io: &mut IO) -> Result<()> {
+        let mut base_cot = vec![[0u8; 16]; self.param.k_pre];
+        self.cot.cot_gen(io, &mut base_cot, self.param.k_pre, None)?;
+
+        let seed_pre = [0u8; 16];
+        let lpn_pre = LpnF2::new(self.param.k_pre, self.param.n_pre, &seed_pre);
+        let mut mpcot_pre = MpcotReg::new(self.param.n_pre, self.param.t_pre, self.param.log_bin_sz_pre, self.party);
+        let mut pre_ot_ini = OTPre::new(self.param.log_bin_sz_pre, self.param.t_pre);
+
+        let mut pre_data = vec![[0u8; 16]; self.param.n_pre];
+        Self::extend_f2k(&mut self.cot, self.party, self.delta, io, &mut pre_data, &mut mpcot_pre, &mut pre_ot_ini, &lpn_pre, &base_cot, self.param.t_pre * self.param.log_bin_sz_pre)?;
+        self.ot_pre_data.copy_from_slice(&pre_data[..self.param.k]);
+
+        self.ot_used = self.ot_limit;
+        self.setup_done = true;
+        Ok(())
+    }
+
+    fn extend_f2k<IO: CommunicationChannel>(cot: &mut BaseCot<16>, party: usize, delta: Block, io: &mut IO, data: &mut [Block], mpcot: &mut MpcotReg, pre_ot: &mut OTPre<16>, lpn: &LpnF2, kk: &[Block], m: usize) -> Result<()> {
+        cot.cot_gen_preot(io, pre_ot, m, None)?;
+        if party == 0 {
+            mpcot.sender_init(delta);
+            mpcot.mpcot_sender(io, pre_ot, data)?;
+        } else {
+            mpcot.receiver_init();
+            mpcot.mpcot_receiver(io, pre_ot, data)?;
+        }
+        pre_ot.reset();
+
+        lpn.compute(data, kk);
+        Ok(())
+    }
+
+    /// Refill the buffer with n fresh COTs and recycle the last k as the next pre-COTs.
+    fn extend_once<IO: CommunicationChannel>(&mut self, io: &mut IO) -> Result<()> {
+        let m = self.param.t * self.param.log_bin_sz;
+        Self::extend_f2k(&mut self.cot, self.party, self.delta, io, &mut self.ot_data, &mut self.mpcot, &mut self.pre_ot, &self.lpn, &self.ot_pre_data, m)?;
+        self.ot_pre_data.copy_from_slice(&self.ot_data[self.ot_limit..]);
+        self.ot_used = 0;
+        Ok(())
+    }
+
+    /// Fill `data` with `num` random COTs.
+    pub fn extend<IO: CommunicationChannel>(&mut self, io: &mut IO, data: &mut [Block], num: usize) -> Result<()> {
+        if self.setup_done == false {
+            return Err(VoleError::Parameter("Run setup first!".to_string()));
+        }
+
+        let mut copied = 0;
+        while copied < num {
+            if self.silent_ot_left() == 0 {
+                self.extend_once(io)?;
+            }
+            let take = std::cmp::min(self.silent_ot_left(), num - copied);
+            data[copied..copied+take].copy_from_slice(&self.ot_data[self.ot_used..self.ot_used+take]);
+            self.ot_used += take;
+            copied += take;
+        }
+        Ok(())
+    }
+
+    pub fn silent_ot_left(&self) -> usize {
+        self.ot_limit - self.ot_used
+    }
+
+    // debug only
+    pub fn check_cot<IO: CommunicationChannel>(&self, io: &mut IO, data: &[Block], size: usize) -> Result<()> {
+        if self.party == 0 {
+            io.send_data(&[self.delta])?;
+            io.send_data(&data[..size])?;
+            io.flush()?;
+        } else {
+            // want w = v ^ lsb(w) * delta
+            let delta = *io.receive_data()?.first()
+                .ok_or_else(|| VoleError::MalformedMessage("Missing COT delta".to_string()))?;
+            let v = io.receive_data()?;
+            if v.len() < size {
+                return Err(VoleError::MalformedMessage("Too few COTs to check".to_string()));
+            }
+            for i in 0..size {
+                let mut w = v[i];
+                if data[i][0] & 1 == 1 {
+                    xor_block_inplace(&mut w, &delta);
+                }
+                if w != data[i] {
+                    return Err(VoleError::ConsistencyCheck(CheckFailure::Cot(i)));
+                }
+            }
+        }
+        Ok(())
+    }
+}
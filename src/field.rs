@@ -0,0 +1,174 @@
+use lambdaworks_math::field::fields::fft_friendly::stark_252_prime_field::Stark252PrimeField;
+use lambdaworks_math::field::fields::u64_goldilocks_field::Goldilocks64Field;
+use lambdaworks_math::field::fields::mersenne31::field::{Mersenne31Field, MERSENNE_31_PRIME_FIELD_ORDER};
+use lambdaworks_math::field::element::FieldElement;
+use lambdaworks_math::field::traits::{IsField, IsPrimeField};
+use lambdaworks_math::errors::ByteConversionError;
+use lambdaworks_math::traits::ByteConversion;
+use std::convert::TryInto;
+
+/// The default field of the sVOLE stack.
+pub type DefaultField = Stark252PrimeField;
+
+/// A field the sVOLE stack can run over.
+///
+/// Elements are carried in 32-byte blocks, so every element must have an encoding of
+/// at most 32 bytes. The GGM trees do not rely on the size of the field: their nodes
+/// are 128-bit blocks, and each leaf is mapped into the field from `RANDOM_BYTES`
+/// pseudorandom bytes, which must be at least 16 so the map keeps 128 bits of entropy.
+pub trait VoleField: IsField<BaseType: Copy + Send + Sync> + Copy + Send + Sync + 'static {
+    /// Short name of the field.
+    const NAME: &'static str;
+    /// Length in bytes of the little-endian encoding.
+    const BYTE_LEN: usize;
+
+    /// Little-endian encoding of `x`, exactly `BYTE_LEN` bytes long.
+    fn to_bytes_le(x: &FieldElement<Self>) -> Vec<u8>;
+
+    /// Decode an element from its little-endian encoding.
+    fn from_bytes_le(bytes: &[u8]) -> Result<FieldElement<Self>, ByteConversionError>;
+
+    /// Leading bytes of its input that `from_random_bytes` reads, the rest may be left zero.
+    /// Between 16 and 32.
+    const RANDOM_BYTES: usize = 32;
+
+    /// Map 32 pseudorandom bytes to a field element.
+    fn from_random_bytes(bytes: &[u8; 32]) -> FieldElement<Self>;
+
+    /// Inner product of `a` and `b`.
+    fn inner_product(a: &[FieldElement<Self>], b: &[FieldElement<Self>]) -> FieldElement<Self> {
+        a.iter().zip(b).fold(FieldElement::zero(), |acc, (x, y)| acc + *x * *y)
+    }
+
+    /// Encode `x` into a zero padded 32-byte block.
+    fn to_block(x: &FieldElement<Self>) -> [u8; 32] {
+        let mut block = [0u8; 32];
+        block[..Self::BYTE_LEN].copy_from_slice(&Self::to_bytes_le(x));
+        block
+    }
+
+    /// Decode an element from a block produced by `to_block`.
+    fn from_block(block: &[u8; 32]) -> Result<FieldElement<Self>, ByteConversionError> {
+        Self::from_bytes_le(&block[..Self::BYTE_LEN])
+    }
+}
+
+impl VoleField for Stark252PrimeField {
+    const NAME: &'static str = "stark252";
+    const BYTE_LEN: usize = 32;
+
+    fn to_bytes_le(x: &FieldElement<Self>) -> Vec<u8> {
+        x.to_bytes_le().to_vec()
+    }
+
+    fn from_bytes_le(bytes: &[u8]) -> Result<FieldElement<Self>, ByteConversionError> {
+        <FieldElement<Self> as ByteConversion>::from_bytes_le(bytes)
+    }
+
+    fn from_random_bytes(bytes: &[u8; 32]) -> FieldElement<Self> {
+        <FieldElement<Self> as ByteConversion>::from_bytes_le(bytes).expect("32 bytes always fit in 4 limbs")
+    }
+
+    fn to_block(x: &FieldElement<Self>) -> [u8; 32] {
+        x.to_bytes_le()
+    }
+}
+
+impl VoleField for Goldilocks64Field {
+    const NAME: &'static str = "goldilocks64";
+    const BYTE_LEN: usize = 8;
+    const RANDOM_BYTES: usize = 16;
+
+    fn to_bytes_le(x: &FieldElement<Self>) -> Vec<u8> {
+        x.representative().to_le_bytes().to_vec()
+    }
+
+    fn from_bytes_le(bytes: &[u8]) -> Result<FieldElement<Self>, ByteConversionError> {
+        let bytes: [u8; 8] = bytes.try_into().map_err(|_| ByteConversionError::FromLEBytesError)?;
+        let value = u64::from_le_bytes(bytes);
+        if value >= Goldilocks64Field::ORDER {
+            return Err(ByteConversionError::InvalidValue);
+        }
+        Ok(FieldElement::from(value))
+    }
+
+    fn from_random_bytes(bytes: &[u8; 32]) -> FieldElement<Self> {
+        // Reduce 128 bits to keep the bias negligible
+        let value = u128::from_le_bytes(bytes[..16].try_into().unwrap());
+        FieldElement::from((value % Goldilocks64Field::ORDER as u128) as u64)
+    }
+}
+
+impl VoleField for Mersenne31Field {
+    const NAME: &'static str = "mersenne31";
+    const BYTE_LEN: usize = 4;
+    const RANDOM_BYTES: usize = 16;
+
+    fn to_bytes_le(x: &FieldElement<Self>) -> Vec<u8> {
+        x.representative().to_le_bytes().to_vec()
+    }
+
+    fn from_bytes_le(bytes: &[u8]) -> Result<FieldElement<Self>, ByteConversionError> {
+        let bytes: [u8; 4] = bytes.try_into().map_err(|_| ByteConversionError::FromLEBytesError)?;
+        let value = u32::from_le_bytes(bytes);
+        if value >= MERSENNE_31_PRIME_FIELD_ORDER {
+            return Err(ByteConversionError::InvalidValue);
+        }
+        Ok(FieldElement::from(value as u64))
+    }
+
+    fn from_random_bytes(bytes: &[u8; 32]) -> FieldElement<Self> {
+        // Reduce 128 bits to keep the bias negligible
+        let value = u128::from_le_bytes(bytes[..16].try_into().unwrap());
+        FieldElement::from((value % MERSENNE_31_PRIME_FIELD_ORDER as u128) as u64)
+    }
+}
+
+/// `Self` as a subfield of the MAC field `K`, for subfield VOLE.
+///
+/// The noise and the receiver's u live in `Self`, while Δ, keys and MACs live in `K`.
+/// Every prime field is a subfield of itself, which gives the plain VOLE.
+pub trait VoleSubfield<K: VoleField>: VoleField + IsPrimeField {
+    /// Degree of `K` over `Self`.
+    const DEGREE: usize;
+
+    /// Embed `x` into `K`.
+    fn embed(x: &FieldElement<Self>) -> FieldElement<K>;
+
+    /// The `i`-th element of the basis of `K` over `Self`.
+    fn basis(i: usize) -> FieldElement<K>;
+
+    /// Coordinates of `y` in the basis, `DEGREE` of them.
+    fn coordinates(y: &FieldElement<K>) -> Vec<FieldElement<Self>>;
+
+    /// Multiply a `K` element by a subfield element.
+    fn scale(x: &FieldElement<Self>, y: &FieldElement<K>) -> FieldElement<K> {
+        Self::embed(x) * *y
+    }
+
+    /// Pack `DEGREE` elements as sum of `ys[i] * basis(i)`, so that `DEGREE` subfield
+    /// VOLE correlations become a single one with u in `K`.
+    fn compose(ys: &[FieldElement<K>]) -> FieldElement<K> {
+        ys.iter().enumerate().fold(FieldElement::zero(), |acc, (i, y)| acc + Self::basis(i) * *y)
+    }
+}
+
+impl<F: VoleField + IsPrimeField> VoleSubfield<F> for F {
+    const DEGREE: usize = 1;
+
+    fn embed(x: &FieldElement<F>) -> FieldElement<F> {
+        *x
+    }
+
+    fn basis(i: usize) -> FieldElement<F> {
+        FieldElement::one()
+    }
+
+    fn coordinates(y: &FieldElement<F>) -> Vec<FieldElement<F>> {
+        vec![*y]
+    }
+
+    fn scale(x: &FieldElement<F>, y: &FieldElement<F>) -> FieldElement<F> {
+        *x * *y
+    }
+}
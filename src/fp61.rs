@@ -0,0 +1,170 @@
+use lambdaworks_math::errors::{ByteConversionError, CreationError};
+use lambdaworks_math::field::element::FieldElement;
+use lambdaworks_math::field::errors::FieldError;
+use lambdaworks_math::field::traits::{IsField, IsPrimeField};
+use crate::field::VoleField;
+use std::convert::TryInto;
+
+/// p = 2^61 - 1
+pub const MERSENNE_61_PRIME_FIELD_ORDER: u64 = (1 << 61) - 1;
+
+/// Native Mersenne-61 field, the field emp-zk's Fp61 parameters were designed for.
+///
+/// Elements are kept weakly reduced in [0, p], p being a second representative
+/// of zero, so additions and multiplications need at most one fold.
+#[derive(Debug, Clone, Copy, Hash, PartialOrd, Ord, PartialEq, Eq)]
+pub struct Mersenne61Field;
+
+pub type Fp61 = FieldElement<Mersenne61Field>;
+
+impl Mersenne61Field {
+    /// Reduce a value below 2^62 into [0, p].
+    #[inline(always)]
+    fn weak_reduce(n: u64) -> u64 {
+        (n & MERSENNE_61_PRIME_FIELD_ORDER) + (n >> 61)
+    }
+
+    /// Reduce any u128 into [0, p] with two folds.
+    #[inline(always)]
+    fn reduce_u128(n: u128) -> u64 {
+        const P: u128 = MERSENNE_61_PRIME_FIELD_ORDER as u128;
+        let t = (n & P) + (n >> 61);
+        let t = (t & P) + (t >> 61);
+        Self::weak_reduce(t as u64)
+    }
+
+    #[inline(always)]
+    fn as_representative(n: &u64) -> u64 {
+        if *n == MERSENNE_61_PRIME_FIELD_ORDER {
+            0
+        } else {
+            *n
+        }
+    }
+}
+
+impl IsField for Mersenne61Field {
+    type BaseType = u64;
+
+    /// Returns the sum of `a` and `b`.
+    #[inline(always)]
+    fn add(a: &u64, b: &u64) -> u64 {
+        // a + b < 2^62, one fold brings it back to [0, p]
+        Self::weak_reduce(a + b)
+    }
+
+    /// Returns the multiplication of `a` and `b`.
+    #[inline(always)]
+    fn mul(a: &u64, b: &u64) -> u64 {
+        Self::reduce_u128(*a as u128 * *b as u128)
+    }
+
+    #[inline(always)]
+    fn sub(a: &u64, b: &u64) -> u64 {
+        // p - b is in [0, p], so no underflow
+        Self::weak_reduce(a + (MERSENNE_61_PRIME_FIELD_ORDER - b))
+    }
+
+    #[inline(always)]
+    fn neg(a: &u64) -> u64 {
+        MERSENNE_61_PRIME_FIELD_ORDER - a
+    }
+
+    /// Returns the multiplicative inverse of `a`, computed as a^(p-2).
+    fn inv(a: &u64) -> Result<u64, FieldError> {
+        if Self::as_representative(a) == 0 {
+            return Err(FieldError::InvZeroError);
+        }
+        Ok(Self::pow(a, MERSENNE_61_PRIME_FIELD_ORDER - 2))
+    }
+
+    /// Returns the division of `a` and `b`.
+    fn div(a: &u64, b: &u64) -> u64 {
+        let b_inv = Self::inv(b).expect("InvZeroError");
+        Self::mul(a, &b_inv)
+    }
+
+    /// Returns a boolean indicating whether `a` and `b` are equal or not.
+    fn eq(a: &u64, b: &u64) -> bool {
+        Self::as_representative(a) == Self::as_representative(b)
+    }
+
+    fn zero() -> u64 {
+        0u64
+    }
+
+    fn one() -> u64 {
+        1u64
+    }
+
+    /// Returns the element `x * 1` where 1 is the multiplicative neutral element.
+    fn from_u64(x: u64) -> u64 {
+        // 2^61 = 1 (mod p), x >> 61 < 8
+        Self::weak_reduce((x & MERSENNE_61_PRIME_FIELD_ORDER) + (x >> 61))
+    }
+
+    fn from_base_type(x: u64) -> u64 {
+        Self::from_u64(x)
+    }
+}
+
+impl IsPrimeField for Mersenne61Field {
+    type RepresentativeType = u64;
+
+    fn representative(x: &u64) -> u64 {
+        Self::as_representative(x)
+    }
+
+    fn field_bit_size() -> usize {
+        61
+    }
+
+    fn from_hex(hex_string: &str) -> Result<Self::BaseType, CreationError> {
+        let hex_string = hex_string.strip_prefix("0x").unwrap_or(hex_string);
+        let value = u64::from_str_radix(hex_string, 16).map_err(|_| CreationError::InvalidHexString)?;
+        Ok(Self::from_u64(value))
+    }
+
+    fn to_hex(x: &u64) -> String {
+        format!("{:X}", Self::as_representative(x))
+    }
+}
+
+impl VoleField for Mersenne61Field {
+    const NAME: &'static str = "mersenne61";
+    const BYTE_LEN: usize = 8;
+    const RANDOM_BYTES: usize = 16;
+
+    fn to_bytes_le(x: &Fp61) -> Vec<u8> {
+        x.representative().to_le_bytes().to_vec()
+    }
+
+    fn from_bytes_le(bytes: &[u8]) -> Result<Fp61, ByteConversionError> {
+        let bytes: [u8; 8] = bytes.try_into().map_err(|_| ByteConversionError::FromLEBytesError)?;
+        let value = u64::from_le_bytes(bytes);
+        if value >= MERSENNE_61_PRIME_FIELD_ORDER {
+            return Err(ByteConversionError::InvalidValue);
+        }
+        Ok(Fp61::from_raw(value))
+    }
+
+    fn from_random_bytes(bytes: &[u8; 32]) -> Fp61 {
+        // Reduce 128 bits to keep the bias negligible
+        let value = u128::from_le_bytes(bytes[..16].try_into().unwrap());
+        Fp61::from_raw(Self::reduce_u128(value))
+    }
+
+    /// Accumulate the products in a u128 and reduce once every 64 terms.
+    fn inner_product(a: &[Fp61], b: &[Fp61]) -> Fp61 {
+        let mut acc = 0u64;
+        for (ca, cb) in a.chunks(64).zip(b.chunks(64)) {
+            // each product is below 2^122, 64 of them fit in a u128
+            let mut sum = 0u128;
+            for (x, y) in ca.iter().zip(cb) {
+                sum += *x.value() as u128 * *y.value() as u128;
+            }
+            acc = Self::add(&acc, &Self::reduce_u128(sum));
+        }
+        Fp61::from_raw(acc)
+    }
+}
@@ -0,0 +1,243 @@
+use crate::field::VoleField;
+use crate::error::{Result, VoleError};
+
+use p256::EncodedPoint;
+use lambdaworks_math::field::element::FieldElement;
+use std::fmt;
+
+/// Bytes in front of every payload: kind tag, session id, sequence number, payload length.
+pub const HEADER_LEN: usize = 17;
+
+/// Largest payload a frame may announce, 1 GiB.
+///
+/// Receivers allocate the announced length before reading the payload, so a longer one
+/// is refused as malformed instead of letting the peer exhaust our memory. It leaves room
+/// for the largest messages of the protocols, e.g. a `check_triple` over a whole round.
+pub const MAX_FRAME_LEN: usize = 1 << 30;
+
+/// What a message carries, one per `send_*` method of the channels.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum MessageKind {
+    Block32 = 0,
+    Bits = 1,
+    FieldElements = 2,
+    Point = 3,
+    Data = 4,
+}
+
+impl MessageKind {
+    pub fn from_tag(tag: u8) -> Option<Self> {
+        match tag {
+            0 => Some(MessageKind::Block32),
+            1 => Some(MessageKind::Bits),
+            2 => Some(MessageKind::FieldElements),
+            3 => Some(MessageKind::Point),
+            4 => Some(MessageKind::Data),
+            _ => None,
+        }
+    }
+}
+
+impl fmt::Display for MessageKind {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            MessageKind::Block32 => write!(f, "32-byte blocks"),
+            MessageKind::Bits => write!(f, "bits"),
+            MessageKind::FieldElements => write!(f, "field elements"),
+            MessageKind::Point => write!(f, "a point"),
+            MessageKind::Data => write!(f, "16-byte blocks"),
+        }
+    }
+}
+
+/// Builds the headers of outgoing frames and checks those of incoming ones.
+///
+/// Frames are numbered from 0 in each direction, so a receive called for the wrong
+/// kind of message, or a frame from another session, gives a `Desync` error instead
+/// of reading the payload as something it is not. Both ends must use the same session id.
+#[derive(Debug, Clone, Default)]
+pub struct Framer {
+    session: u32,
+    sent: u32,
+    received: u32,
+}
+
+impl Framer {
+    pub fn new(session: u32) -> Self {
+        Self { session, sent: 0, received: 0 }
+    }
+
+    /// The header of the next outgoing frame
+    pub fn header(&mut self, kind: MessageKind, payload_len: usize) -> [u8; HEADER_LEN] {
+        let mut header = [0u8; HEADER_LEN];
+        header[0] = kind as u8;
+        header[1..5].copy_from_slice(&self.session.to_le_bytes());
+        header[5..9].copy_from_slice(&self.sent.to_le_bytes());
+        header[9..].copy_from_slice(&(payload_len as u64).to_le_bytes());
+        self.sent = self.sent.wrapping_add(1);
+        header
+    }
+
+    /// The next outgoing frame, header and payload
+    pub fn frame(&mut self, kind: MessageKind, payload: &[u8]) -> Vec<u8> {
+        let mut frame = Vec::with_capacity(HEADER_LEN + payload.len());
+        frame.extend_from_slice(&self.header(kind, payload.len()));
+        frame.extend_from_slice(payload);
+        frame
+    }
+
+    /// Checks the header of the next incoming frame and returns its payload length,
+    /// at most `MAX_FRAME_LEN`
+    pub fn check(&mut self, header: &[u8; HEADER_LEN], expected: MessageKind) -> Result<usize> {
+        let mut word = [0u8; 4];
+        word.copy_from_slice(&header[1..5]);
+        let session = u32::from_le_bytes(word);
+        word.copy_from_slice(&header[5..9]);
+        let sequence = u32::from_le_bytes(word);
+        let mut len = [0u8; 8];
+        len.copy_from_slice(&header[9..]);
+
+        if session != self.session {
+            return Err(VoleError::Desync(format!(
+                "message {} is from session {}, expected session {}",
+                sequence, session, self.session
+            )));
+        }
+        if sequence != self.received {
+            return Err(VoleError::Desync(format!(
+                "expected message {}, the peer sent message {}",
+                self.received, sequence
+            )));
+        }
+        match MessageKind::from_tag(header[0]) {
+            Some(kind) if kind == expected => {}
+            Some(kind) => {
+                return Err(VoleError::Desync(format!(
+                    "expected {} as message {}, the peer sent {}",
+                    expected, sequence, kind
+                )))
+            }
+            None => {
+                return Err(VoleError::Desync(format!(
+                    "message {} has unknown tag {}", sequence, header[0]
+                )))
+            }
+        }
+        let len = u64::from_le_bytes(len);
+        if len > MAX_FRAME_LEN as u64 {
+            return Err(VoleError::MalformedMessage(format!(
+                "message {} announces {} bytes, more than the {} allowed",
+                sequence, len, MAX_FRAME_LEN
+            )));
+        }
+        self.received = self.received.wrapping_add(1);
+        Ok(len as usize)
+    }
+
+    /// Checks a whole incoming frame and returns its payload
+    pub fn split<'a>(&mut self, frame: &'a [u8], expected: MessageKind) -> Result<&'a [u8]> {
+        if frame.len() < HEADER_LEN {
+            return Err(VoleError::MalformedMessage("Truncated frame header".to_string()));
+        }
+        let mut header = [0u8; HEADER_LEN];
+        header.copy_from_slice(&frame[..HEADER_LEN]);
+        let len = self.check(&header, expected)?;
+        if frame.len() - HEADER_LEN != len {
+            return Err(VoleError::MalformedMessage(format!(
+                "Frame of {} bytes announces a payload of {}",
+                frame.len(), len
+            )));
+        }
+        Ok(&frame[HEADER_LEN..])
+    }
+}
+
+// Payload encodings shared by all channels
+
+/// The bit count as u64, then the bits packed LSB first
+pub(crate) fn encode_bits(bits: &[bool]) -> Vec<u8> {
+    let mut payload = Vec::with_capacity(8 + (bits.len() + 7) / 8);
+    payload.extend_from_slice(&(bits.len() as u64).to_le_bytes());
+    for chunk in bits.chunks(8) {
+        let mut byte = 0u8;
+        for (i, &bit) in chunk.iter().enumerate() {
+            if bit {
+                byte |= 1 << i;
+            }
+        }
+        payload.push(byte);
+    }
+    payload
+}
+
+pub(crate) fn decode_bits(payload: &[u8]) -> Result<Vec<bool>> {
+    if payload.len() < 8 {
+        return Err(VoleError::MalformedMessage("Truncated bit message".to_string()));
+    }
+    let mut num_bits_buf = [0u8; 8];
+    num_bits_buf.copy_from_slice(&payload[..8]);
+    let num_bits = u64::from_le_bytes(num_bits_buf) as usize;
+    if payload.len() - 8 != num_bits.div_ceil(8) {
+        return Err(VoleError::MalformedMessage("Unexpected bit message length".to_string()));
+    }
+    Ok((0..num_bits).map(|i| (payload[8 + i / 8] & (1 << (i % 8))) != 0).collect())
+}
+
+pub(crate) fn encode_field_elements<F: VoleField>(elements: &[FieldElement<F>]) -> Vec<u8> {
+    let mut payload = Vec::with_capacity(elements.len() * F::BYTE_LEN);
+    for element in elements {
+        payload.extend_from_slice(&F::to_bytes_le(element));
+    }
+    payload
+}
+
+pub(crate) fn decode_field_elements<F: VoleField>(payload: &[u8], count: usize) -> Result<Vec<FieldElement<F>>> {
+    // Validate the size
+    let expected_size = count * F::BYTE_LEN;
+    if payload.len() != expected_size {
+        return Err(VoleError::MalformedMessage(format!(
+            "Unexpected data size received: expected {}, got {}",
+            expected_size, payload.len()
+        )));
+    }
+
+    let elements: std::result::Result<Vec<_>, _> = payload
+        .chunks_exact(F::BYTE_LEN)
+        .map(F::from_bytes_le)
+        .collect();
+
+    elements.map_err(|e| {
+        VoleError::MalformedMessage(format!("Failed to deserialize {} element: {:?}", F::NAME, e))
+    })
+}
+
+pub(crate) fn decode_point(payload: &[u8]) -> Result<EncodedPoint> {
+    // An uncompressed SEC1 point is at most 65 bytes
+    if payload.len() > 65 {
+        return Err(VoleError::MalformedMessage(format!("Point of {} bytes", payload.len())));
+    }
+    EncodedPoint::from_bytes(payload)
+        .map_err(|e| VoleError::MalformedMessage(format!("Invalid point received: {:?}", e)))
+}
+
+pub(crate) fn encode_blocks<T: AsRef<[u8]>>(data: &[T]) -> Vec<u8> {
+    let mut payload = Vec::with_capacity(data.iter().map(|b| b.as_ref().len()).sum());
+    for block in data {
+        payload.extend_from_slice(block.as_ref());
+    }
+    payload
+}
+
+pub(crate) fn decode_blocks<const N: usize>(payload: &[u8]) -> Result<Vec<[u8; N]>> {
+    if payload.len() % N != 0 {
+        return Err(VoleError::MalformedMessage("Received data is not a whole number of blocks".to_string()));
+    }
+    Ok(payload
+        .chunks_exact(N)
+        .map(|chunk| {
+            let mut block = [0u8; N];
+            block.copy_from_slice(chunk);
+            block
+        })
+        .collect())
+}
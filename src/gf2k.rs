@@ -0,0 +1,169 @@
+#[cfg(target_arch = "x86")]
+use std::arch::x86::*;
+#[cfg(target_arch = "x86_64")]
+use std::arch::x86_64::*;
+use std::convert::TryInto;
+
+// Arithmetic in GF(2^128) = GF(2)[x] / (x^128 + x^7 + x^2 + x + 1) and
+// GF(2^256) = GF(2)[x] / (x^256 + x^10 + x^5 + x^2 + 1), for the IKNP check. An element
+// of GF(2^8N) is N bytes, N being 16 or 32, the coefficient of x^i is bit i % 8 of byte
+// i / 8. Products are first taken without reduction, as two elements holding the low and
+// high 8N coefficients, so that sums of products only need to be reduced once.
+
+/// Carry-less product of two 64-bit polynomials, with PCLMULQDQ when the CPU has it.
+pub fn clmul64(a: u64, b: u64) -> u128 {
+    #[cfg(any(target_arch = "x86", target_arch = "x86_64"))]
+    {
+        if has_pclmul() {
+            // SAFETY: PCLMULQDQ and SSE2 are available
+            return unsafe { clmul64_pclmul(a, b) };
+        }
+    }
+    clmul64_portable(a, b)
+}
+
+/// `clmul64` one bit at a time.
+pub fn clmul64_portable(a: u64, b: u64) -> u128 {
+    let mut result = 0u128;
+    for i in 0..64 {
+        if (b & (1 << i)) != 0 {
+            result ^= (a as u128) << i;
+        }
+    }
+    result
+}
+
+/// Product of `a` and `b` without reduction, low half first.
+pub fn mul_no_red<const N: usize>(a: &[u8; N], b: &[u8; N]) -> [[u8; N]; 2] {
+    inner_product_no_red(std::slice::from_ref(a), std::slice::from_ref(b))
+}
+
+/// Sum of `a[i] * b[i]` without reduction, low half first.
+pub fn inner_product_no_red<const N: usize>(a: &[[u8; N]], b: &[[u8; N]]) -> [[u8; N]; 2] {
+    check_width::<N>();
+    #[cfg(any(target_arch = "x86", target_arch = "x86_64"))]
+    {
+        if has_pclmul() {
+            // SAFETY: PCLMULQDQ and SSE2 are available
+            return unsafe { inner_product_pclmul(a, b) };
+        }
+    }
+    inner_product_portable(a, b)
+}
+
+/// `inner_product_no_red` without PCLMULQDQ.
+pub fn inner_product_portable<const N: usize>(a: &[[u8; N]], b: &[[u8; N]]) -> [[u8; N]; 2] {
+    check_width::<N>();
+    let mut z = [0u128; 7];
+    for (x, y) in a.iter().zip(b.iter()) {
+        accumulate_product(&mut z, x, y, clmul64_portable);
+    }
+    assemble(&z)
+}
+
+/// Reduces an unreduced product modulo the polynomial of GF(2^8N).
+pub fn reduce<const N: usize>(x: &[[u8; N]; 2]) -> [u8; N] {
+    let taps = taps::<N>();
+    let len = N / 8;
+    let mut r = limbs(&x[0]);
+    let high = limbs(&x[1]);
+
+    // x^8N is the sum of the taps, which push up to 10 bits past the top coefficient
+    let mut overflow = 0u64;
+    for &shift in taps.iter() {
+        for i in 0..len {
+            r[i] ^= high[i] << shift;
+            if shift > 0 {
+                let carry = high[i] >> (64 - shift);
+                if i < len - 1 {
+                    r[i + 1] ^= carry;
+                } else {
+                    overflow ^= carry;
+                }
+            }
+        }
+    }
+    // Folding those back stays below x^20
+    for &shift in taps.iter() {
+        r[0] ^= overflow << shift;
+    }
+
+    let mut out = [0u8; N];
+    for (chunk, limb) in out.chunks_exact_mut(8).zip(r.iter()) {
+        chunk.copy_from_slice(&limb.to_le_bytes());
+    }
+    out
+}
+
+/// Product of `a` and `b` in GF(2^8N).
+pub fn gf_mul<const N: usize>(a: &[u8; N], b: &[u8; N]) -> [u8; N] {
+    reduce(&mul_no_red(a, b))
+}
+
+fn check_width<const N: usize>() {
+    assert!(N == 16 || N == 32, "GF(2^k) elements are 16 or 32 bytes, not {}", N);
+}
+
+// Exponents of the reduction polynomial below x^8N
+fn taps<const N: usize>() -> [u32; 4] {
+    check_width::<N>();
+    if N == 16 { [0, 1, 2, 7] } else { [0, 2, 5, 10] }
+}
+
+fn limbs<const N: usize>(x: &[u8; N]) -> [u64; 4] {
+    let mut limbs = [0u64; 4];
+    for (limb, chunk) in limbs.iter_mut().zip(x.chunks_exact(8)) {
+        *limb = u64::from_le_bytes(chunk.try_into().unwrap());
+    }
+    limbs
+}
+
+// Schoolbook on 64-bit limbs, z[k] collects the products of limbs i and j with i + j = k
+#[inline(always)]
+fn accumulate_product<const N: usize>(z: &mut [u128; 7], a: &[u8; N], b: &[u8; N], clmul: impl Fn(u64, u64) -> u128) {
+    let (a, b) = (limbs(a), limbs(b));
+    for i in 0..N / 8 {
+        for j in 0..N / 8 {
+            z[i + j] ^= clmul(a[i], b[j]);
+        }
+    }
+}
+
+// z[k] sits at bit 64k, each overlaps the next one by a limb
+fn assemble<const N: usize>(z: &[u128; 7]) -> [[u8; N]; 2] {
+    let len = N / 8;
+    let mut words = [0u64; 8];
+    for (k, &zk) in z.iter().enumerate().take(2 * len - 1) {
+        words[k] ^= zk as u64;
+        words[k + 1] ^= (zk >> 64) as u64;
+    }
+    let mut out = [[0u8; N]; 2];
+    for (k, word) in words.iter().enumerate().take(2 * len) {
+        out[k / len][8 * (k % len)..8 * (k % len + 1)].copy_from_slice(&word.to_le_bytes());
+    }
+    out
+}
+
+#[cfg(any(target_arch = "x86", target_arch = "x86_64"))]
+fn has_pclmul() -> bool {
+    is_x86_feature_detected!("pclmulqdq") && is_x86_feature_detected!("sse2")
+}
+
+#[cfg(any(target_arch = "x86", target_arch = "x86_64"))]
+#[target_feature(enable = "pclmulqdq,sse2")]
+unsafe fn clmul64_pclmul(a: u64, b: u64) -> u128 {
+    let product = _mm_clmulepi64_si128(_mm_set_epi64x(0, a as i64), _mm_set_epi64x(0, b as i64), 0x00);
+    let mut out = [0u8; 16];
+    _mm_storeu_si128(out.as_mut_ptr() as *mut __m128i, product);
+    u128::from_le_bytes(out)
+}
+
+#[cfg(any(target_arch = "x86", target_arch = "x86_64"))]
+#[target_feature(enable = "pclmulqdq,sse2")]
+unsafe fn inner_product_pclmul<const N: usize>(a: &[[u8; N]], b: &[[u8; N]]) -> [[u8; N]; 2] {
+    let mut z = [0u128; 7];
+    for (x, y) in a.iter().zip(b.iter()) {
+        accumulate_product(&mut z, x, y, |u, v| clmul64_pclmul(u, v));
+    }
+    assemble(&z)
+}
@@ -1,5 +1,5 @@
 use sha2::{Digest, Sha256};
-use aes::Aes256;
+use aes::{Aes128, Aes256};
 use aes::cipher::{KeyInit, BlockEncrypt, generic_array::GenericArray};
 use std::convert::TryInto;
 
@@ -96,51 +96,52 @@ impl Hash {
 }
 
 // I need to revisit CCRH in the future
+/// Circular correlation robust hash over 32-byte blocks, or 16-byte ones for the 128-bit COTs.
 pub struct CCRH {
+    aes: Aes128, // Fixed key, for 16-byte blocks
 }
 
 impl CCRH {
     pub fn new() -> Self {
-        CCRH {}
+        CCRH {
+            aes: Aes128::new(GenericArray::from_slice(&[0u8; 16])),
+        }
     }
 
     /// Permute blocks using AES encryption
     pub fn permute_block(&self, blocks: &mut [[u8; 32]]) {
-        for (i, block) in blocks.iter_mut().enumerate() {
-            let aes_key = Aes256::new(GenericArray::from_slice(block));
-            let mut permuted_block: [_; 2] = core::array::from_fn(|i| GenericArray::clone_from_slice(&[i as u8; 16]));
-            // Encrypt the 4 blocks using the AES key
-            aes_key.encrypt_blocks(&mut permuted_block);
-            let new_block = [permuted_block[0].as_slice(), permuted_block[1].as_slice()].concat();
-            block.copy_from_slice(&new_block);
+        for block in blocks.iter_mut() {
+            self.permute(block);
+        }
+    }
+
+    fn permute(&self, block: &mut [u8]) {
+        match block.len() {
+            32 => {
+                let aes_key = Aes256::new(GenericArray::from_slice(block));
+                let mut permuted_block: [_; 2] = core::array::from_fn(|i| GenericArray::clone_from_slice(&[i as u8; 16]));
+                // Encrypt the 4 blocks using the AES key
+                aes_key.encrypt_blocks(&mut permuted_block);
+                let new_block = [permuted_block[0].as_slice(), permuted_block[1].as_slice()].concat();
+                block.copy_from_slice(&new_block);
+            }
+            16 => self.aes.encrypt_block(GenericArray::from_mut_slice(block)),
+            n => panic!("No CCRH for {}-byte blocks", n),
         }
     }
 
     /// Single hash function
-    pub fn h(&self, input: &[u8; 32]) -> [u8; 32] {
+    pub fn h<const N: usize>(&self, input: &[u8; N]) -> [u8; N] {
         let t = sigma(input);
-
-        let mut blocks = vec![t];
-        self.permute_block(&mut blocks);
-
-        xor_block(&t, &blocks[0])
+        let mut permuted = t;
+        self.permute(&mut permuted);
+        core::array::from_fn(|i| t[i] ^ permuted[i])
     }
 
     /// Hash multiple blocks
-    pub fn hn(&self, output: &mut [[u8; 32]], input: &[[u8; 32]]) {
-        let len = input.len();
-        let mut tmp: Vec<[u8; 32]> = vec![[0; 32]; len];
-
-        for (i, block) in input.iter().enumerate() {
-            tmp[i] = block.clone();
-            tmp[i] = sigma(&tmp[i]);
-            output[i] = tmp[i];
-        }
-
-        self.permute_block(&mut tmp);
-
-        for i in 0..len {
-            output[i] = xor_block(&output[i], &tmp[i]);
+    pub fn hn<const N: usize>(&self, output: &mut [[u8; N]], input: &[[u8; N]]) {
+        for (out, block) in output.iter_mut().zip(input.iter()) {
+            *out = self.h(block);
         }
     }
 
@@ -148,37 +149,31 @@ impl CCRH {
 
 /// A helper function to simulate sigma operation
 // I think it can be arbitrary. Check later: https://eprint.iacr.org/2019/074.pdf
-fn sigma(input: &[u8; 32]) -> [u8; 32] {
-    let mut output = [0u8; 32];
-
-    // Basically rotating each pair of 4-bytes
-    output[0..4].copy_from_slice(&input[4..8]);
-    output[4..8].copy_from_slice(&input[0..4]);
-    output[8..12].copy_from_slice(&input[12..16]);
-    output[12..16].copy_from_slice(&input[8..12]);
-    output[16..20].copy_from_slice(&input[20..24]);
-    output[20..24].copy_from_slice(&input[16..20]);
-    output[24..28].copy_from_slice(&input[28..32]);
-    output[28..32].copy_from_slice(&input[20..24]);
+fn sigma<const N: usize>(input: &[u8; N]) -> [u8; N] {
+    let mut output = [0u8; N];
+
+    if N == 32 {
+        // Basically rotating each pair of 4-bytes
+        output[0..4].copy_from_slice(&input[4..8]);
+        output[4..8].copy_from_slice(&input[0..4]);
+        output[8..12].copy_from_slice(&input[12..16]);
+        output[12..16].copy_from_slice(&input[8..12]);
+        output[16..20].copy_from_slice(&input[20..24]);
+        output[20..24].copy_from_slice(&input[16..20]);
+        output[24..28].copy_from_slice(&input[28..32]);
+        output[28..32].copy_from_slice(&input[20..24]);
+    } else {
+        // Swapping the 8-byte halves, as in the paper
+        output[..8].copy_from_slice(&input[8..16]);
+        output[8..16].copy_from_slice(&input[..8]);
+    }
 
-    // Apply a mask and XOR: (a & mask) ^ shuffled
-    let mask: [u8; 32] = [0xFF; 8].iter().chain(&[0x00; 8]).chain(&[0xFF; 8]).chain(&[0x00; 8]).copied().collect::<Vec<u8>>().try_into().unwrap();
-    for i in 0..32 {
-        output[i] ^= input[i] & mask[i];
+    // Apply a mask and XOR: (a & mask) ^ shuffled, the mask keeping every other 8 bytes
+    for i in 0..N {
+        if (i / 8) % 2 == 0 {
+            output[i] ^= input[i];
+        }
     }
 
     output
 }
-
-fn xor_block(a: &[u8; 32], b: &[u8; 32]) -> [u8; 32] {
-    [
-        a[0] ^ b[0], a[1] ^ b[1], a[2] ^ b[2], a[3] ^ b[3],
-        a[4] ^ b[4], a[5] ^ b[5], a[6] ^ b[6], a[7] ^ b[7],
-        a[8] ^ b[8], a[9] ^ b[9], a[10] ^ b[10], a[11] ^ b[11],
-        a[12] ^ b[12], a[13] ^ b[13], a[14] ^ b[14], a[15] ^ b[15],
-        a[16] ^ b[16], a[17] ^ b[17], a[18] ^ b[18], a[19] ^ b[19],
-        a[20] ^ b[20], a[21] ^ b[21], a[22] ^ b[22], a[23] ^ b[23],
-        a[24] ^ b[24], a[25] ^ b[25], a[26] ^ b[26], a[27] ^ b[27],
-        a[28] ^ b[28], a[29] ^ b[29], a[30] ^ b[30], a[31] ^ b[31],
-    ]
-}
\ No newline at end of file
@@ -1,44 +1,77 @@
 use crate::ot::OTCO;
 use crate::comm_channel::CommunicationChannel;
+use crate::async_comm_channel::{AsyncCommunicationChannel, SyncChannel, block_on};
+use crate::error::{Result, VoleError, CheckFailure};
 use crate::prg::PRG;
+use crate::transpose::transpose;
+use crate::gf2k::{inner_product_no_red, mul_no_red, reduce};
+use crate::ot_extension::{CotSender, CotReceiver, OtExtension};
 use std::convert::TryInto;
 
-const BLOCK_SIZE: usize = 1024 * 2;
-const NUM_BITS: usize = 256;
+pub(crate) const BLOCK_SIZE: usize = 1024 * 2;
 
-
-pub struct IKNP {
-    pub(crate) base_ot: OTCO,
-    delta: Option<[u8; 32]>,
+/// IKNP OT extension with `N`-byte COT blocks, from `8 * N` base OTs.
+///
+/// `N = 16` is the standard construction with κ = 128. `N = 32`, the default, gives
+/// the 256-bit correlations the SPFSS of the Stark252 VOLE needs. The malicious check
+/// works in GF(2^8N).
+pub struct IKNP<const N: usize = 32> {
+    base_ot: OTCO,
+    delta: Option<[u8; N]>,
     setup: bool,
-    s: [bool; NUM_BITS],
-    local_r: [bool; 2*NUM_BITS],
-    local_out: Vec<[u8; 32]>,
+    s: Vec<bool>,
+    local_r: Vec<bool>,
+    local_out: Vec<[u8; N]>,
     g0: Option<Vec<PRG>>,
     g1: Option<Vec<PRG>>,
     malicious: bool,
     k0: Vec<[u8; 16]>,
     k1: Vec<[u8; 16]>,
+    // Per-block scratch, allocated once: the PRG rows, the masked rows and the columns sent
+    t: Vec<[u8; N]>,
+    res: Vec<[u8; N]>,
+    tmp: Vec<[u8; N]>,
+    // Output of a partial or check block
+    block_out: Vec<[u8; N]>,
 }
 
-impl IKNP {
+impl<const N: usize> IKNP<N> {
+    /// Number of base OTs, i.e. bits in a COT block
+    pub const NUM_BITS: usize = 8 * N;
+
+    const WIDTH_CHECK: () = assert!(N == 16 || N == 32, "IKNP blocks are 16 or 32 bytes");
+
     pub fn new(malicious: bool) -> Self {
+        let () = Self::WIDTH_CHECK;
         Self {
             base_ot: OTCO::new(),
             delta: None,
             setup: false,
-            s: [false; NUM_BITS],
-            local_r: [false; 2*NUM_BITS],
-            local_out: vec![[0u8; 32]; BLOCK_SIZE],
+            s: vec![false; Self::NUM_BITS],
+            local_r: vec![false; 2 * Self::NUM_BITS],
+            local_out: vec![[0u8; N]; BLOCK_SIZE],
             g0: None,
             g1: None,
             malicious,
-            k0: vec![[0u8; 16]; NUM_BITS],
-            k1: vec![[0u8; 16]; NUM_BITS],
+            k0: vec![[0u8; 16]; Self::NUM_BITS],
+            k1: vec![[0u8; 16]; Self::NUM_BITS],
+            t: vec![[0u8; N]; BLOCK_SIZE],
+            res: vec![[0u8; N]; BLOCK_SIZE],
+            tmp: vec![[0u8; N]; BLOCK_SIZE],
+            block_out: vec![[0u8; N]; BLOCK_SIZE],
         }
     }
 
-    pub fn setup_send<IO: CommunicationChannel>(&mut self, io: &mut IO, in_s: Option<&[bool]>, in_k0: Option<&[[u8; 16]]>) {
+    pub fn malicious(&self) -> bool {
+        self.malicious
+    }
+
+    pub fn setup_send<IO: CommunicationChannel>(&mut self, io: &mut IO, in_s: Option<&[bool]>, in_k0: Option<&[[u8; 16]]>) -> Result<()> {
+        block_on(self.setup_send_async(&mut SyncChannel(io), in_s, in_k0))
+    }
+
+    /// Async variant of `setup_send`.
+    pub async fn setup_send_async<IO: AsyncCommunicationChannel>(&mut self, io: &mut IO, in_s: Option<&[bool]>, in_k0: Option<&[[u8; 16]]>) -> Result<()> {
         self.setup = true;
 
         if let Some(in_s) = in_s {
@@ -52,24 +85,30 @@ impl IKNP {
             self.k0.copy_from_slice(in_k0);
         } else {
             self.k0.clear();
-            self.base_ot.recv(io, &self.s, &mut self.k0);
+            self.base_ot.recv_async(io, &self.s, &mut self.k0).await?;
         }
 
         self.g0 = Some(
             self.k0.iter()
                 .enumerate()
                 .map(|(i, key)| {
-                    let mut prg = PRG::new(None, (i + (self.s[i] as usize) * NUM_BITS) as u64);
-                    prg.reseed(key, (i + (self.s[i] as usize) * NUM_BITS) as u64);
+                    let mut prg = PRG::new(None, (i + (self.s[i] as usize) * Self::NUM_BITS) as u64);
+                    prg.reseed(key, (i + (self.s[i] as usize) * Self::NUM_BITS) as u64);
                     prg
                 })
                 .collect(),
         );
 
         self.delta = Some(bool_to_block(&self.s));
+        Ok(())
     }
 
-    pub fn setup_recv<IO: CommunicationChannel>(&mut self, io: &mut IO, in_k0: Option<&[[u8; 16]]>, in_k1: Option<&[[u8; 16]]>) {
+    pub fn setup_recv<IO: CommunicationChannel>(&mut self, io: &mut IO, in_k0: Option<&[[u8; 16]]>, in_k1: Option<&[[u8; 16]]>) -> Result<()> {
+        block_on(self.setup_recv_async(&mut SyncChannel(io), in_k0, in_k1))
+    }
+
+    /// Async variant of `setup_recv`.
+    pub async fn setup_recv_async<IO: AsyncCommunicationChannel>(&mut self, io: &mut IO, in_k0: Option<&[[u8; 16]]>, in_k1: Option<&[[u8; 16]]>) -> Result<()> {
         self.setup = true;
 
         if let (Some(in_k0), Some(in_k1)) = (in_k0, in_k1) {
@@ -79,7 +118,7 @@ impl IKNP {
             let mut prg = PRG::new(None, 0);
             prg.random_block(&mut self.k0);
             prg.random_block(&mut self.k1);
-            self.base_ot.send(io, &self.k0, &self.k1);
+            self.base_ot.send_async(io, &self.k0, &self.k1).await?;
         }
 
         self.g0 = Some(
@@ -96,374 +135,417 @@ impl IKNP {
             self.k1.iter()
                 .enumerate()
                 .map(|(i, key)| {
-                    let mut prg = PRG::new(None, (i + NUM_BITS) as u64);
-                    prg.reseed(key, (i + NUM_BITS) as u64);
+                    let mut prg = PRG::new(None, (i + Self::NUM_BITS) as u64);
+                    prg.reseed(key, (i + Self::NUM_BITS) as u64);
                     prg
                 })
                 .collect(),
         );
+        Ok(())
+    }
+
+    pub fn send_pre<IO: CommunicationChannel>(&mut self, io: &mut IO, out: &mut [[u8; N]], length: usize) -> Result<()> {
+        block_on(self.send_pre_async(&mut SyncChannel(io), out, length))
     }
 
-    pub fn send_pre<IO: CommunicationChannel>(&mut self, io: &mut IO, out: &mut [[u8; 32]], length: usize) {
+    /// Async variant of `send_pre`.
+    pub async fn send_pre_async<IO: AsyncCommunicationChannel>(&mut self, io: &mut IO, out: &mut [[u8; N]], length: usize) -> Result<()> {
         if !self.setup {
-            self.setup_send(io, None, None);
+            self.setup_send_async(io, None, None).await?;
         }
 
         let mut idx = 0;
         while idx + BLOCK_SIZE <= length {
-            self.send_pre_block(io, &mut out[idx..idx+BLOCK_SIZE], BLOCK_SIZE);
+            self.send_pre_block_async(io, &mut out[idx..idx+BLOCK_SIZE], BLOCK_SIZE).await?;
             idx += BLOCK_SIZE;
         }
 
         let remaining = length - idx;
         if remaining > 0 {
-            let mut temp_out = self.local_out.clone();
-            self.send_pre_block(io, &mut temp_out, remaining);
+            let mut temp_out = std::mem::take(&mut self.block_out);
+            let res = self.send_pre_block_async(io, &mut temp_out, remaining).await;
             out[idx..].copy_from_slice(&temp_out[..remaining]);
+            self.block_out = temp_out;
+            res?;
         }
 
         if self.malicious {
-            println!("There is malicious!");
-            let mut temp_out = self.local_out.clone();
-            self.send_pre_block(io, &mut temp_out, 2 * NUM_BITS);
-            self.local_out.copy_from_slice(&temp_out);
+            let mut temp_out = std::mem::take(&mut self.local_out);
+            let res = self.send_pre_block_async(io, &mut temp_out, 2 * Self::NUM_BITS).await;
+            self.local_out = temp_out;
+            res?;
         }
+        Ok(())
     }
 
-    fn send_pre_block<IO: CommunicationChannel>(&mut self, io: &mut IO, out: &mut [[u8; 32]], length: usize) {
-        let local_block_size = (length + NUM_BITS - 1) / NUM_BITS * NUM_BITS;
+    async fn send_pre_block_async<IO: AsyncCommunicationChannel>(&mut self, io: &mut IO, out: &mut [[u8; N]], length: usize) -> Result<()> {
+        let local_block_size = (length + Self::NUM_BITS - 1) / Self::NUM_BITS * Self::NUM_BITS;
         // println!("local_block_size: {}", local_block_size);
 
-        let mut t = vec![[0u8; 32]; BLOCK_SIZE];
-        let mut res = vec![[0u8; 32]; BLOCK_SIZE];
-        let mut tmp = io.receive_32byte_block();
+        let (t, res) = (&mut self.t, &mut self.res);
+        let tmp = receive_blocks::<_, N>(io).await?;
+        if tmp.len() != BLOCK_SIZE {
+            return Err(VoleError::MalformedMessage(format!("Expected {} IKNP blocks, got {}", BLOCK_SIZE, tmp.len())));
+        }
 
         // println!("Received tmp: {:?}", &tmp[..5]);
 
         if let Some(prgs) = &mut self.g0 {
             // println!("The number of keys is: {}", prgs.len());
             for (i, prg) in prgs.iter_mut().enumerate() {
-                let start = i * BLOCK_SIZE / NUM_BITS;
-                let end = start + local_block_size / NUM_BITS;
+                let start = i * BLOCK_SIZE / Self::NUM_BITS;
+                let end = start + local_block_size / Self::NUM_BITS;
                 // println!("i, start, end: {}, {}, {}", i, start, end);
-                prg.random_32byte_block(&mut t[start..end]);
+                prg.random_blocks(&mut t[start..end]);
                 // println!("PRG: {:?}", t[start]);
                 if self.s[i] {
                     xor_blocks_arr(&mut res[start..end], &t[start..end], &tmp[start..end]);
                 } else {
                     res[start..end].copy_from_slice(&t[start..end]);
                 }
+                // The rest of the row is left over from the previous block
+                res[end..start + BLOCK_SIZE / Self::NUM_BITS].fill([0u8; N]);
                 // println!("res: {:?}", res[start]);
             }
         }
 
-        transpose(out, &res, NUM_BITS, BLOCK_SIZE);
+        transpose(out.as_flattened_mut(), res.as_flattened(), Self::NUM_BITS, BLOCK_SIZE);
+        Ok(())
     }
 
-    pub fn recv_pre<IO: CommunicationChannel>(&mut self, io: &mut IO, out: &mut [[u8; 32]], r: &[bool], length: usize) {
+    pub fn recv_pre<IO: CommunicationChannel>(&mut self, io: &mut IO, out: &mut [[u8; N]], r: &[bool], length: usize) -> Result<()> {
+        block_on(self.recv_pre_async(&mut SyncChannel(io), out, r, length))
+    }
+
+    /// Async variant of `recv_pre`.
+    pub async fn recv_pre_async<IO: AsyncCommunicationChannel>(&mut self, io: &mut IO, out: &mut [[u8; N]], r: &[bool], length: usize) -> Result<()> {
         if !self.setup {
-            self.setup_recv(io, None, None);
+            self.setup_recv_async(io, None, None).await?;
         }
 
-        let mut block_r = vec![[0u8; 32]; (length + NUM_BITS - 1) / NUM_BITS];
+        let mut block_r = vec![[0u8; N]; (length + Self::NUM_BITS - 1) / Self::NUM_BITS];
 
-        for (i, chunk) in r.chunks(NUM_BITS).enumerate() {
+        for (i, chunk) in r.chunks(Self::NUM_BITS).enumerate() {
             block_r[i] = bool_to_block(chunk);
         }
 
         let mut idx = 0;
 
         while idx + BLOCK_SIZE <= length {
-            self.recv_pre_block(io, &mut out[idx..idx+BLOCK_SIZE], &block_r[idx / NUM_BITS..(idx + BLOCK_SIZE) / NUM_BITS], BLOCK_SIZE);
+            self.recv_pre_block_async(io, &mut out[idx..idx+BLOCK_SIZE], &block_r[idx / Self::NUM_BITS..(idx + BLOCK_SIZE) / Self::NUM_BITS], BLOCK_SIZE).await?;
             idx += BLOCK_SIZE;
         }
 
         let remaining = length - idx;
         if remaining > 0 {
-            println!("There is remaining!");
-            let mut temp_out = self.local_out.clone();
-            self.recv_pre_block(io, &mut temp_out, &block_r[idx / NUM_BITS..], remaining);
+            let mut temp_out = std::mem::take(&mut self.block_out);
+            let res = self.recv_pre_block_async(io, &mut temp_out, &block_r[idx / Self::NUM_BITS..], remaining).await;
             out[idx..].copy_from_slice(&temp_out[..remaining]);
+            self.block_out = temp_out;
+            res?;
         }
 
         if self.malicious {
-            println!("There is malicious!");
             let mut prg = PRG::new(None, 0);
             prg.random_bool_array(&mut self.local_r);
-            let mut local_r_block = vec![[0u8; 32]; 2];
-            for (i, chunk) in self.local_r.chunks(NUM_BITS).enumerate() {
+            let mut local_r_block = vec![[0u8; N]; 2];
+            for (i, chunk) in self.local_r.chunks(Self::NUM_BITS).enumerate() {
                 local_r_block[i] = bool_to_block(chunk);
             }
-            let mut temp_out = self.local_out.clone();
-            self.recv_pre_block(io, &mut temp_out, &local_r_block, 2 * NUM_BITS);
-            self.local_out.copy_from_slice(&temp_out);
+            let mut temp_out = std::mem::take(&mut self.local_out);
+            let res = self.recv_pre_block_async(io, &mut temp_out, &local_r_block, 2 * Self::NUM_BITS).await;
+            self.local_out = temp_out;
+            res?;
         }
+        io.flush().await?;
+        Ok(())
     }
 
-    fn recv_pre_block<IO: CommunicationChannel>(&mut self, io: &mut IO, out: &mut [[u8; 32]], r: &[[u8; 32]], length: usize) {
-        let mut t = vec![[0u8; 32]; BLOCK_SIZE];
-        let mut tmp = vec![[0u8; 32]; BLOCK_SIZE];
-        let mut res = vec![[0u8; 32]; BLOCK_SIZE];
-        let local_block_size = (length + NUM_BITS - 1) / NUM_BITS * NUM_BITS;
+    async fn recv_pre_block_async<IO: AsyncCommunicationChannel>(&mut self, io: &mut IO, out: &mut [[u8; N]], r: &[[u8; N]], length: usize) -> Result<()> {
+        let (t, tmp, res) = (&mut self.t, &mut self.tmp, &mut self.res);
+        let local_block_size = (length + Self::NUM_BITS - 1) / Self::NUM_BITS * Self::NUM_BITS;
 
         if let (Some(prgs_g0), Some(prgs_g1)) = (&mut self.g0, &mut self.g1) {
             for (i, (prg0, prg1)) in prgs_g0.iter_mut().zip(prgs_g1.iter_mut()).enumerate() {
-                let start = i * BLOCK_SIZE / NUM_BITS;
-                let end = start + local_block_size / NUM_BITS;
+                let start = i * BLOCK_SIZE / Self::NUM_BITS;
+                let end = start + local_block_size / Self::NUM_BITS;
                 // println!("Start end: {}, {}", start, end);
-                prg0.random_32byte_block(&mut t[start..end]);
+                prg0.random_blocks(&mut t[start..end]);
                 // println!("PRG: {:?}", t[start]);
-                prg1.random_32byte_block(&mut tmp[start..end]);
+                prg1.random_blocks(&mut tmp[start..end]);
                 xor_blocks_arr(&mut res[start..end], &t[start..end], &tmp[start..end]);
                 xor_blocks_arr(&mut tmp[start..end], &res[start..end], r);
+                // The rest of the row is left over from the previous block
+                t[end..start + BLOCK_SIZE / Self::NUM_BITS].fill([0u8; N]);
+                tmp[end..start + BLOCK_SIZE / Self::NUM_BITS].fill([0u8; N]);
                 // println!("t: {:?}", t[start]);
             }
         }
 
-        io.send_32byte_block(&tmp);
+        send_blocks(io, tmp).await?;
 
         // println!("Sent tmp: {:?}", &tmp[..5]);
 
-        transpose(out, &t, NUM_BITS, BLOCK_SIZE);
+        transpose(out.as_flattened_mut(), t.as_flattened(), Self::NUM_BITS, BLOCK_SIZE);
+        Ok(())
+    }
+
+    pub fn send_cot<IO: CommunicationChannel>(&mut self, io: &mut IO, data: &mut [[u8; N]], length: usize) -> Result<()> {
+        block_on(self.send_cot_async(&mut SyncChannel(io), data, length))
     }
 
-    pub fn send_cot<IO: CommunicationChannel>(&mut self, io: &mut IO, data: &mut [[u8; 32]], length: usize) {
-        self.send_pre(io, data, length);
+    /// Async variant of `send_cot`.
+    pub async fn send_cot_async<IO: AsyncCommunicationChannel>(&mut self, io: &mut IO, data: &mut [[u8; N]], length: usize) -> Result<()> {
+        self.send_pre_async(io, data, length).await?;
 
         if self.malicious {
-            if !self.send_check(io, data, length) {
-                // panic!("OT Extension check failed");
-                println!("OT Extension check failed");
-            } else {
-                println!("OT Extension IKNP successful!");
+            if !self.send_check_async(io, data, length).await? {
+                return Err(VoleError::ConsistencyCheck(CheckFailure::Iknp));
             }
         }
+        Ok(())
+    }
+
+    pub fn recv_cot<IO: CommunicationChannel>(&mut self, io: &mut IO, data: &mut [[u8; N]], r: &[bool], length: usize) -> Result<()> {
+        block_on(self.recv_cot_async(&mut SyncChannel(io), data, r, length))
     }
 
-    pub fn recv_cot<IO: CommunicationChannel>(&mut self, io: &mut IO, data: &mut [[u8; 32]], r: &[bool], length: usize) {
-        self.recv_pre(io, data, r, length);
+    /// Async variant of `recv_cot`.
+    pub async fn recv_cot_async<IO: AsyncCommunicationChannel>(&mut self, io: &mut IO, data: &mut [[u8; N]], r: &[bool], length: usize) -> Result<()> {
+        self.recv_pre_async(io, data, r, length).await?;
 
         if self.malicious {
-            self.recv_check(io, data, r, length);
+            self.recv_check_async(io, data, r, length).await?;
         }
+        Ok(())
     }
 
-    pub fn send_check<IO: CommunicationChannel>(&mut self, io: &mut IO, out: &[[u8; 32]], length: usize) -> bool {
-        let mut seed2 = [0u8; 16];
-        let mut x = [0u8; 32];
-        let mut t = [[0u8; 32]; 2];
-        let mut q = [[0u8; 32]; 2];
-        let mut tmp = [[0u8; 32]; 2];
-        let mut chi = vec![[0u8; 32]; BLOCK_SIZE];
-        q[0] = [0u8; 32];
-        q[1] = [0u8; 32];
+    pub fn send_check<IO: CommunicationChannel>(&mut self, io: &mut IO, out: &[[u8; N]], length: usize) -> Result<bool> {
+        block_on(self.send_check_async(&mut SyncChannel(io), out, length))
+    }
 
-        seed2 = io.receive_data()[0];
-        io.flush();
+    /// Async variant of `send_check`.
+    pub async fn send_check_async<IO: AsyncCommunicationChannel>(&mut self, io: &mut IO, out: &[[u8; N]], length: usize) -> Result<bool> {
+        let delta = self.delta.ok_or_else(|| VoleError::Parameter("Delta must be set during setup".to_string()))?;
+        check_send_async(io, out, length, &self.local_out, delta).await
+    }
 
-        // println!("Seed received: {:?}", seed2);
+    pub fn recv_check<IO: CommunicationChannel>(&mut self, io: &mut IO, out: &[[u8; N]], r: &[bool], length: usize) -> Result<()> {
+        block_on(self.recv_check_async(&mut SyncChannel(io), out, r, length))
+    }
 
-        let mut chi_prg = PRG::new(Some(&seed2), 0);
+    /// Async variant of `recv_check`.
+    pub async fn recv_check_async<IO: AsyncCommunicationChannel>(&mut self, io: &mut IO, out: &[[u8; N]], r: &[bool], length: usize) -> Result<()> {
+        check_recv_async(io, out, r, length, &self.local_out, &self.local_r).await
+    }
+}
 
-        for i in 0..length / BLOCK_SIZE {
-            chi_prg.random_32byte_block(&mut chi);
-            // println!("Check chi: {:?}", &chi[..5]);
-            vector_inn_prdt_sum_no_red(&mut tmp, &chi, &out[i * BLOCK_SIZE..(i + 1) * BLOCK_SIZE]);
-            xor_blocks(&mut q, &tmp);
-        }
+impl<const N: usize> CotSender<N> for IKNP<N> {
+    async fn setup_sender<IO: AsyncCommunicationChannel>(&mut self, io: &mut IO, delta: &[u8; N]) -> Result<()> {
+        self.setup_send_async(io, Some(&block_to_bool(delta)), None).await
+    }
 
-        let remain = length % BLOCK_SIZE;
-        if remain != 0 {
-            println!("There is remain in check!");
-            chi_prg.random_32byte_block(&mut chi);
-            vector_inn_prdt_sum_no_red(&mut tmp, &chi[..remain], &out[length - remain..]);
-            xor_blocks(&mut q, &tmp);
-        }
+    async fn extend_send<IO: AsyncCommunicationChannel>(&mut self, io: &mut IO, data: &mut [[u8; N]], length: usize) -> Result<()> {
+        self.send_pre_async(io, data, length).await
+    }
 
-        // Handle local_out
-        chi_prg.random_32byte_block(&mut chi);
-        vector_inn_prdt_sum_no_red(&mut tmp, &chi, &self.local_out);
-        xor_blocks(&mut q, &tmp);
+    async fn check_send<IO: AsyncCommunicationChannel>(&mut self, io: &mut IO, data: &[[u8; N]], length: usize) -> Result<()> {
+        if self.malicious && !self.send_check_async(io, data, length).await? {
+            return Err(VoleError::ConsistencyCheck(CheckFailure::Iknp));
+        }
+        Ok(())
+    }
+}
 
-        // println!("chi: {:?}, local_out: {:?}", chi, self.local_out);
+impl<const N: usize> CotReceiver<N> for IKNP<N> {
+    async fn setup_receiver<IO: AsyncCommunicationChannel>(&mut self, io: &mut IO) -> Result<()> {
+        self.setup_recv_async(io, None, None).await
+    }
 
-        x = io.receive_32byte_block()[0];
-        println!("Received x: {:?}", x);
-        // Receive t
-        let received_data: Vec<[u8; 32]> = io.receive_32byte_block();
-        assert_eq!(received_data.len(), 2, "Expected exactly 2 elements in received data");
-        t = [received_data[0], received_data[1]]; // Convert Vec to array
+    async fn extend_recv<IO: AsyncCommunicationChannel>(&mut self, io: &mut IO, data: &mut [[u8; N]], r: &[bool], length: usize) -> Result<()> {
+        self.recv_pre_async(io, data, r, length).await
+    }
 
-        println!("Received t: {:?}", t);
+    async fn check_recv<IO: AsyncCommunicationChannel>(&mut self, io: &mut IO, data: &[[u8; N]], r: &[bool], length: usize) -> Result<()> {
+        if self.malicious {
+            self.recv_check_async(io, data, r, length).await?;
+        }
+        Ok(())
+    }
+}
 
-        let delta = self.delta.expect("Delta must be set during setup");
-        mul256(&x, &delta, &mut tmp);
-        xor_blocks(&mut q, &tmp);
+impl<const N: usize> OtExtension<N> for IKNP<N> {
+    fn name(&self) -> String {
+        "IKNP".to_string()
+    }
 
-        println!("Current q: {:?}", q);
+    fn malicious(&self) -> bool {
+        self.malicious
+    }
+}
 
-        cmp_blocks(&q, &t)
+/// Sender side of the KOS-style check on `length` COTs in `out`, over GF(2^8N).
+///
+/// `local_out` are the sender's COTs from an extra block of random choices, which mask the
+/// receiver's choice bits in the check.
+pub(crate) async fn check_send_async<IO: AsyncCommunicationChannel, const N: usize>(io: &mut IO, out: &[[u8; N]], length: usize, local_out: &[[u8; N]], delta: [u8; N]) -> Result<bool> {
+    let mut seed2 = [0u8; 16];
+    let mut x = [0u8; N];
+    let mut t = [0u8; N];
+    let mut q = [[0u8; N]; 2];
+    let mut tmp = [[0u8; N]; 2];
+    let mut chi = vec![[0u8; N]; BLOCK_SIZE];
+    q[0] = [0u8; N];
+    q[1] = [0u8; N];
+
+    seed2 = *io.receive_data().await?.first()
+        .ok_or_else(|| VoleError::MalformedMessage("Missing IKNP check seed".to_string()))?;
+    io.flush().await?;
+
+    let mut chi_prg = PRG::new(Some(&seed2), 0);
+
+    for i in 0..length / BLOCK_SIZE {
+        chi_prg.random_blocks(&mut chi);
+        vector_inn_prdt_sum_no_red(&mut tmp, &chi, &out[i * BLOCK_SIZE..(i + 1) * BLOCK_SIZE]);
+        xor_blocks(&mut q, &tmp);
     }
 
-    pub fn recv_check<IO: CommunicationChannel>(&mut self, io: &mut IO, out: &[[u8; 32]], r: &[bool], length: usize) {
-        let select = [[0u8; 32], [255u8; 32]]; // zero_block and all_one_block
-        let mut seed2 = [0u8; 16];
-        let mut x = [0u8; 32];
-        let mut t = [[0u8; 32]; 2];
-        let mut tmp = [[0u8; 32]; 2];
-        let mut chi = vec![[0u8; 32]; BLOCK_SIZE];
-        t[0] = [0u8; 32];
-        t[1] = [0u8; 32];
+    let remain = length % BLOCK_SIZE;
+    if remain != 0 {
+        chi_prg.random_blocks(&mut chi);
+        vector_inn_prdt_sum_no_red(&mut tmp, &chi[..remain], &out[length - remain..]);
+        xor_blocks(&mut q, &tmp);
+    }
 
-        let mut prg = PRG::new(None, 0);
-        let mut tmp_seed2 = [[0u8; 16]];
-        prg.random_block(&mut tmp_seed2);
-        seed2 = tmp_seed2[0];
+    // Handle local_out
+    chi_prg.random_blocks(&mut chi);
+    vector_inn_prdt_sum_no_red(&mut tmp, &chi, local_out);
+    xor_blocks(&mut q, &tmp);
+
+    x = *receive_blocks::<_, N>(io).await?.first()
+        .ok_or_else(|| VoleError::MalformedMessage("Missing IKNP check value x".to_string()))?;
+    // Receive t, reduced to one element of GF(2^8N)
+    let received_data: Vec<[u8; N]> = receive_blocks(io).await?;
+    if received_data.len() != 1 {
+        return Err(VoleError::MalformedMessage("Expected exactly 1 element in received data".to_string()));
+    }
+    t = received_data[0];
 
-        // println!("Seed sent: {:?}", seed2);
+    tmp = mul_no_red(&x, &delta);
+    xor_blocks(&mut q, &tmp);
 
-        io.send_data(&[seed2]);
-        io.flush();
+    // Reduction is linear, so once at the end is enough
+    let q = reduce(&q);
 
-        let mut chi_prg = PRG::new(Some(&seed2), 0);
+    Ok(q == t)
+}
 
-        for i in 0..length / BLOCK_SIZE {
-            chi_prg.random_32byte_block(&mut chi);
-            // println!("Check chi: {:?}", &chi[..5]);
-            vector_inn_prdt_sum_no_red(&mut tmp, &chi, &out[i * BLOCK_SIZE..(i + 1) * BLOCK_SIZE]);
-            xor_blocks(&mut t, &tmp);
+/// Receiver side of `check_send_async`, `local_r` being the choice bits of `local_out`.
+pub(crate) async fn check_recv_async<IO: AsyncCommunicationChannel, const N: usize>(io: &mut IO, out: &[[u8; N]], r: &[bool], length: usize, local_out: &[[u8; N]], local_r: &[bool]) -> Result<()> {
+    let select = [[0u8; N], [255u8; N]]; // zero_block and all_one_block
+    let mut seed2 = [0u8; 16];
+    let mut x = [0u8; N];
+    let mut t = [[0u8; N]; 2];
+    let mut tmp = [[0u8; N]; 2];
+    let mut chi = vec![[0u8; N]; BLOCK_SIZE];
+    t[0] = [0u8; N];
+    t[1] = [0u8; N];
+
+    let mut prg = PRG::new(None, 0);
+    let mut tmp_seed2 = [[0u8; 16]];
+    prg.random_block(&mut tmp_seed2);
+    seed2 = tmp_seed2[0];
+
+    io.send_data(&[seed2]).await?;
+    io.flush().await?;
+
+    let mut chi_prg = PRG::new(Some(&seed2), 0);
+
+    for i in 0..length / BLOCK_SIZE {
+        chi_prg.random_blocks(&mut chi);
+        vector_inn_prdt_sum_no_red(&mut tmp, &chi, &out[i * BLOCK_SIZE..(i + 1) * BLOCK_SIZE]);
+        xor_blocks(&mut t, &tmp);
 
-            for j in 0..BLOCK_SIZE {
-                for byt in 0..32 {
-                    x[byt] = x[byt] ^ (chi[j][byt] & select[r[i * BLOCK_SIZE + j] as usize][byt]);
-                }
+        for j in 0..BLOCK_SIZE {
+            for byt in 0..N {
+                x[byt] = x[byt] ^ (chi[j][byt] & select[r[i * BLOCK_SIZE + j] as usize][byt]);
             }
         }
+    }
 
-        println!("current x: {:?}", x);
-        println!("current tmp: {:?}", tmp);
-        println!("current t: {:?}", t);
-
-        let remain = length % BLOCK_SIZE;
-        if remain != 0 {
-            chi_prg.random_32byte_block(&mut chi);
-            vector_inn_prdt_sum_no_red(&mut tmp, &chi[..remain], &out[length - remain..]);
-            xor_blocks(&mut t, &tmp);
+    let remain = length % BLOCK_SIZE;
+    if remain != 0 {
+        chi_prg.random_blocks(&mut chi);
+        vector_inn_prdt_sum_no_red(&mut tmp, &chi[..remain], &out[length - remain..]);
+        xor_blocks(&mut t, &tmp);
 
-            for j in 0..remain {
-                for byt in 0..32 {
-                    x[byt] = x[byt] ^ (chi[j][byt] & select[r[length - remain + 1] as usize][byt]);
-                }
+        for j in 0..remain {
+            for byt in 0..N {
+                x[byt] = x[byt] ^ (chi[j][byt] & select[r[length - remain + j] as usize][byt]);
             }
         }
+    }
 
-        // Handle local_out
-        chi_prg.random_32byte_block(&mut chi);
-        vector_inn_prdt_sum_no_red(&mut tmp, &chi, &self.local_out);
-        xor_blocks(&mut t, &tmp);
-        // println!("chi: {:?}", &chi[..5]);
-        // println!("out: {:?}", &out[..5]);
-
-        println!("current tmp: {:?}", tmp);
-        println!("current t: {:?}", t);
-        // println!("chi: {:?}, local_out: {:?}", chi, self.local_out);
-        // println!("local r: {:?}", self.local_r);
+    // Handle local_out
+    chi_prg.random_blocks(&mut chi);
+    vector_inn_prdt_sum_no_red(&mut tmp, &chi, local_out);
+    xor_blocks(&mut t, &tmp);
 
-        for j in 0..(NUM_BITS * 2) {
-            for byt in 0..32 {
-                x[byt] = x[byt] ^ (chi[j][byt] & select[self.local_r[j] as usize][byt]);
-            }
+    for j in 0..local_r.len() {
+        for byt in 0..N {
+            x[byt] = x[byt] ^ (chi[j][byt] & select[local_r[j] as usize][byt]);
         }
+    }
+
+    // Reduction is linear, so once at the end is enough
+    let t = reduce(&t);
+    send_blocks(io, &[x]).await?;
+    send_blocks(io, &[t]).await?;
+    io.flush().await?;
+    Ok(())
+}
 
-        io.send_32byte_block(&[x]);
-        io.send_32byte_block(&t);
+// Sum of `a[i] * b[i]` in GF(2^8N), without reduction
+fn vector_inn_prdt_sum_no_red<const N: usize>(res: &mut [[u8; N]; 2], a: &[[u8; N]], b: &[[u8; N]]) {
+    *res = inner_product_no_red(a, b);
+}
 
-        println!("Current x: {:?}", x);
-        println!("Current t: {:?}", t);
+// The channel has a message for each block width
+pub(crate) async fn send_blocks<IO: AsyncCommunicationChannel, const N: usize>(io: &mut IO, blocks: &[[u8; N]]) -> Result<()> {
+    let bytes = blocks.as_flattened();
+    match N {
+        16 => io.send_data(&to_blocks::<16>(bytes)).await,
+        32 => io.send_32byte_block(&to_blocks::<32>(bytes)).await,
+        _ => Err(VoleError::Parameter(format!("No message for {}-byte blocks", N))),
     }
 }
 
-fn mul256(a: &[u8; 32], b: &[u8; 32], res: &mut [[u8; 32]; 2]) {
-    let mask: u128 = 0xFFFFFFFFFFFFFFFF;
-
-    let mut r1 = [0u8; 32];
-    let mut r2 = [0u8; 32];
-
-    // Split inputs into 4 limbs (64 bits each)
-    let a0 = u64::from_le_bytes(a[0..8].try_into().unwrap());
-    let a1 = u64::from_le_bytes(a[8..16].try_into().unwrap());
-    let a2 = u64::from_le_bytes(a[16..24].try_into().unwrap());
-    let a3 = u64::from_le_bytes(a[24..32].try_into().unwrap());
-
-    let b0 = u64::from_le_bytes(b[0..8].try_into().unwrap());
-    let b1 = u64::from_le_bytes(b[8..16].try_into().unwrap());
-    let b2 = u64::from_le_bytes(b[16..24].try_into().unwrap());
-    let b3 = u64::from_le_bytes(b[24..32].try_into().unwrap());
-
-    // Perform carry-less multiplications
-    let z00 = clmul64(a0, b0); // a0 * b0
-    let z01 = clmul64(a0, b1) ^ clmul64(a1, b0); // (a0 * b1) ^ (a1 * b0)
-    let z02 = clmul64(a0, b2) ^ clmul64(a1, b1) ^ clmul64(a2, b0); // Mixed terms
-    let z03 = clmul64(a0, b3) ^ clmul64(a1, b2) ^ clmul64(a2, b1) ^ clmul64(a3, b0);
-    let z04 = clmul64(a1, b3) ^ clmul64(a2, b2) ^ clmul64(a3, b1);
-    let z05 = clmul64(a2, b3) ^ clmul64(a3, b2);
-    let z06 = clmul64(a3, b3);
-    // println!("z06: {:?}", z06.to_le_bytes());
-    // println!("z06: {:?}", (z06 >> 64).to_le_bytes());
-
-    // Assemble the result into two 256-bit limbs
-    r1[0..8].copy_from_slice(&((z00 & mask) as u64).to_le_bytes());
-    r1[8..16].copy_from_slice(&((z00 >> 64 ^ (z01 & mask)) as u64).to_le_bytes());
-    r1[16..24].copy_from_slice(&((z01 >> 64 ^ (z02 & mask)) as u64).to_le_bytes());
-    r1[24..32].copy_from_slice(&((z02 >> 64 ^ (z03 & mask)) as u64).to_le_bytes());
-
-    r2[0..8].copy_from_slice(&((z03 >> 64 ^ (z04 & mask)) as u64).to_le_bytes());
-    r2[8..16].copy_from_slice(&((z04 >> 64 ^ (z05 & mask)) as u64).to_le_bytes());
-    r2[16..24].copy_from_slice(&((z05 >> 64 ^ (z06 & mask)) as u64).to_le_bytes());
-    r2[24..32].copy_from_slice(&((z06 >> 64) as u64).to_le_bytes());
-
-    // println!("last 8 bits: {:?}, {:?}, {:?}", &a[24..32], &b[24..32], &r2[24..32]);
-    // println!("a: {:?}", a);
-    // println!("b: {:?}", b);
-
-    res[0] = r1;
-    res[1] = r2;
+pub(crate) async fn receive_blocks<IO: AsyncCommunicationChannel, const N: usize>(io: &mut IO) -> Result<Vec<[u8; N]>> {
+    let bytes = match N {
+        16 => io.receive_data().await?.into_flattened(),
+        32 => io.receive_32byte_block().await?.into_flattened(),
+        _ => return Err(VoleError::Parameter(format!("No message for {}-byte blocks", N))),
+    };
+    Ok(to_blocks(&bytes))
 }
 
-
-// Helper function to perform 64-bit carry-less multiplication
-fn clmul64(a: u64, b: u64) -> u128 {
-    let mut result = 0u128;
-    for i in 0..64 {
-        if (b & (1 << i)) != 0 {
-            result ^= (a as u128) << i;
-        }
-    }
-    result
+fn to_blocks<const N: usize>(bytes: &[u8]) -> Vec<[u8; N]> {
+    bytes.chunks_exact(N).map(|chunk| chunk.try_into().unwrap()).collect()
 }
 
-fn vector_inn_prdt_sum_no_red(res: &mut [[u8; 32]; 2], a: &[[u8; 32]], b: &[[u8; 32]]) {
-    // let mut r1 = [0u8; 16]; // Accumulator for first half
-    // let mut r2 = [0u8; 16]; // Accumulator for second half
-    let mut r1 = [0u8; 32];
-    let mut r2 = [0u8; 32];
-    let mut r11 = [[0u8; 32]; 2];
-
-    for i in 0..a.len() {
-        mul256(&a[i], &b[i], &mut r11); // Perform 128-bit multiplication
-        for byt in 0..32 {
-            r1[byt] = r1[byt] ^ r11[0][byt];
-            r2[byt] = r2[byt] ^ r11[1][byt];
+// Helper functions
+pub(crate) fn block_to_bool<const N: usize>(block: &[u8; N]) -> Vec<bool> {
+    let mut result = vec![false; 8 * N];
+    for (i, byte) in block.iter().enumerate() {
+        for bit in 0..8 {
+            result[i * 8 + bit] = (byte >> bit) & 1 != 0;
         }
     }
-
-    res[0] = r1;
-    res[1] = r2;
+    result
 }
 
-// Helper functions
-fn bool_to_block(bits: &[bool]) -> [u8; 32] {
-    let mut block = [0u8; 32];
+pub(crate) fn bool_to_block<const N: usize>(bits: &[bool]) -> [u8; N] {
+    let mut block = [0u8; N];
     for (i, &bit) in bits.iter().enumerate() {
         if bit {
             block[i / 8] |= 1 << (i % 8);
@@ -472,131 +554,27 @@ fn bool_to_block(bits: &[bool]) -> [u8; 32] {
     block
 }
 
-fn cmp_blocks(a: &[[u8; 32]], b: &[[u8; 32]]) -> bool {
-    a == b
-}
-
-fn xor_blocks(a: &mut [[u8; 32]], b: &[[u8; 32]]) {
-    for i in 0..a.len() {
-        a[i][0] ^= b[i][0];
-        a[i][1] ^= b[i][1];
-        a[i][2] ^= b[i][2];
-        a[i][3] ^= b[i][3];
-        a[i][4] ^= b[i][4];
-        a[i][5] ^= b[i][5];
-        a[i][6] ^= b[i][6];
-        a[i][7] ^= b[i][7];
-        a[i][8] ^= b[i][8];
-        a[i][9] ^= b[i][9];
-        a[i][10] ^= b[i][10];
-        a[i][11] ^= b[i][11];
-        a[i][12] ^= b[i][12];
-        a[i][13] ^= b[i][13];
-        a[i][14] ^= b[i][14];
-        a[i][15] ^= b[i][15];
-        a[i][16] ^= b[i][16];
-        a[i][17] ^= b[i][17];
-        a[i][18] ^= b[i][18];
-        a[i][19] ^= b[i][19];
-        a[i][20] ^= b[i][20];
-        a[i][21] ^= b[i][21];
-        a[i][22] ^= b[i][22];
-        a[i][23] ^= b[i][23];
-        a[i][24] ^= b[i][24];
-        a[i][25] ^= b[i][25];
-        a[i][26] ^= b[i][26];
-        a[i][27] ^= b[i][27];
-        a[i][28] ^= b[i][28];
-        a[i][29] ^= b[i][29];
-        a[i][30] ^= b[i][30];
-        a[i][31] ^= b[i][31];
+// Plain loops over whole blocks, the compiler turns them into SIMD
+pub(crate) fn xor_blocks<const N: usize>(a: &mut [[u8; N]], b: &[[u8; N]]) {
+    for (x, y) in a.iter_mut().zip(b.iter()) {
+        for k in 0..N {
+            x[k] ^= y[k];
+        }
     }
 }
 
-fn and_blocks(a: &mut [[u8; 32]], b: &[[u8; 32]]) {
-    for i in 0..a.len() {
-        a[i][0] &= b[i][0];
-        a[i][1] &= b[i][1];
-        a[i][2] &= b[i][2];
-        a[i][3] &= b[i][3];
-        a[i][4] &= b[i][4];
-        a[i][5] &= b[i][5];
-        a[i][6] &= b[i][6];
-        a[i][7] &= b[i][7];
-        a[i][8] &= b[i][8];
-        a[i][9] &= b[i][9];
-        a[i][10] &= b[i][10];
-        a[i][11] &= b[i][11];
-        a[i][12] &= b[i][12];
-        a[i][13] &= b[i][13];
-        a[i][14] &= b[i][14];
-        a[i][15] &= b[i][15];
-        a[i][16] &= b[i][16];
-        a[i][17] &= b[i][17];
-        a[i][18] &= b[i][18];
-        a[i][19] &= b[i][19];
-        a[i][20] &= b[i][20];
-        a[i][21] &= b[i][21];
-        a[i][22] &= b[i][22];
-        a[i][23] &= b[i][23];
-        a[i][24] &= b[i][24];
-        a[i][25] &= b[i][25];
-        a[i][26] &= b[i][26];
-        a[i][27] &= b[i][27];
-        a[i][28] &= b[i][28];
-        a[i][29] &= b[i][29];
-        a[i][30] &= b[i][30];
-        a[i][31] &= b[i][31];
+fn and_blocks<const N: usize>(a: &mut [[u8; N]], b: &[[u8; N]]) {
+    for (x, y) in a.iter_mut().zip(b.iter()) {
+        for k in 0..N {
+            x[k] &= y[k];
+        }
     }
 }
 
-fn xor_blocks_arr(res: &mut [[u8; 32]], x: &[[u8; 32]], y: &[[u8; 32]]) {
+fn xor_blocks_arr<const N: usize>(res: &mut [[u8; N]], x: &[[u8; N]], y: &[[u8; N]]) {
     for ((r, a), b) in res.iter_mut().zip(x.iter()).zip(y.iter()) {
-        r[0] = a[0] ^ b[0];
-        r[1] = a[1] ^ b[1];
-        r[2] = a[2] ^ b[2];
-        r[3] = a[3] ^ b[3];
-        r[4] = a[4] ^ b[4];
-        r[5] = a[5] ^ b[5];
-        r[6] = a[6] ^ b[6];
-        r[7] = a[7] ^ b[7];
-        r[8] = a[8] ^ b[8];
-        r[9] = a[9] ^ b[9];
-        r[10] = a[10] ^ b[10];
-        r[11] = a[11] ^ b[11];
-        r[12] = a[12] ^ b[12];
-        r[13] = a[13] ^ b[13];
-        r[14] = a[14] ^ b[14];
-        r[15] = a[15] ^ b[15];
-        r[16] = a[16] ^ b[16];
-        r[17] = a[17] ^ b[17];
-        r[18] = a[18] ^ b[18];
-        r[19] = a[19] ^ b[19];
-        r[20] = a[20] ^ b[20];
-        r[21] = a[21] ^ b[21];
-        r[22] = a[22] ^ b[22];
-        r[23] = a[23] ^ b[23];
-        r[24] = a[24] ^ b[24];
-        r[25] = a[25] ^ b[25];
-        r[26] = a[26] ^ b[26];
-        r[27] = a[27] ^ b[27];
-        r[28] = a[28] ^ b[28];
-        r[29] = a[29] ^ b[29];
-        r[30] = a[30] ^ b[30];
-        r[31] = a[31] ^ b[31];
-    }
-}
-
-
-fn transpose(out: &mut [[u8; 32]], t: &[[u8; 32]], num_bits: usize, block_size: usize) {
-    // println!("Size of out is: {} x {}", out.len(), out[0].len() * 8);
-    // println!("Size of t is: {} x {}", t.len(), t[0].len() * 8);
-    for row in 0..num_bits {
-        for col in 0..block_size {
-            let idx = row * block_size + col;
-            let bit = (t[idx / num_bits][(idx / 8) % 32] >> (idx % 8)) & 1;
-            let new_idx = col * num_bits + row;
-            out[new_idx / num_bits][(new_idx / 8) % 32] |= bit << (new_idx % 8);
+        for k in 0..N {
+            r[k] = a[k] ^ b[k];
         }
     }
-}
\ No newline at end of file
+}
@@ -2,24 +2,50 @@
 
 extern crate lambdaworks_math;
 extern crate rand;
+extern crate rand_chacha;
 extern crate p256;
 extern crate sha2;
 extern crate aes;
+extern crate aes_gcm;
+extern crate hkdf;
 
+pub mod error;
 pub mod ot;
 pub mod hash;
 pub mod comm_channel;
+pub mod framing;
+pub mod async_comm_channel;
 pub mod socket_channel;
+pub mod async_socket_channel;
 pub mod prg;
+pub mod parallel;
 pub mod cope;
 pub mod base_svole;
 pub mod two_key_prp;
 pub mod preot;
 pub mod iknp;
+pub mod softspoken;
+pub mod ot_extension;
+pub mod transpose;
+pub mod gf2k;
 pub mod base_cot;
 pub mod spfss_sender;
 pub mod spfss_receiver;
 pub mod mpfss_reg;
 pub mod prp;
 pub mod lpn;
-pub mod vole_triple;
\ No newline at end of file
+pub mod vole_triple;
+pub mod field;
+pub mod fp61;
+pub mod ext_field;
+pub mod mem_channel;
+pub mod counting_channel;
+pub mod sim_channel;
+pub mod transcript;
+pub mod secure_channel;
+pub mod mux_channel;
+pub mod spcot_sender;
+pub mod spcot_receiver;
+pub mod mpcot_reg;
+pub mod lpn_f2;
+pub mod ferret_cot;
\ No newline at end of file
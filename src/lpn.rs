@@ -1,121 +1,150 @@
-use crate::prp::{PRP, LubyRackoffPRP, FieldPRP};
-use lambdaworks_math::field::fields::fft_friendly::stark_252_prime_field::Stark252PrimeField;
+use crate::field::{VoleField, VoleSubfield, DefaultField};
+use crate::parallel::{default_threads, parallel_for_each};
+use aes::{Aes128, Aes256};
+use aes::cipher::{BlockEncrypt, KeyInit, generic_array::GenericArray};
+use aes::cipher::consts::U16;
 use lambdaworks_math::field::element::FieldElement;
-use lambdaworks_math::traits::ByteConversion;
 
-pub type F = Stark252PrimeField;
-pub type FE = FieldElement<F>;
+type Block = GenericArray<u8, U16>;
 
-pub struct Lpn {
+// Non-zero entries in each row of the matrix
+const ROW_WEIGHT: usize = 10;
+// Rows whose indices and coefficients come out of one batch of AES calls, small enough to stay in L1
+const ROWS_PER_BATCH: usize = 32;
+// Rows a thread encodes at a time
+const ROWS_PER_CHUNK: usize = 4096;
+
+/// Primal LPN encoder, the matrix and M live in `F`, K lives in the MAC field `K`.
+///
+/// Row `i` of the matrix has `ROW_WEIGHT` non-zero entries. Their columns and
+/// coefficients are fixed-key AES of counters `(i, j)`: the AES-128 key `seed` gives
+/// two columns per block, the AES-256 key `seed_field` gives the coefficients, one
+/// block per 16 bytes that `VoleField::from_random_bytes` reads. Both parties derive
+/// the same matrix from the same seeds, whatever their number of threads.
+pub struct Lpn<F: VoleSubfield<K> = DefaultField, K: VoleField = F> {
     party: usize,
-    k: usize, 
+    k: usize,
     n: usize,
-    seed: [u8; 16],
-    seed_field: [u8; 32],
-    M: Vec<FE>,
-    preM: Vec<FE>,
-    // prex: Vec<FE>,
-    K: Vec<FE>,
-    preK: Vec<FE>,
+    index_cipher: Aes128,
+    coef_cipher: Aes256,
+    threads: usize,
+    _field: std::marker::PhantomData<(F, K)>,
 }
 
-impl Lpn {
+impl<F: VoleSubfield<K>, K: VoleField> Lpn<F, K> {
+    // AES blocks per coefficient, enough for what `from_random_bytes` reads
+    const COEF_BLOCKS: usize = (F::RANDOM_BYTES + 15) / 16;
+
     pub fn new(k: usize, n: usize, seed: &[u8; 16], seed_field: &[u8; 32]) -> Self {
         Self {
             party: 0,
             k: k,
             n: n,
-            seed: *seed,
-            seed_field: *seed_field,
-            M: vec![FE::zero(); n],
-            preM: vec![FE::zero(); k],
-            K: vec![FE::zero(); n],
-            preK: vec![FE::zero(); k],
+            index_cipher: Aes128::new(GenericArray::from_slice(seed)),
+            coef_cipher: Aes256::new(GenericArray::from_slice(seed_field)),
+            threads: default_threads(),
+            _field: std::marker::PhantomData,
         }
     }
 
-    pub fn compute_K(&mut self) {
-        let prp = PRP::new(Some(&self.seed));
-        // let field_prp = LubyRackoffPRP::new(Some(&self.seed_field));
-        let field_prp = FieldPRP::new(Some(&self.seed_field));
-        for i in 0..self.n {
-            let mut tmp = vec![[0u8; 16]; 10];
-            let mut tmp2 = vec![[0u8; 32]; 10];
-            for m in 0..10 {
-                tmp[m][0..8].copy_from_slice(&i.to_le_bytes());
-                tmp[m][8..].copy_from_slice(&(m as usize).to_le_bytes());
-                tmp2[m][0..8].copy_from_slice(&i.to_le_bytes());
-                tmp2[m][8..16].copy_from_slice(&(m as usize).to_le_bytes());
-            }
-
-            prp.permute_block(&mut tmp, 10);
-            let r: Vec<usize> = tmp
-                .iter()
-                .map(|x| ((u128::from_le_bytes(*x) >> 64) as usize) % self.k)
-                .collect();
-
-            let mut tmp_field: Vec<_> = tmp2
-                .iter()
-                .map(|x| FE::from_bytes_le(x).expect("Cannot get FE from bytes"))
-                .collect();
-            field_prp.permute_block(&mut tmp_field, 10);
-
-            for m in 0..10 {
-                self.K[i] += tmp_field[m] * self.preK[r[m]];
-            }
-        }
+    /// Number of threads encoding the rows, one per core by default.
+    /// The output is the same for any number.
+    pub fn set_threads(&mut self, threads: usize) {
+        self.threads = threads.max(1);
     }
 
-    pub fn compute_K_and_M(&mut self) {
-        let prp = PRP::new(Some(&self.seed));
-        // let field_prp = LubyRackoffPRP::new(Some(&self.seed_field));
-        let field_prp = FieldPRP::new(Some(&self.seed_field));
-        for i in 0..self.n {
-            let mut tmp = vec![[0u8; 16]; 10];
-            let mut tmp2 = vec![[0u8; 32]; 10];
-            for m in 0..10 {
-                tmp[m][0..8].copy_from_slice(&i.to_le_bytes());
-                tmp[m][8..].copy_from_slice(&(m as usize).to_le_bytes());
-                tmp2[m][0..8].copy_from_slice(&i.to_le_bytes());
-                tmp2[m][8..16].copy_from_slice(&(m as usize).to_le_bytes());
-            }
+    /// Adds the encoding of `preK` to `K`, and that of `preM` to `M` unless it is empty
+    fn encode(&self, K: &mut [FieldElement<K>], M: &mut [FieldElement<F>], preK: &[FieldElement<K>], preM: &[FieldElement<F>]) {
+        assert_eq!(K.len(), self.n, "LPN output of the wrong length");
+        assert_eq!(preK.len(), self.k, "LPN input of the wrong length");
+        let mut chunks: Vec<(usize, &mut [FieldElement<K>], &mut [FieldElement<F>])> = if M.is_empty() {
+            K.chunks_mut(ROWS_PER_CHUNK)
+                .enumerate()
+                .map(|(c, K)| (c * ROWS_PER_CHUNK, K, &mut [][..]))
+                .collect()
+        } else {
+            assert_eq!(M.len(), self.n, "LPN output of the wrong length");
+            assert_eq!(preM.len(), self.k, "LPN input of the wrong length");
+            K.chunks_mut(ROWS_PER_CHUNK)
+                .zip(M.chunks_mut(ROWS_PER_CHUNK))
+                .enumerate()
+                .map(|(c, (K, M))| (c * ROWS_PER_CHUNK, K, M))
+                .collect()
+        };
+        parallel_for_each(&mut chunks, self.threads, |(first_row, K, M)| {
+            self.encode_chunk(*first_row, K, M, preK, preM)
+        });
+    }
 
-            prp.permute_block(&mut tmp, 10);
-            let r: Vec<usize> = tmp
-                .iter()
-                .map(|x| ((u128::from_le_bytes(*x) >> 64) as usize) % self.k)
-                .collect();
+    fn encode_chunk(&self, first_row: usize, K: &mut [FieldElement<K>], M: &mut [FieldElement<F>], preK: &[FieldElement<K>], preM: &[FieldElement<F>]) {
+        let mut index_blocks = [Block::default(); ROWS_PER_BATCH * ROW_WEIGHT / 2];
+        let mut coef_blocks = [Block::default(); ROWS_PER_BATCH * ROW_WEIGHT * 2];
+        let coef_blocks_per_row = ROW_WEIGHT * Self::COEF_BLOCKS;
 
+        for batch in (0..K.len()).step_by(ROWS_PER_BATCH) {
+            let rows = ROWS_PER_BATCH.min(K.len() - batch);
+            let index_blocks = &mut index_blocks[..rows * ROW_WEIGHT / 2];
+            let coef_blocks = &mut coef_blocks[..rows * coef_blocks_per_row];
+            for r in 0..rows {
+                let row = ((first_row + batch + r) as u64).to_le_bytes();
+                for (j, block) in index_blocks[r * ROW_WEIGHT / 2..(r + 1) * ROW_WEIGHT / 2].iter_mut().enumerate() {
+                    block[..8].copy_from_slice(&row);
+                    block[8..].copy_from_slice(&(j as u64).to_le_bytes());
+                }
+                for (j, block) in coef_blocks[r * coef_blocks_per_row..(r + 1) * coef_blocks_per_row].iter_mut().enumerate() {
+                    block[..8].copy_from_slice(&row);
+                    block[8..].copy_from_slice(&(j as u64).to_le_bytes());
+                }
+            }
+            self.index_cipher.encrypt_blocks(index_blocks);
+            self.coef_cipher.encrypt_blocks(coef_blocks);
 
-            let mut tmp_field: Vec<_> = tmp2
-                .iter()
-                .map(|x| FE::from_bytes_le(x).expect("Cannot get FE from bytes"))
-                .collect();
-            field_prp.permute_block(&mut tmp_field, 10);
+            for r in 0..rows {
+                let mut columns = [0usize; ROW_WEIGHT];
+                for (j, block) in index_blocks[r * ROW_WEIGHT / 2..(r + 1) * ROW_WEIGHT / 2].iter().enumerate() {
+                    let value = u128::from_le_bytes((*block).into());
+                    columns[2 * j] = ((value as u64) % self.k as u64) as usize;
+                    columns[2 * j + 1] = (((value >> 64) as u64) % self.k as u64) as usize;
+                }
 
-            for m in 0..10 {
-                self.K[i] += tmp_field[m] * self.preK[r[m]];
-                self.M[i] += tmp_field[m] * self.preM[r[m]];
+                let mut acc_K = FieldElement::<K>::zero();
+                let mut acc_M = FieldElement::<F>::zero();
+                let mut bytes = [0u8; 32];
+                for (j, &column) in columns.iter().enumerate() {
+                    for (h, block) in coef_blocks[(r * ROW_WEIGHT + j) * Self::COEF_BLOCKS..][..Self::COEF_BLOCKS].iter().enumerate() {
+                        bytes[16 * h..16 * (h + 1)].copy_from_slice(block);
+                    }
+                    let coef = F::from_random_bytes(&bytes);
+                    acc_K += F::scale(&coef, &preK[column]);
+                    if !M.is_empty() {
+                        acc_M += coef * preM[column];
+                    }
+                }
+                K[batch + r] += acc_K;
+                if !M.is_empty() {
+                    M[batch + r] += acc_M;
+                }
             }
         }
     }
 
-    pub fn compute_send(&mut self, K: &mut [FE], kkK: &[FE]) {
+    /// Adds the encoding of `preK` to `K`.
+    pub fn compute_K(&self, K: &mut [FieldElement<K>], preK: &[FieldElement<K>]) {
+        self.encode(K, &mut [], preK, &[]);
+    }
+
+    /// Adds the encodings of `preK` to `K` and of `preM` to `M`, see `compute_K`.
+    pub fn compute_K_and_M(&self, K: &mut [FieldElement<K>], M: &mut [FieldElement<F>], preK: &[FieldElement<K>], preM: &[FieldElement<F>]) {
+        self.encode(K, M, preK, preM);
+    }
+
+    pub fn compute_send(&mut self, K: &mut [FieldElement<K>], kkK: &[FieldElement<K>]) {
         self.party = 0;
-        self.K.copy_from_slice(K);
-        self.preK.copy_from_slice(kkK);
-        self.compute_K();
-        K.copy_from_slice(&self.K);
+        self.compute_K(K, kkK);
     }
 
-    pub fn compute_recv(&mut self, K: &mut [FE], M: &mut [FE], kkK: &[FE], kkM: &[FE]) {
+    pub fn compute_recv(&mut self, K: &mut [FieldElement<K>], M: &mut [FieldElement<F>], kkK: &[FieldElement<K>], kkM: &[FieldElement<F>]) {
         self.party = 1;
-        self.K.copy_from_slice(K);
-        self.preK.copy_from_slice(kkK);
-        self.M.copy_from_slice(M);
-        self.preM.copy_from_slice(kkM);
-        self.compute_K_and_M();
-        K.copy_from_slice(&self.K);
-        M.copy_from_slice(&self.M);
+        self.compute_K_and_M(K, M, kkK, kkM);
     }
-}
\ No newline at end of file
+}
@@ -0,0 +1,38 @@
+use crate::prp::PRP;
+use crate::hash::Block;
+use crate::spcot_sender::xor_block_inplace;
+
+/// Primal LPN encoder over GF(2): every output is XORed with 10 random entries of the k pre-COTs.
+pub struct LpnF2 {
+    k: usize,
+    n: usize,
+    seed: [u8; 16],
+}
+
+impl LpnF2 {
+    pub fn new(k: usize, n: usize, seed: &[u8; 16]) -> Self {
+        Self {
+            k: k,
+            n: n,
+            seed: *seed,
+        }
+    }
+
+    /// nn[i] ^= kk[r_0] ^ ... ^ kk[r_9], both parties run it on their own shares.
+    pub fn compute(&self, nn: &mut [Block], kk: &[Block]) {
+        let prp = PRP::new(Some(&self.seed));
+        for i in 0..self.n {
+            let mut tmp = [[0u8; 16]; 10];
+            for m in 0..10 {
+                tmp[m][0..8].copy_from_slice(&i.to_le_bytes());
+                tmp[m][8..].copy_from_slice(&(m as usize).to_le_bytes());
+            }
+
+            prp.permute_block(&mut tmp, 10);
+            for x in tmp.iter() {
+                let r = ((u128::from_le_bytes(*x) >> 64) as usize) % self.k;
+                xor_block_inplace(&mut nn[i], &kk[r]);
+            }
+        }
+    }
+}
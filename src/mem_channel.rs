@@ -0,0 +1,117 @@
+use crate::comm_channel::CommunicationChannel;
+
+use std::sync::mpsc::{channel, Receiver, Sender};
+use p256::EncodedPoint;
+
+use lambdaworks_math::field::fields::fft_friendly::stark_252_prime_field::Stark252PrimeField;
+use lambdaworks_math::field::element::FieldElement;
+use crate::field::VoleField;
+use crate::framing::{self, Framer, MessageKind, HEADER_LEN};
+use crate::error::{Result, VoleError};
+
+pub type FE = FieldElement<Stark252PrimeField>;
+
+/// In-process channel, so both parties can run as two threads of one program.
+///
+/// Each send is one frame on an mpsc queue, so sends never block and `flush` is a no-op.
+/// Build the two ends with `MemChannel::pair`.
+pub struct MemChannel {
+    tx: Sender<Vec<u8>>,
+    rx: Receiver<Vec<u8>>,
+    framer: Framer,
+}
+
+impl MemChannel {
+    /// Creates the two connected ends of a channel
+    pub fn pair() -> (Self, Self) {
+        let (tx_a, rx_b) = channel();
+        let (tx_b, rx_a) = channel();
+        (
+            Self { tx: tx_a, rx: rx_a, framer: Framer::default() },
+            Self { tx: tx_b, rx: rx_b, framer: Framer::default() },
+        )
+    }
+
+    fn send_message(&mut self, message: Vec<u8>) -> Result<()> {
+        self.tx.send(message).map_err(|_| {
+            VoleError::Io(std::io::Error::new(std::io::ErrorKind::BrokenPipe, "Peer dropped the channel"))
+        })
+    }
+
+    fn receive_message(&mut self) -> Result<Vec<u8>> {
+        self.rx.recv().map_err(|_| {
+            VoleError::Io(std::io::Error::new(std::io::ErrorKind::UnexpectedEof, "Peer dropped the channel"))
+        })
+    }
+
+    fn send_frame(&mut self, kind: MessageKind, payload: &[u8]) -> Result<()> {
+        let frame = self.framer.frame(kind, payload);
+        self.send_message(frame)
+    }
+
+    fn receive_frame(&mut self, kind: MessageKind) -> Result<Vec<u8>> {
+        let mut frame = self.receive_message()?;
+        self.framer.split(&frame, kind)?;
+        frame.drain(..HEADER_LEN);
+        Ok(frame)
+    }
+}
+
+impl CommunicationChannel for MemChannel {
+    fn send_bits(&mut self, bits: &[bool]) -> Result<()> {
+        self.send_frame(MessageKind::Bits, &framing::encode_bits(bits))
+    }
+
+    fn receive_bits(&mut self) -> Result<Vec<bool>> {
+        let payload = self.receive_frame(MessageKind::Bits)?;
+        framing::decode_bits(&payload)
+    }
+
+    fn send_stark252(&mut self, elements: &[FE]) -> Result<()> {
+        self.send_field_elements(elements)
+    }
+
+    fn receive_stark252(&mut self, count: usize) -> Result<Vec<FE>> {
+        self.receive_field_elements(count)
+    }
+
+    fn send_field_elements<F: VoleField>(&mut self, elements: &[FieldElement<F>]) -> Result<()> {
+        self.send_frame(MessageKind::FieldElements, &framing::encode_field_elements(elements))
+    }
+
+    fn receive_field_elements<F: VoleField>(&mut self, count: usize) -> Result<Vec<FieldElement<F>>> {
+        let payload = self.receive_frame(MessageKind::FieldElements)?;
+        framing::decode_field_elements(&payload, count)
+    }
+
+    fn send_point(&mut self, point: &EncodedPoint) -> Result<()> {
+        self.send_frame(MessageKind::Point, point.as_bytes())
+    }
+
+    fn receive_point(&mut self) -> Result<EncodedPoint> {
+        let payload = self.receive_frame(MessageKind::Point)?;
+        framing::decode_point(&payload)
+    }
+
+    fn send_data(&mut self, data: &[[u8; 16]]) -> Result<()> {
+        self.send_frame(MessageKind::Data, &framing::encode_blocks(data))
+    }
+
+    fn receive_data(&mut self) -> Result<Vec<[u8; 16]>> {
+        let payload = self.receive_frame(MessageKind::Data)?;
+        framing::decode_blocks(&payload)
+    }
+
+    fn send_32byte_block(&mut self, data: &[[u8; 32]]) -> Result<()> {
+        self.send_frame(MessageKind::Block32, &framing::encode_blocks(data))
+    }
+
+    fn receive_32byte_block(&mut self) -> Result<Vec<[u8; 32]>> {
+        let payload = self.receive_frame(MessageKind::Block32)?;
+        framing::decode_blocks(&payload)
+    }
+
+    fn flush(&mut self) -> Result<()> {
+        Ok(())
+    }
+}
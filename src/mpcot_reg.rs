@@ -0,0 +1,83 @@
+use crate::prg::PRG;
+use crate::preot::OTPre;
+use crate::comm_channel::CommunicationChannel;
+use crate::spcot_sender::SpcotSender;
+use crate::spcot_receiver::SpcotRecver;
+use crate::hash::Block;
+use crate::error::Result;
+
+// Regular-noise MPCOT over GF(2^128), semi-honest, no multithreading
+pub struct MpcotReg {
+    party: usize,
+    idx_max: usize,
+    tree_height: usize,
+    leave_n: usize,
+    tree_n: usize,
+    prg: PRG,
+    delta: Block,
+    item_pos_receiver: Vec<usize>,
+}
+
+impl MpcotReg {
+    pub fn new(n: usize, t: usize, log_bin_sz: usize, party: usize) -> Self {
+        // make sure n = t * leave_n
+        MpcotReg {
+            party: party,
+            idx_max: n,
+            tree_height: log_bin_sz + 1,
+            leave_n: 1 << log_bin_sz,
+            tree_n: t,
+            prg: PRG::new(None, 0),
+            delta: [0u8; 16],
+            item_pos_receiver: vec![0; t],
+        }
+    }
+
+    pub fn sender_init(&mut self, delta: Block) {
+        self.delta = delta;
+    }
+
+    pub fn receiver_init(&mut self) {
+    }
+
+    /// Noise positions of the last `mpcot_receiver` call, one per bin.
+    pub fn item_pos(&self) -> &[usize] {
+        &self.item_pos_receiver
+    }
+
+    pub fn mpcot_sender<IO: CommunicationChannel>(&mut self, io: &mut IO, ot: &mut OTPre<16>, sparse_vector: &mut [Block]) -> Result<()> {
+        // Set up PreOT first
+        for i in 0..self.tree_n {
+            ot.choices_sender(io)?;
+        }
+        io.flush()?;
+        ot.reset();
+
+        for i in 0..self.tree_n {
+            let mut sender = SpcotSender::new(self.tree_height);
+            sender.compute(&mut sparse_vector[i*self.leave_n..(i+1)*self.leave_n], self.delta);
+            sender.send(io, ot, i)?;
+        }
+        io.flush()
+    }
+
+    pub fn mpcot_receiver<IO: CommunicationChannel>(&mut self, io: &mut IO, ot: &mut OTPre<16>, sparse_vector: &mut [Block]) -> Result<()> {
+        // The path bits are random, so is the noise position in every bin
+        let mut receivers = Vec::with_capacity(self.tree_n);
+        for i in 0..self.tree_n {
+            let mut receiver = SpcotRecver::new(self.tree_height);
+            self.prg.random_bool_array(&mut receiver.b);
+            ot.choices_recver(io, &receiver.b)?;
+            receivers.push(receiver);
+        }
+        io.flush()?;
+        ot.reset();
+
+        for (i, receiver) in receivers.iter_mut().enumerate() {
+            self.item_pos_receiver[i] = receiver.get_index();
+            receiver.recv(io, ot, i)?;
+            receiver.compute(&mut sparse_vector[i*self.leave_n..(i+1)*self.leave_n]);
+        }
+        Ok(())
+    }
+}
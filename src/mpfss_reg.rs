@@ -1,18 +1,20 @@
 use crate::prg::PRG;
 use crate::preot::OTPre;
 use crate::comm_channel::CommunicationChannel;
+use crate::async_comm_channel::{AsyncCommunicationChannel, SyncChannel, block_on};
 use crate::spfss_sender::SpfssSenderFp;
 use crate::spfss_receiver::SpfssRecverFp;
+use crate::two_key_prp::GgmExpansion;
 use crate::hash::Hash;
-use lambdaworks_math::field::fields::fft_friendly::stark_252_prime_field::Stark252PrimeField;
+use crate::error::{Result, VoleError, CheckFailure};
+use crate::field::{VoleField, VoleSubfield, DefaultField};
+use crate::parallel::{default_threads, parallel_for_each};
 use lambdaworks_math::field::element::FieldElement;
-use lambdaworks_math::traits::ByteConversion;
 
-pub type F = Stark252PrimeField;
-pub type FE = FieldElement<F>;
-
-// No multithreading
-pub struct MpfssReg {
+// The noise values are in F, the trees, keys and MACs are in K
+// The GGM trees are expanded on `threads` threads, the channel is only used from the
+// calling thread and in tree order, so the messages do not depend on `threads`
+pub struct MpfssReg<F: VoleSubfield<K> = DefaultField, K: VoleField = F> {
     party: usize,
     item_n: usize,
     idx_max: usize, 
@@ -21,17 +23,18 @@ pub struct MpfssReg {
     leave_n: usize,
     tree_n: usize,
     is_malicious: bool,
+    threads: usize,
+    expansion: GgmExpansion,
     prg: PRG,
-    secret_share_x: FE,
-    ggm_tree: Vec<Vec<FE>>,
-    check_chialpha_buf: Vec<FE>,
-    check_vw_buf: Vec<FE>,
+    secret_share_x: FieldElement<K>,
+    check_chialpha_buf: Vec<FieldElement<K>>,
+    check_vw_buf: Vec<FieldElement<K>>,
     item_pos_receiver: Vec<usize>,
-    triple_y: Vec<FE>,
-    triple_z: Vec<FE>,
+    triple_y: Vec<FieldElement<K>>,
+    triple_z: Vec<FieldElement<F>>,
 }
 
-impl MpfssReg {
+impl<F: VoleSubfield<K>, K: VoleField> MpfssReg<F, K> {
     pub fn new(n: usize, t: usize, log_bin_sz: usize, party: usize) -> Self {
         // make sure n = t * leave_n
         MpfssReg {
@@ -43,14 +46,15 @@ impl MpfssReg {
             leave_n: 1 << log_bin_sz,
             tree_n: t,
             is_malicious: false,
+            threads: default_threads(),
+            expansion: GgmExpansion::default(),
             prg: PRG::new(None, 0),
-            secret_share_x: FE::zero(),
-            ggm_tree: vec![vec![FE::zero(); 1 << log_bin_sz]; t],
-            check_chialpha_buf: vec![FE::zero(); t],
-            check_vw_buf: vec![FE::zero(); t],
+            secret_share_x: FieldElement::zero(),
+            check_chialpha_buf: vec![FieldElement::zero(); t],
+            check_vw_buf: vec![FieldElement::zero(); t],
             item_pos_receiver: vec![0; t],
-            triple_y: vec![FE::zero(); t+1],
-            triple_z: vec![FE::zero(); t+1],
+            triple_y: vec![FieldElement::zero(); t + F::DEGREE],
+            triple_z: vec![FieldElement::zero(); t + F::DEGREE],
         }
     }
 
@@ -58,14 +62,29 @@ impl MpfssReg {
         self.is_malicious = true;
     }
 
-    pub fn sender_init(&mut self, delta: FE) {
+    /// Number of threads expanding the GGM trees, one per core by default.
+    pub fn set_threads(&mut self, threads: usize) {
+        self.threads = threads.max(1);
+    }
+
+    /// How the GGM trees are expanded, fixed-key AES by default. Both parties must use the same.
+    pub fn set_ggm_expansion(&mut self, expansion: GgmExpansion) {
+        self.expansion = expansion;
+    }
+
+    /// Number of sVOLE correlations consumed per call: one per tree, then the check mask.
+    pub fn triple_n(&self) -> usize {
+        self.tree_n + F::DEGREE
+    }
+
+    pub fn sender_init(&mut self, delta: FieldElement<K>) {
         self.secret_share_x = delta.clone();
     }
 
     pub fn receiver_init(&mut self) {
     }
 
-    pub fn set_vec_x(&self, out_vec: &mut [FE], in_vec: &[FE]) {
+    pub fn set_vec_x(&self, out_vec: &mut [FieldElement<F>], in_vec: &[FieldElement<F>]) {
         for i in 0..self.tree_n {
             let pt = i * self.leave_n + self.item_pos_receiver[i] % self.leave_n;
             // not sure, check the math later
@@ -73,134 +92,183 @@ impl MpfssReg {
         }
     }
 
-    pub fn mpfss_sender<IO: CommunicationChannel>(&mut self, io: &mut IO, ot: &mut OTPre, triple_y: &[FE], sparse_vector: &mut [FE]) {
+    pub fn mpfss_sender<IO: CommunicationChannel>(&mut self, io: &mut IO, ot: &mut OTPre, triple_y: &[FieldElement<K>], sparse_vector: &mut [FieldElement<K>]) -> Result<()> {
+        block_on(self.mpfss_sender_async(&mut SyncChannel(io), ot, triple_y, sparse_vector))
+    }
+
+    /// Async variant of `mpfss_sender`.
+    pub async fn mpfss_sender_async<IO: AsyncCommunicationChannel>(&mut self, io: &mut IO, ot: &mut OTPre, triple_y: &[FieldElement<K>], sparse_vector: &mut [FieldElement<K>]) -> Result<()> {
         // triple_y_recv = triple_y_send + delta * triple_z
 
-        self.triple_y.copy_from_slice(&triple_y[..self.tree_n+1]);
+        self.triple_y.copy_from_slice(&triple_y[..self.tree_n + F::DEGREE]);
 
         // Set up PreOT first
         for i in 0..self.tree_n {
-            ot.choices_sender(io);
+            ot.choices_sender_async(io).await?;
         }
-        io.flush();
+        io.flush().await?;
         ot.reset();
 
-        let mut seeds = vec![FE::zero(); self.tree_n];
+        let mut seeds = vec![FieldElement::zero(); self.tree_n];
         if self.is_malicious {
-            self.seed_expand(io, &mut seeds, self.tree_n);
+            self.seed_expand_async(io, &mut seeds).await?;
         }
-        io.flush();
-
-        println!("Done creating seeds");
+        io.flush().await?;
 
         // Now start doing Spfss
-        for i in 0..self.tree_n {
-            let mut sender = SpfssSenderFp::new(self.tree_height);
-            sender.compute(&mut self.ggm_tree[i], self.secret_share_x, self.triple_y[i]);
-            sender.send(io, ot, i);
-            sparse_vector[i*self.leave_n..(i+1)*self.leave_n].copy_from_slice(&self.ggm_tree[i]);
-
+        // The tree seeds come from this thread's PRG, so the trees do not depend on `threads` either
+        let mut tree_seeds = vec![[0u8; 16]; self.tree_n];
+        self.prg.random_block(&mut tree_seeds);
+        let (threads, leave_n, tree_height, is_malicious) = (self.threads, self.leave_n, self.tree_height, self.is_malicious);
+        let (delta, triple_y, expansion) = (self.secret_share_x, &self.triple_y, self.expansion);
+        let mut jobs: Vec<_> = sparse_vector[..self.tree_n * leave_n]
+            .chunks_mut(leave_n)
+            .zip(self.check_vw_buf.iter_mut())
+            .enumerate()
+            .map(|(i, (tree, vw))| {
+                let mut sender = SpfssSenderFp::<K>::with_seed(tree_height, tree_seeds[i]);
+                sender.set_expansion(expansion);
+                (i, sender, tree, vw)
+            })
+            .collect();
+        parallel_for_each(&mut jobs, threads, |(i, sender, tree, vw)| {
+            sender.compute(tree, delta, triple_y[*i]);
             // Malicious check
-            if self.is_malicious {
-                sender.consistency_check_msg_gen(&mut self.check_vw_buf[i], io, seeds[i]);
+            if is_malicious {
+                sender.consistency_check_msg_gen(vw, seeds[*i]);
             }
+        });
+        for (i, sender, _, _) in jobs.iter_mut() {
+            sender.send_async(io, ot, *i).await?;
         }
+        io.flush().await?;
 
         // consistency batch check
         if self.is_malicious {
-            let x_star = io.receive_stark252(1).expect("Failed to receive x_star")[0];
+            let x_star = io.receive_field_elements::<K>(1).await?[0];
             // tmp should be equal to triple_y_recv[self.tree_n] - something
-            let tmp = self.secret_share_x * x_star + self.triple_y[self.tree_n];
-            let mut vb = FE::zero();
+            let tmp = self.secret_share_x * x_star + F::compose(&self.triple_y[self.tree_n..]);
+            let mut vb = FieldElement::zero();
             vb = vb - tmp;
             for i in 0..self.tree_n {
                 vb += self.check_vw_buf[i];
             }
 
             let hash = Hash::new();
-            let digest = hash.hash_32byte_block(&vb.to_bytes_le());
-            let h = FE::from_bytes_le(&digest).unwrap();
-            io.send_stark252(&[h]).expect("Failed to send h");
+            let digest = hash.hash_32byte_block(&K::to_block(&vb));
+            let h = K::from_random_bytes(&digest);
+            io.send_field_elements(&[h]).await?;
+            io.flush().await?;
         }
+        Ok(())
+    }
 
-        println!("Done here");
-
+    pub fn mpfss_receiver<IO: CommunicationChannel>(&mut self, io: &mut IO, ot: &mut OTPre, triple_y: &[FieldElement<K>], triple_z: &[FieldElement<F>], sparse_vector_y: &mut [FieldElement<K>], sparse_vector_z: &mut [FieldElement<F>]) -> Result<()> {
+        block_on(self.mpfss_receiver_async(&mut SyncChannel(io), ot, triple_y, triple_z, sparse_vector_y, sparse_vector_z))
     }
 
-    pub fn mpfss_receiver<IO: CommunicationChannel>(&mut self, io: &mut IO, ot: &mut OTPre, triple_y: &[FE], triple_z: &[FE], sparse_vector_y: &mut [FE], sparse_vector_z: &mut [FE]) {
+    /// Async variant of `mpfss_receiver`.
+    pub async fn mpfss_receiver_async<IO: AsyncCommunicationChannel>(&mut self, io: &mut IO, ot: &mut OTPre, triple_y: &[FieldElement<K>], triple_z: &[FieldElement<F>], sparse_vector_y: &mut [FieldElement<K>], sparse_vector_z: &mut [FieldElement<F>]) -> Result<()> {
         // triple_y_recv = triple_y_send + delta * triple_z
 
-        self.triple_y.copy_from_slice(&triple_y[..self.tree_n+1]);
-        self.triple_z.copy_from_slice(&triple_z[..self.tree_n+1]);
+        self.triple_y.copy_from_slice(&triple_y[..self.tree_n + F::DEGREE]);
+        self.triple_z.copy_from_slice(&triple_z[..self.tree_n + F::DEGREE]);
 
         for i in 0..self.tree_n {
             let b = vec![false; self.tree_height - 1];
-            ot.choices_recver(io, &b);
+            ot.choices_recver_async(io, &b).await?;
         }
-        io.flush();
+        io.flush().await?;
         ot.reset();
 
-        let mut seeds = vec![FE::zero(); self.tree_n];
+        let mut seeds = vec![FieldElement::zero(); self.tree_n];
         if self.is_malicious {
-            self.seed_expand(io, &mut seeds, self.tree_n);
+            self.seed_expand_async(io, &mut seeds).await?;
         }
 
-        for i in 0..self.tree_n {
-            let mut receiver = SpfssRecverFp::new(self.tree_height);
-            self.item_pos_receiver[i] = receiver.get_index();
-            receiver.recv(io, ot, i);
-            receiver.compute(&mut self.ggm_tree[i], self.triple_y[i]);
-            sparse_vector_y[i*self.leave_n..(i+1)*self.leave_n].copy_from_slice(&self.ggm_tree[i]);
-            for j in i*self.leave_n..(i+1)*self.leave_n {
-                sparse_vector_z[j] = FE::zero();
+        // Receive every tree, then rebuild them in parallel
+        let (threads, leave_n, tree_height, is_malicious) = (self.threads, self.leave_n, self.tree_height, self.is_malicious);
+        let expansion = self.expansion;
+        let mut receivers = Vec::with_capacity(self.tree_n);
+        for (i, pos) in self.item_pos_receiver.iter_mut().enumerate() {
+            let mut receiver = SpfssRecverFp::<K>::new(tree_height);
+            receiver.set_expansion(expansion);
+            *pos = receiver.get_index();
+            receiver.recv_async(io, ot, i).await?;
+            receivers.push(receiver);
+        }
+
+        let triple_y = &self.triple_y;
+        let mut jobs: Vec<_> = receivers
+            .into_iter()
+            .zip(sparse_vector_y[..self.tree_n * leave_n].chunks_mut(leave_n))
+            .zip(self.check_chialpha_buf.iter_mut().zip(self.check_vw_buf.iter_mut()))
+            .enumerate()
+            .map(|(i, ((receiver, tree), (chialpha, vw)))| (i, receiver, tree, chialpha, vw))
+            .collect();
+        parallel_for_each(&mut jobs, threads, |(i, receiver, tree, chialpha, vw)| {
+            receiver.compute(tree, triple_y[*i]);
+            if is_malicious {
+                receiver.consistency_check_msg_gen(chialpha, vw, seeds[*i]);
             }
-            sparse_vector_z[i*self.leave_n + self.item_pos_receiver[i]] = self.triple_z[i];
+        });
 
-            if self.is_malicious {
-                receiver.consistency_check_msg_gen(&mut self.check_chialpha_buf[i], &mut self.check_vw_buf[i], io, seeds[i]);
+        for i in 0..self.tree_n {
+            let z = &mut sparse_vector_z[i*self.leave_n..(i+1)*self.leave_n];
+            for e in z.iter_mut() {
+                *e = FieldElement::zero();
             }
+            z[self.item_pos_receiver[i]] = self.triple_z[i];
         }
 
         if self.is_malicious {
-            let mut beta_mul_chialpha = FE::zero();
+            let mut beta_mul_chialpha = FieldElement::<K>::zero();
             for i in 0..self.tree_n {
-                beta_mul_chialpha += self.check_chialpha_buf[i] * self.triple_z[i];
+                beta_mul_chialpha += F::scale(&self.triple_z[i], &self.check_chialpha_buf[i]);
             }
-            let x_star = self.triple_z[self.tree_n] - beta_mul_chialpha;
-            io.send_stark252(&[x_star]).expect("Cannot send x_star.");
+            let z_mask: Vec<FieldElement<K>> = self.triple_z[self.tree_n..].iter().map(|z| F::embed(z)).collect();
+            let x_star = F::compose(&z_mask) - beta_mul_chialpha;
+            io.send_field_elements(&[x_star]).await?;
+            io.flush().await?;
 
-            let mut va = FE::zero();
-            va = va - self.triple_y[self.tree_n];
+            let mut va = FieldElement::zero();
+            va = va - F::compose(&self.triple_y[self.tree_n..]);
             for i in 0..self.tree_n {
                 va += self.check_vw_buf[i];
             }
 
             let hash = Hash::new();
-            let digest = hash.hash_32byte_block(&va.to_bytes_le());
-            let h = FE::from_bytes_le(&digest).unwrap();
+            let digest = hash.hash_32byte_block(&K::to_block(&va));
+            let h = K::from_random_bytes(&digest);
 
-            let r = io.receive_stark252(1).expect("Cound not receive h from Sender")[0];
+            let r = io.receive_field_elements::<K>(1).await?[0];
 
             if r != h {
-                panic!("Consistency check for Mpfss failed!");
-            } else {
-                println!("Consistency check for Mpfss successful!");
+                return Err(VoleError::ConsistencyCheck(CheckFailure::SpfssBatch));
             }
         }
+        Ok(())
+    }
 
+    pub fn seed_expand<IO: CommunicationChannel>(&mut self, io: &mut IO, seed: &mut [FieldElement<K>]) -> Result<()> {
+        block_on(self.seed_expand_async(&mut SyncChannel(io), seed))
     }
 
-    pub fn seed_expand<IO: CommunicationChannel>(&mut self, io: &mut IO, seed: &mut [FE], threads: usize) {
+    /// Async variant of `seed_expand`.
+    pub async fn seed_expand_async<IO: AsyncCommunicationChannel>(&mut self, io: &mut IO, seed: &mut [FieldElement<K>]) -> Result<()> {
         let mut sd = [0u8; 16];
         if self.party == 0 {
-            sd = io.receive_data()[0];
+            sd = *io.receive_data().await?.first()
+                .ok_or_else(|| VoleError::MalformedMessage("Missing seed for the batch check".to_string()))?;
         } else {
             let mut sd_buf = vec![[0u8; 16]; 1];
             self.prg.random_block(&mut sd_buf);
             sd = sd_buf[0].clone();
-            io.send_data(&[sd]);
+            io.send_data(&[sd]).await?;
+            io.flush().await?;
         }
         let mut prg2 = PRG::new(Some(&sd), 0);
-        prg2.random_stark252_elements(seed);
+        prg2.random_field_elements(seed);
+        Ok(())
     }
 }
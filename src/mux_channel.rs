@@ -0,0 +1,200 @@
+use crate::comm_channel::{CommunicationChannel, FE};
+use crate::socket_channel::TcpChannel;
+use crate::field::VoleField;
+use crate::framing::{self, Framer, MessageKind, HEADER_LEN};
+use crate::error::{Result, VoleError};
+
+use p256::EncodedPoint;
+use lambdaworks_math::field::element::FieldElement;
+use std::io::ErrorKind;
+use std::net::TcpStream;
+use std::sync::mpsc::{channel, Receiver, Sender};
+use std::sync::{Arc, Mutex};
+use std::thread;
+
+// Sub-channel id of the message a party sends once all its sub-channels are gone
+const CLOSE: u32 = u32::MAX;
+
+/// The sending direction shared by all sub-channels of one side.
+struct MuxWriter {
+    inner: Box<dyn CommunicationChannel + Send>,
+}
+
+impl Drop for MuxWriter {
+    // Lets the peer's reader thread stop, it would block on the connection forever otherwise
+    fn drop(&mut self) {
+        let mut close = [0u8; 16];
+        close[..4].copy_from_slice(&CLOSE.to_le_bytes());
+        let _ = self.inner.send_data(&[close]);
+        let _ = self.inner.flush();
+    }
+}
+
+/// One of N logical channels sharing a single connection.
+///
+/// Sub-channel `i` of one party talks to sub-channel `i` of the other, each with its own
+/// framing, so independent protocol instances can run on their own threads. Every
+/// message goes out as one `send_data` on the shared connection, led by a block with the
+/// sub-channel id. A reader thread sorts incoming messages into per-sub-channel queues,
+/// so a sub-channel that falls behind never blocks the others.
+///
+/// One thread cannot block in a receive while others send, so the connection is taken
+/// as two channels, one per direction. `split_tcp` does that for a socket.
+pub struct MuxChannel {
+    id: u32,
+    writer: Arc<Mutex<MuxWriter>>,
+    rx: Receiver<Result<Vec<u8>>>,
+    framer: Framer,
+}
+
+impl MuxChannel {
+    /// Splits the connection made of `reader` and `writer` into `n` sub-channels
+    pub fn split<R, W>(reader: R, writer: W, n: usize) -> Vec<MuxChannel>
+    where
+        R: CommunicationChannel + Send + 'static,
+        W: CommunicationChannel + Send + 'static,
+    {
+        let writer = Arc::new(Mutex::new(MuxWriter { inner: Box::new(writer) }));
+        let (senders, receivers): (Vec<_>, Vec<_>) = (0..n).map(|_| channel()).unzip();
+        thread::spawn(move || Self::route(reader, senders));
+
+        receivers
+            .into_iter()
+            .enumerate()
+            .map(|(id, rx)| MuxChannel { id: id as u32, writer: writer.clone(), rx, framer: Framer::default() })
+            .collect()
+    }
+
+    /// Splits a socket into `n` sub-channels
+    pub fn split_tcp(stream: TcpStream, n: usize) -> Result<Vec<MuxChannel>> {
+        let reader = TcpChannel::new(stream.try_clone()?);
+        Ok(Self::split(reader, TcpChannel::new(stream), n))
+    }
+
+    /// The index of this sub-channel
+    pub fn id(&self) -> usize {
+        self.id as usize
+    }
+
+    // Runs on the reader thread until the peer closes or the connection fails
+    fn route<R: CommunicationChannel>(mut reader: R, senders: Vec<Sender<Result<Vec<u8>>>>) {
+        let failure = loop {
+            let mut message = match reader.receive_data() {
+                Ok(message) => message,
+                Err(e) => break e,
+            };
+            let id = match message.first() {
+                Some(block) => u32::from_le_bytes([block[0], block[1], block[2], block[3]]),
+                None => break VoleError::MalformedMessage("Empty multiplexed message".to_string()),
+            };
+            if id == CLOSE {
+                return;
+            }
+            let sender = match senders.get(id as usize) {
+                Some(sender) => sender,
+                None => break VoleError::MalformedMessage(format!("Message for unknown sub-channel {}", id)),
+            };
+            message.remove(0);
+            // A dropped sub-channel just loses what is still addressed to it
+            let _ = sender.send(Ok(framing::encode_blocks(&message)));
+        };
+
+        // Every sub-channel learns why the connection is gone
+        let kind = match &failure {
+            VoleError::Io(e) => e.kind(),
+            _ => ErrorKind::InvalidData,
+        };
+        for sender in senders.iter() {
+            let _ = sender.send(Err(VoleError::Io(std::io::Error::new(kind, failure.to_string()))));
+        }
+    }
+
+    fn send_frame(&mut self, kind: MessageKind, payload: &[u8]) -> Result<()> {
+        let mut frame = self.framer.frame(kind, payload);
+        frame.resize((frame.len() + 15) / 16 * 16, 0);
+
+        let mut message = vec![[0u8; 16]];
+        message[0][..4].copy_from_slice(&self.id.to_le_bytes());
+        message.extend(framing::decode_blocks::<16>(&frame)?);
+        let mut writer = self.writer.lock().unwrap();
+        writer.inner.send_data(&message)
+    }
+
+    fn receive_frame(&mut self, kind: MessageKind) -> Result<Vec<u8>> {
+        let mut frame = self.rx.recv().map_err(|_| {
+            VoleError::Io(std::io::Error::new(ErrorKind::UnexpectedEof, "Multiplexed connection closed"))
+        })??;
+
+        if frame.len() < HEADER_LEN {
+            return Err(VoleError::MalformedMessage("Truncated frame header".to_string()));
+        }
+        let mut header = [0u8; HEADER_LEN];
+        header.copy_from_slice(&frame[..HEADER_LEN]);
+        let len = self.framer.check(&header, kind)?;
+        if HEADER_LEN + len > frame.len() {
+            return Err(VoleError::MalformedMessage("Frame longer than its message".to_string()));
+        }
+        frame.truncate(HEADER_LEN + len);
+        frame.drain(..HEADER_LEN);
+        Ok(frame)
+    }
+}
+
+impl CommunicationChannel for MuxChannel {
+    fn send_32byte_block(&mut self, data: &[[u8; 32]]) -> Result<()> {
+        self.send_frame(MessageKind::Block32, &framing::encode_blocks(data))
+    }
+
+    fn receive_32byte_block(&mut self) -> Result<Vec<[u8; 32]>> {
+        let payload = self.receive_frame(MessageKind::Block32)?;
+        framing::decode_blocks(&payload)
+    }
+
+    fn send_bits(&mut self, bits: &[bool]) -> Result<()> {
+        self.send_frame(MessageKind::Bits, &framing::encode_bits(bits))
+    }
+
+    fn receive_bits(&mut self) -> Result<Vec<bool>> {
+        let payload = self.receive_frame(MessageKind::Bits)?;
+        framing::decode_bits(&payload)
+    }
+
+    fn send_stark252(&mut self, elements: &[FE]) -> Result<()> {
+        self.send_field_elements(elements)
+    }
+
+    fn receive_stark252(&mut self, count: usize) -> Result<Vec<FE>> {
+        self.receive_field_elements(count)
+    }
+
+    fn send_field_elements<F: VoleField>(&mut self, elements: &[FieldElement<F>]) -> Result<()> {
+        self.send_frame(MessageKind::FieldElements, &framing::encode_field_elements(elements))
+    }
+
+    fn receive_field_elements<F: VoleField>(&mut self, count: usize) -> Result<Vec<FieldElement<F>>> {
+        let payload = self.receive_frame(MessageKind::FieldElements)?;
+        framing::decode_field_elements(&payload, count)
+    }
+
+    fn send_point(&mut self, point: &EncodedPoint) -> Result<()> {
+        self.send_frame(MessageKind::Point, point.as_bytes())
+    }
+
+    fn receive_point(&mut self) -> Result<EncodedPoint> {
+        let payload = self.receive_frame(MessageKind::Point)?;
+        framing::decode_point(&payload)
+    }
+
+    fn send_data(&mut self, data: &[[u8; 16]]) -> Result<()> {
+        self.send_frame(MessageKind::Data, &framing::encode_blocks(data))
+    }
+
+    fn receive_data(&mut self) -> Result<Vec<[u8; 16]>> {
+        let payload = self.receive_frame(MessageKind::Data)?;
+        framing::decode_blocks(&payload)
+    }
+
+    fn flush(&mut self) -> Result<()> {
+        self.writer.lock().unwrap().inner.flush()
+    }
+}
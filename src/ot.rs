@@ -1,5 +1,8 @@
 use crate::hash::Hash;
+use crate::prg::with_thread_rng;
 use crate::comm_channel::CommunicationChannel;
+use crate::async_comm_channel::{AsyncCommunicationChannel, SyncChannel, block_on};
+use crate::error::{Result, VoleError};
 use p256::elliptic_curve::sec1::{ToEncodedPoint, FromEncodedPoint};
 use p256::elliptic_curve::{Field, Group}; 
 use p256::{Scalar, AffinePoint, ProjectivePoint};
@@ -13,12 +16,16 @@ impl OTCO {
     }
 
     /// Sender's OT implementation
-    pub fn send<IO: CommunicationChannel>(&mut self, io: &mut IO, data0: &[[u8; 16]], data1: &[[u8; 16]]) {
+    pub fn send<IO: CommunicationChannel>(&mut self, io: &mut IO, data0: &[[u8; 16]], data1: &[[u8; 16]]) -> Result<()> {
+        block_on(self.send_async(&mut SyncChannel(io), data0, data1))
+    }
+
+    /// Async variant of `send`.
+    pub async fn send_async<IO: AsyncCommunicationChannel>(&mut self, io: &mut IO, data0: &[[u8; 16]], data1: &[[u8; 16]]) -> Result<()> {
         let length = data0.len();
-        let mut rng = rand::thread_rng();
 
         // Generate random scalar `a`
-        let a = Scalar::random(&mut rng);
+        let a = with_thread_rng(|rng| Scalar::random(rng));
 
         // Compute A = G * a (G is the generator of the curve)
         let A = ProjectivePoint::generator() * a;
@@ -26,7 +33,8 @@ impl OTCO {
 
         // Send A to the receiver
         let A_encoded = A_affine.to_encoded_point(false);
-        io.send_point(&A_encoded);
+        io.send_point(&A_encoded).await?;
+        io.flush().await?;
 
         // Compute (A * a)^-1
         let mut A_a_inverse = A * a;
@@ -37,9 +45,9 @@ impl OTCO {
 
         // Receive B points and compute BA points
         for i in 0..length {
-            let b_point = io.receive_point();
-            let b_affine = AffinePoint::from_encoded_point(&b_point)
-                .expect("Failed to decode AffinePoint from EncodedPoint");
+            let b_point = io.receive_point().await?;
+            let b_affine = Option::<AffinePoint>::from(AffinePoint::from_encoded_point(&b_point))
+                .ok_or_else(|| VoleError::MalformedMessage("Failed to decode AffinePoint from EncodedPoint".to_string()))?;
             let B_projective = ProjectivePoint::from(b_affine);
 
             // Compute B[i] * a
@@ -50,8 +58,6 @@ impl OTCO {
             BA_points[i] = B_a + A_a_inverse;
         }
 
-        io.flush();
-
         // Encrypt and send the data
         for i in 0..length {
             let key_b = Hash::kdf(
@@ -66,21 +72,27 @@ impl OTCO {
             let encrypted0 = xor_blocks(&data0[i], &key_b);
             let encrypted1 = xor_blocks(&data1[i], &key_ba);
 
-            io.send_data(&[encrypted0, encrypted1]);
+            io.send_data(&[encrypted0, encrypted1]).await?;
         }
+        io.flush().await?;
+        Ok(())
     }
 
     /// Receiver's OT implementation
-    pub fn recv<IO: CommunicationChannel>(&mut self, io: &mut IO, choices: &[bool], output: &mut Vec<[u8; 16]>) {
+    pub fn recv<IO: CommunicationChannel>(&mut self, io: &mut IO, choices: &[bool], output: &mut Vec<[u8; 16]>) -> Result<()> {
+        block_on(self.recv_async(&mut SyncChannel(io), choices, output))
+    }
+
+    /// Async variant of `recv`.
+    pub async fn recv_async<IO: AsyncCommunicationChannel>(&mut self, io: &mut IO, choices: &[bool], output: &mut Vec<[u8; 16]>) -> Result<()> {
         let length = choices.len();
-        let mut rng = rand::thread_rng();
 
         // Generate random scalars `b`
-        let b_scalars: Vec<Scalar> = (0..length).map(|_| Scalar::random(&mut rng)).collect();
+        let b_scalars: Vec<Scalar> = with_thread_rng(|rng| (0..length).map(|_| Scalar::random(&mut *rng)).collect());
 
-        let A_encoded = io.receive_point();
-        let A_affine = AffinePoint::from_encoded_point(&A_encoded)
-            .expect("Invalid A point received");
+        let A_encoded = io.receive_point().await?;
+        let A_affine = Option::<AffinePoint>::from(AffinePoint::from_encoded_point(&A_encoded))
+            .ok_or_else(|| VoleError::MalformedMessage("Invalid A point received".to_string()))?;
         let A_projective = ProjectivePoint::from(A_affine);
 
         // Compute and send B points
@@ -93,10 +105,10 @@ impl OTCO {
             }
 
             let B_encoded = B_projective.to_affine().to_encoded_point(false);
-            io.send_point(&B_encoded);
+            io.send_point(&B_encoded).await?;
         }
 
-        io.flush();
+        io.flush().await?;
 
         // Compute shared points and decrypt data
         for i in 0..length {
@@ -106,13 +118,17 @@ impl OTCO {
                 i as u64,
             );
 
-            let encrypted = io.receive_data();
+            let encrypted = io.receive_data().await?;
+            if encrypted.len() != 2 {
+                return Err(VoleError::MalformedMessage("Expected two encrypted blocks".to_string()));
+            }
             output.push(if choices[i] {
                 xor_blocks(&encrypted[1], &key_as)
             } else {
                 xor_blocks(&encrypted[0], &key_as)
             });
         }
+        Ok(())
     }
 }
 
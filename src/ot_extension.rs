@@ -0,0 +1,48 @@
+use crate::async_comm_channel::AsyncCommunicationChannel;
+use crate::error::Result;
+
+/// The side of a COT extension that holds Δ and gets q_i, the receiver getting q_i ⊕ r_i·Δ.
+///
+/// Implemented by the OT extensions, IKNP and SoftSpokenOT, and open to anything else that
+/// produces such COTs, e.g. a silent COT generator.
+///
+/// Like those of `AsyncCommunicationChannel`, the futures of these `async fn`s are not
+/// required to be `Send`.
+pub trait CotSender<const N: usize> {
+    /// Everything before the first extension, e.g. base OTs, with correlation `delta`
+    async fn setup_sender<IO: AsyncCommunicationChannel>(&mut self, io: &mut IO, delta: &[u8; N]) -> Result<()>;
+
+    /// The sender's `length` COTs, into `data`
+    async fn extend_send<IO: AsyncCommunicationChannel>(&mut self, io: &mut IO, data: &mut [[u8; N]], length: usize) -> Result<()>;
+
+    /// Checks that the receiver was consistent in the last extension, nothing by default.
+    ///
+    /// A failure is a `ConsistencyCheck` error.
+    async fn check_send<IO: AsyncCommunicationChannel>(&mut self, io: &mut IO, data: &[[u8; N]], length: usize) -> Result<()> {
+        Ok(())
+    }
+}
+
+/// The side of a COT extension that picks the choice bits r_i, see `CotSender`, futures
+/// not `Send` either.
+pub trait CotReceiver<const N: usize> {
+    /// Everything before the first extension, e.g. base OTs
+    async fn setup_receiver<IO: AsyncCommunicationChannel>(&mut self, io: &mut IO) -> Result<()>;
+
+    /// The receiver's `length` COTs for the choice bits `r`, into `data`
+    async fn extend_recv<IO: AsyncCommunicationChannel>(&mut self, io: &mut IO, data: &mut [[u8; N]], r: &[bool], length: usize) -> Result<()>;
+
+    /// Answers `CotSender::check_send` for the last extension, nothing by default
+    async fn check_recv<IO: AsyncCommunicationChannel>(&mut self, io: &mut IO, data: &[[u8; N]], r: &[bool], length: usize) -> Result<()> {
+        Ok(())
+    }
+}
+
+/// Both sides of a COT extension, what `BaseCot` and the `VoleTriple` bootstrap run on.
+pub trait OtExtension<const N: usize>: CotSender<N> + CotReceiver<N> {
+    /// The extension and its parameters, both parties must run the same
+    fn name(&self) -> String;
+
+    /// Whether the checks run, the receiver's choice bits must then be its own random ones
+    fn malicious(&self) -> bool;
+}
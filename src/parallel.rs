@@ -0,0 +1,32 @@
+use std::sync::Mutex;
+
+// Items a worker takes from the shared queue at a time
+const ITEMS_PER_TAKE: usize = 4;
+
+/// One worker thread per core, the default wherever a thread count can be set.
+pub fn default_threads() -> usize {
+    std::thread::available_parallelism().map(|n| n.get()).unwrap_or(1)
+}
+
+/// Calls `f` on every item. The `threads` scoped threads are spawned once per call and take
+/// the items a few at a time from a shared queue until it runs dry.
+pub fn parallel_for_each<T: Send>(items: &mut [T], threads: usize, f: impl Fn(&mut T) + Sync) {
+    if threads <= 1 || items.len() <= 1 {
+        items.iter_mut().for_each(f);
+        return;
+    }
+    let workers = threads.min(items.len());
+    let queue = Mutex::new(items.chunks_mut(ITEMS_PER_TAKE));
+    let (f, queue) = (&f, &queue);
+    std::thread::scope(|s| {
+        for _ in 0..workers {
+            s.spawn(move || loop {
+                let part = queue.lock().unwrap().next();
+                match part {
+                    Some(part) => part.iter_mut().for_each(f),
+                    None => break,
+                }
+            });
+        }
+    });
+}
@@ -1,21 +1,26 @@
 use crate::hash::CCRH;
 use crate::comm_channel::CommunicationChannel;
+use crate::async_comm_channel::{AsyncCommunicationChannel, SyncChannel, block_on};
+use crate::iknp::{send_blocks, receive_blocks};
+use crate::error::{Result, VoleError};
 
-pub struct OTPre {
-    pre_data: Vec<[u8; 32]>,
+/// Precomputed random OTs of `N`-byte messages, derandomized with the choice bits
+/// of a batch. 32-byte ones for the field SPFSS, 16-byte ones for the GF(2^128) SPCOT.
+pub struct OTPre<const N: usize = 32> {
+    pre_data: Vec<[u8; N]>,
     bits: Vec<bool>,
     n: usize,
     count: usize,
     length: usize,
-    delta: Option<[u8; 32]>,
+    delta: Option<[u8; N]>,
 }
 
-impl OTPre {
+impl<const N: usize> OTPre<N> {
     /// Create a new `OTPre` instance
     pub fn new(length: usize, times: usize) -> Self {
         let n = length * times;
         Self {
-            pre_data: vec![[0u8; 32]; 2 * n],
+            pre_data: vec![[0u8; N]; 2 * n],
             bits: vec![false; n],
             n,
             count: 0,
@@ -25,28 +30,42 @@ impl OTPre {
     }
 
     /// Receives choice bits from the receiver and updates internal state
-    pub fn choices_sender<IO: CommunicationChannel>(&mut self, io: &mut IO) {
-        let received_bits = io.receive_bits().expect("Failed to receive bits");
+    pub fn choices_sender<IO: CommunicationChannel>(&mut self, io: &mut IO) -> Result<()> {
+        block_on(self.choices_sender_async(&mut SyncChannel(io)))
+    }
+
+    /// Async variant of `choices_sender`.
+    pub async fn choices_sender_async<IO: AsyncCommunicationChannel>(&mut self, io: &mut IO) -> Result<()> {
+        let received_bits = io.receive_bits().await?;
+        if received_bits.len() > self.n - self.count {
+            return Err(VoleError::MalformedMessage("Too many choice bits received".to_string()));
+        }
         for (i, &bit) in received_bits.iter().enumerate() {
             self.bits[self.count + i] = bit;
         }
         self.count += self.length;
+        Ok(())
     }
 
     /// Sends the adjusted choice bits to the sender
-    pub fn choices_recver<IO: CommunicationChannel>(&mut self, io: &mut IO, choices: &[bool]) {
+    pub fn choices_recver<IO: CommunicationChannel>(&mut self, io: &mut IO, choices: &[bool]) -> Result<()> {
+        block_on(self.choices_recver_async(&mut SyncChannel(io), choices))
+    }
+
+    /// Async variant of `choices_recver`.
+    pub async fn choices_recver_async<IO: AsyncCommunicationChannel>(&mut self, io: &mut IO, choices: &[bool]) -> Result<()> {
         let mut adjusted_bits = vec![false; self.length];
         for i in 0..self.length {
             adjusted_bits[i] = choices[i] ^ self.bits[self.count + i];
             self.bits[self.count+i] = adjusted_bits[i].clone();
         }
-        io.send_bits(&adjusted_bits).expect("Failed to send bits");
+        io.send_bits(&adjusted_bits).await?;
         self.count += self.length;
+        Ok(())
     }
 
     /// Precompute data for the sender
-    pub fn send_pre(&mut self, data: &[[u8; 32]], delta: [u8; 32]) {
-        println!("Send pre");
+    pub fn send_pre(&mut self, data: &[[u8; N]], delta: [u8; N]) {
         let ccrh = CCRH::new();
         self.delta = Some(delta);
 
@@ -62,7 +81,7 @@ impl OTPre {
     }
 
     /// Precompute data for the receiver
-    pub fn recv_pre(&mut self, data: &[[u8; 32]], bits: Option<&[bool]>) {
+    pub fn recv_pre(&mut self, data: &[[u8; N]], bits: Option<&[bool]>) {
         let ccrh = CCRH::new();
         if let Some(b) = bits {
             self.bits[..self.n].copy_from_slice(b);
@@ -78,12 +97,24 @@ impl OTPre {
     pub fn send<IO: CommunicationChannel>(
         &mut self,
         io: &mut IO,
-        m0: &[[u8; 32]],
-        m1: &[[u8; 32]],
+        m0: &[[u8; N]],
+        m1: &[[u8; N]],
+        length: usize,
+        s: usize,
+    ) -> Result<()> {
+        block_on(self.send_async(&mut SyncChannel(io), m0, m1, length, s))
+    }
+
+    /// Async variant of `send`.
+    pub async fn send_async<IO: AsyncCommunicationChannel>(
+        &mut self,
+        io: &mut IO,
+        m0: &[[u8; N]],
+        m1: &[[u8; N]],
         length: usize,
         s: usize,
-    ) {
-        let mut pad = vec![[0u8; 32]; 2*length];
+    ) -> Result<()> {
+        let mut pad = vec![[0u8; N]; 2*length];
         let k = s * length;
 
         for i in 0..length {
@@ -96,25 +127,41 @@ impl OTPre {
                 pad[2*i+1] = xor_block(&m1[i], &self.pre_data[idx]);
             }
         }
-        io.send_32byte_block(&pad);
+        send_blocks(io, &pad).await
     }
 
     /// Receive and reconstruct data based on precomputed values
     pub fn recv<IO: CommunicationChannel>(
         &mut self,
         io: &mut IO,
-        data: &mut [[u8; 32]],
+        data: &mut [[u8; N]],
+        b: &[bool],
+        length: usize,
+        s: usize,
+    ) -> Result<()> {
+        block_on(self.recv_async(&mut SyncChannel(io), data, b, length, s))
+    }
+
+    /// Async variant of `recv`.
+    pub async fn recv_async<IO: AsyncCommunicationChannel>(
+        &mut self,
+        io: &mut IO,
+        data: &mut [[u8; N]],
         b: &[bool],
         length: usize,
         s: usize,
-    ) {
-        let pad = io.receive_32byte_block();
+    ) -> Result<()> {
+        let pad = receive_blocks::<_, N>(io).await?;
+        if pad.len() != 2 * length {
+            return Err(VoleError::MalformedMessage(format!("Expected {} OT pads, got {}", 2 * length, pad.len())));
+        }
         let k = s * length;
 
         for i in 0..length {
             let idx = if b[i] { 1 } else { 0 };
             data[i] = xor_block(&self.pre_data[k + i], &pad[2*i + idx]);
         }
+        Ok(())
     }
 
     /// Reset the internal counter
@@ -123,15 +170,6 @@ impl OTPre {
     }
 }
 
-fn xor_block(a: &[u8; 32], b: &[u8; 32]) -> [u8; 32] {
-    [
-        a[0] ^ b[0], a[1] ^ b[1], a[2] ^ b[2], a[3] ^ b[3],
-        a[4] ^ b[4], a[5] ^ b[5], a[6] ^ b[6], a[7] ^ b[7],
-        a[8] ^ b[8], a[9] ^ b[9], a[10] ^ b[10], a[11] ^ b[11],
-        a[12] ^ b[12], a[13] ^ b[13], a[14] ^ b[14], a[15] ^ b[15],
-        a[16] ^ b[16], a[17] ^ b[17], a[18] ^ b[18], a[19] ^ b[19],
-        a[20] ^ b[20], a[21] ^ b[21], a[22] ^ b[22], a[23] ^ b[23],
-        a[24] ^ b[24], a[25] ^ b[25], a[26] ^ b[26], a[27] ^ b[27],
-        a[28] ^ b[28], a[29] ^ b[29], a[30] ^ b[30], a[31] ^ b[31],
-    ]
-}
\ No newline at end of file
+fn xor_block<const N: usize>(a: &[u8; N], b: &[u8; N]) -> [u8; N] {
+    std::array::from_fn(|i| a[i] ^ b[i])
+}
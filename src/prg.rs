@@ -2,13 +2,39 @@ use aes::Aes128;
 use aes::cipher::{BlockEncrypt, KeyInit, generic_array::GenericArray};
 use lambdaworks_math::field::fields::fft_friendly::stark_252_prime_field::Stark252PrimeField;
 use lambdaworks_math::field::element::FieldElement;
-use lambdaworks_math::traits::ByteConversion;
-use rand::Rng;
+use crate::field::VoleField;
+use rand::{Rng, RngCore, SeedableRng};
+use rand_chacha::ChaCha20Rng;
+use std::cell::RefCell;
 use std::time::Instant;
 
-/// Alias for the STARK-252 prime field and its field element.
-pub type F = Stark252PrimeField;
-pub type FE = FieldElement<F>;
+thread_local! {
+    // Replaces the OS randomness of the thread once `set_thread_seed` is called
+    static THREAD_SEED: RefCell<Option<ChaCha20Rng>> = RefCell::new(None);
+}
+
+/// Makes all fresh randomness of the calling thread come from `seed`, `None` goes back to the OS.
+///
+/// This is what lets a `ReplayChannel` reproduce a recorded run. It only exists with the
+/// `replay` feature, which must never be enabled in production.
+#[cfg(feature = "replay")]
+pub fn set_thread_seed(seed: Option<[u8; 32]>) {
+    THREAD_SEED.with(|seeded| *seeded.borrow_mut() = seed.map(ChaCha20Rng::from_seed));
+}
+
+/// Runs `f` with the randomness source of the calling thread.
+pub(crate) fn with_thread_rng<T>(f: impl FnOnce(&mut dyn RngCore) -> T) -> T {
+    THREAD_SEED.with(|seeded| match seeded.borrow_mut().as_mut() {
+        Some(rng) => f(rng),
+        None => f(&mut rand::thread_rng()),
+    })
+}
+
+// 32-byte blocks generated per batch of AES calls
+const PRG_CHUNK: usize = 32;
+
+/// Alias for the STARK-252 field element.
+pub type FE = FieldElement<Stark252PrimeField>;
 
 pub struct PRG {
     counter: u64,
@@ -37,9 +63,8 @@ impl PRG {
 
     /// Generate a random 16-byte key using a secure random generator.
     fn generate_random_key() -> [u8; 16] {
-        let mut rng = rand::thread_rng();
         let mut key = [0u8; 16];
-        rng.fill(&mut key);
+        with_thread_rng(|rng| rng.fill(&mut key));
         key
     }
 
@@ -78,36 +103,46 @@ impl PRG {
         }
     }
 
+    /// Fill `blocks` with two AES counter blocks each, whatever they held before.
     pub fn random_32byte_block(&mut self, blocks: &mut [[u8; 32]]) {
-        // Preallocate space for AES blocks (2 AES blocks per 32-byte block)
-        let mut aes_blocks: Vec<_> =
-            vec![GenericArray::default(); blocks.len() * 2];
-
-        for (i, block) in blocks.iter_mut().enumerate() {
-            // Embed the counter in the last 8 bytes of the 32-byte block
-            block[8..16].copy_from_slice(&self.counter.to_le_bytes());
-            self.counter += 1;
-            block[24..32].copy_from_slice(&self.counter.to_le_bytes());
-            self.counter += 1;
-
-            // Write the two halves of the 32-byte block into the AES blocks vector
-            aes_blocks[i * 2].copy_from_slice(&block[0..16]);
-            aes_blocks[i * 2 + 1].copy_from_slice(&block[16..32]);
-
-        }
-
-        // Encrypt all blocks in one batch
-        self.aes.encrypt_blocks(&mut aes_blocks);
+        self.random_blocks(blocks);
+    }
 
-        // Copy the encrypted halves back into their respective 32-byte blocks
-        for (i, block) in blocks.iter_mut().enumerate() {
-            block[0..16].copy_from_slice(&aes_blocks[i * 2]);
-            block[16..32].copy_from_slice(&aes_blocks[i * 2 + 1]);
+    /// Fill `blocks` with `N / 16` AES counter blocks each, whatever they held before.
+    ///
+    /// Gives the same stream as `random_block` for `N = 16` and `random_32byte_block` for `N = 32`.
+    pub fn random_blocks<const N: usize>(&mut self, blocks: &mut [[u8; N]]) {
+        assert!(N % 16 == 0 && N <= 32, "Blocks are 16 or 32 bytes, not {}", N);
+        let per_block = N / 16;
+        // A chunk at a time on the stack, the IKNP rows call this for every block
+        let mut aes_blocks = [GenericArray::default(); 2 * PRG_CHUNK];
+        for chunk in blocks.chunks_mut(PRG_CHUNK) {
+            let aes_blocks = &mut aes_blocks[..per_block * chunk.len()];
+            for aes_block in aes_blocks.iter_mut() {
+                let mut block = [0u8; 16];
+                block[8..].copy_from_slice(&self.counter.to_le_bytes());
+                self.counter += 1;
+                *aes_block = GenericArray::from(block);
+            }
+
+            // Encrypt the chunk in one batch
+            self.aes.encrypt_blocks(aes_blocks);
+
+            for (block, parts) in chunk.iter_mut().zip(aes_blocks.chunks_exact(per_block)) {
+                for (bytes, part) in block.chunks_exact_mut(16).zip(parts.iter()) {
+                    bytes.copy_from_slice(part);
+                }
+            }
         }
     }
 
     /// Generate `elements.len()` random STARK-252 field elements in-place.
     pub fn random_stark252_elements(&mut self, elements: &mut [FE]) {
+        self.random_field_elements(elements);
+    }
+
+    /// Generate `elements.len()` random field elements in-place.
+    pub fn random_field_elements<F: VoleField>(&mut self, elements: &mut [FieldElement<F>]) {
         for element in elements.iter_mut() {
             let mut block1 = [0u8; 16];
             let mut block2 = [0u8; 16];
@@ -131,8 +166,8 @@ impl PRG {
             value_bytes[..16].copy_from_slice(&block1);
             value_bytes[16..].copy_from_slice(&block2);
 
-            // Convert to STARK-252 field element
-            *element = FE::from_bytes_le(&value_bytes).unwrap();
+            // Map the random bytes into the field
+            *element = F::from_random_bytes(&value_bytes);
         }
     }
 
@@ -2,12 +2,9 @@ use aes::{Aes128, Aes256};
 use aes::cipher::{BlockEncrypt, KeyInit, generic_array::GenericArray};
 use aes::cipher::consts::U16;
 use rand::Rng;
-use lambdaworks_math::field::fields::fft_friendly::stark_252_prime_field::Stark252PrimeField;
 use lambdaworks_math::field::element::FieldElement;
-use lambdaworks_math::traits::ByteConversion;
-
-pub type F = Stark252PrimeField;
-pub type FE = FieldElement<F>;
+use crate::field::VoleField;
+use crate::prg::with_thread_rng;
 
 pub struct PRP {
     key: [u8; 16],
@@ -20,10 +17,8 @@ impl PRP {
         if let Some(k) = key {
             aes_key.copy_from_slice(k);
         } else {
-            let mut rng = rand::thread_rng();
-            rng.fill(&mut aes_key);
+            with_thread_rng(|rng| rng.fill(&mut aes_key));
         }
-        println!("AES key: {:?}", aes_key);
 
         let aes = Aes128::new(GenericArray::from_slice(&aes_key));
 
@@ -55,8 +50,7 @@ impl FieldPRP {
         if let Some(s) = seeds {
             prp_key.copy_from_slice(s);
         } else {
-            let mut rng = rand::thread_rng();
-            rng.fill(&mut prp_key);
+            with_thread_rng(|rng| rng.fill(&mut prp_key));
         }
 
         FieldPRP {
@@ -64,7 +58,7 @@ impl FieldPRP {
         }
     }
 
-    pub fn permute_block(&self, data: &mut [FE], nblocks: usize) {
+    pub fn permute_block<F: VoleField>(&self, data: &mut [FieldElement<F>], nblocks: usize) {
         // let mut aes_block: Vec<_> = (0..data.len() * 2)
         //     .map(|x| {
         //         let mut block = [0u8; 16];
@@ -82,18 +76,18 @@ impl FieldPRP {
 
         for (i, element) in data.iter_mut().enumerate() {
             // Create AES key from the `element` field element.
-            let aes_key = Aes256::new(GenericArray::from_slice(&element.to_bytes_le()));
+            let aes_key = Aes256::new(GenericArray::from_slice(&F::to_block(element)));
             let mut tmp = [aes_block[0].clone(), aes_block[1].clone()];
             // Encrypt two blocks at a time.
             aes_key.encrypt_blocks(&mut tmp);
 
             // Update the `data` with the new field element created from the encrypted bytes.
-            *element = FE::from_bytes_le(&[
+            *element = F::from_random_bytes(&[
                 tmp[0][0], tmp[0][1], tmp[0][2], tmp[0][3], tmp[0][4], tmp[0][5], tmp[0][6], tmp[0][7],
                 tmp[0][8], tmp[0][9], tmp[0][10], tmp[0][11], tmp[0][12], tmp[0][13], tmp[0][14], tmp[0][15],
                 tmp[1][0], tmp[1][1], tmp[1][2], tmp[1][3], tmp[1][4], tmp[1][5], tmp[1][6], tmp[1][7],
                 tmp[1][8], tmp[1][9], tmp[1][10], tmp[1][11], tmp[1][12], tmp[1][13], tmp[1][14], tmp[1][15]
-                ]).expect("Failed to convert random FE.");
+                ]);
         }
     }
 }
@@ -112,10 +106,11 @@ impl LubyRackoffPRP {
         if let Some(s) = seeds {
             keys.copy_from_slice(s);
         } else {
-            let mut rng = rand::thread_rng();
-            for key in keys.iter_mut() {
-                rng.fill(key);
-            }
+            with_thread_rng(|rng| {
+                for key in keys.iter_mut() {
+                    rng.fill(key);
+                }
+            });
         }
 
         LubyRackoffPRP {
@@ -123,10 +118,10 @@ impl LubyRackoffPRP {
         }
     }
 
-    pub fn permute_block(&self, data: &mut [FE], nblocks: usize) {
+    pub fn permute_block<F: VoleField>(&self, data: &mut [FieldElement<F>], nblocks: usize) {
         let data_bytes: Vec<[u8; 32]> = data
             .iter()
-            .map(|x| x.to_bytes_le())
+            .map(|x| F::to_block(x))
             .collect();
         let mut data_left: Vec<_> = data_bytes
             .iter()
@@ -167,7 +162,7 @@ impl LubyRackoffPRP {
             let mut block = [0u8; 32];
             block[0..16].copy_from_slice(&data_left[i]);
             block[16..32].copy_from_slice(&data_right[i]);
-            *element = FE::from_bytes_le(&block).expect("cannot transform prp-ed block into FE");
+            *element = F::from_random_bytes(&block);
         }
     }
 }
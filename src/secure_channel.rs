@@ -0,0 +1,220 @@
+use crate::comm_channel::{CommunicationChannel, FE};
+use crate::field::VoleField;
+use crate::framing::{self, Framer, MessageKind, HEADER_LEN};
+use crate::error::{Result, VoleError};
+
+use aes_gcm::aead::{Aead, KeyInit};
+use aes_gcm::{Aes256Gcm, Nonce};
+use hkdf::Hkdf;
+use p256::ecdh::{diffie_hellman, EphemeralSecret};
+use p256::elliptic_curve::sec1::FromEncodedPoint;
+use p256::{EncodedPoint, PublicKey, SecretKey};
+use lambdaworks_math::field::element::FieldElement;
+use rand::rngs::OsRng;
+use sha2::Sha256;
+
+const KDF_SALT: &[u8] = b"vole-rust secure channel v1";
+const CONFIRMATION: [u8; 16] = *b"key confirmation";
+
+/// One direction of the channel: its key and the number of messages so far.
+struct Direction {
+    cipher: Aes256Gcm,
+    counter: u64,
+}
+
+impl Direction {
+    fn new(key: &[u8]) -> Self {
+        Self { cipher: Aes256Gcm::new_from_slice(key).expect("AES-256 key is 32 bytes"), counter: 0 }
+    }
+
+    // The counter is the nonce, each key only ever encrypts in one direction
+    fn next_nonce(&mut self) -> [u8; 12] {
+        let mut nonce = [0u8; 12];
+        nonce[..8].copy_from_slice(&self.counter.to_le_bytes());
+        self.counter += 1;
+        nonce
+    }
+}
+
+/// Encrypts and authenticates everything sent over `inner` with AES-256-GCM.
+///
+/// The keys come from an ECDH exchange over P-256 done by `handshake`. On its own it
+/// only keeps out passive eavesdroppers; `handshake_with_keys` also mixes in the
+/// parties' long-term keys, so that an active attacker who does not hold them is
+/// caught during the handshake.
+///
+/// Each message is framed as in `framing`, padded to whole blocks, encrypted and sent
+/// as one `send_data` on `inner`. Nonces are message counters, so a dropped, replayed
+/// or reordered message fails authentication. The inner channel still sees the message
+/// count and rough sizes, since each ciphertext is one message.
+pub struct SecureChannel<C: CommunicationChannel> {
+    inner: C,
+    framer: Framer,
+    sending: Direction,
+    receiving: Direction,
+}
+
+impl<C: CommunicationChannel> SecureChannel<C> {
+    /// Runs an ephemeral ECDH exchange with the peer over `inner`
+    pub fn handshake(inner: C) -> Result<Self> {
+        Self::establish(inner, None)
+    }
+
+    /// Like `handshake`, and only succeeds if the peer holds the secret key of `peer`
+    /// and expects `own` as ours
+    pub fn handshake_with_keys(inner: C, own: &SecretKey, peer: &PublicKey) -> Result<Self> {
+        let static_secret = diffie_hellman(own.to_nonzero_scalar(), peer.as_affine());
+        Self::establish(inner, Some(static_secret.raw_secret_bytes().to_vec()))
+    }
+
+    pub fn inner(&self) -> &C {
+        &self.inner
+    }
+
+    fn establish(mut inner: C, static_secret: Option<Vec<u8>>) -> Result<Self> {
+        let ephemeral = EphemeralSecret::random(&mut OsRng);
+        let own_point = EncodedPoint::from(ephemeral.public_key());
+        inner.send_point(&own_point)?;
+        inner.flush()?;
+        let peer_point = inner.receive_point()?;
+        let peer_key: Option<PublicKey> = PublicKey::from_encoded_point(&peer_point).into();
+        let peer_key = peer_key
+            .ok_or_else(|| VoleError::MalformedMessage("Invalid ECDH public key received".to_string()))?;
+
+        // Whoever has the smaller public key sends with the first key, so both agree without party ids
+        let own_first = match own_point.as_bytes().cmp(peer_point.as_bytes()) {
+            std::cmp::Ordering::Less => true,
+            std::cmp::Ordering::Greater => false,
+            std::cmp::Ordering::Equal => {
+                return Err(VoleError::Handshake("the peer echoed our ECDH public key".to_string()))
+            }
+        };
+        let (first, second) = if own_first { (&own_point, &peer_point) } else { (&peer_point, &own_point) };
+
+        let mut ikm = ephemeral.diffie_hellman(&peer_key).raw_secret_bytes().to_vec();
+        if let Some(secret) = static_secret {
+            ikm.extend_from_slice(&secret);
+        }
+        let mut info = first.as_bytes().to_vec();
+        info.extend_from_slice(second.as_bytes());
+        let mut keys = [0u8; 64];
+        Hkdf::<Sha256>::new(Some(KDF_SALT), &ikm)
+            .expand(&info, &mut keys)
+            .expect("64 bytes is a valid HKDF output length");
+
+        let (send_key, receive_key) = if own_first { keys.split_at(32) } else { (&keys[32..], &keys[..32]) };
+        let mut channel = Self {
+            inner,
+            framer: Framer::default(),
+            sending: Direction::new(send_key),
+            receiving: Direction::new(receive_key),
+        };
+
+        // Both sides must have derived the same keys before any protocol message goes out
+        channel.send_frame(MessageKind::Data, &CONFIRMATION)?;
+        channel.flush()?;
+        match channel.receive_frame(MessageKind::Data) {
+            Ok(payload) if payload == CONFIRMATION => Ok(channel),
+            Ok(_) | Err(VoleError::MalformedMessage(_)) => Err(VoleError::Handshake(
+                "key confirmation failed, the peer derived other keys".to_string()
+            )),
+            Err(e) => Err(e),
+        }
+    }
+
+    fn send_frame(&mut self, kind: MessageKind, payload: &[u8]) -> Result<()> {
+        let mut plaintext = self.framer.frame(kind, payload);
+        plaintext.resize((plaintext.len() + 15) / 16 * 16, 0);
+
+        let nonce = self.sending.next_nonce();
+        let ciphertext = self.sending.cipher
+            .encrypt(Nonce::from_slice(&nonce), plaintext.as_slice())
+            .map_err(|_| VoleError::Parameter("Message too long to encrypt".to_string()))?;
+        // Whole blocks plus the 16-byte tag
+        self.inner.send_data(&framing::decode_blocks::<16>(&ciphertext)?)
+    }
+
+    fn receive_frame(&mut self, kind: MessageKind) -> Result<Vec<u8>> {
+        let ciphertext = framing::encode_blocks(&self.inner.receive_data()?);
+        let nonce = self.receiving.next_nonce();
+        let mut plaintext = self.receiving.cipher
+            .decrypt(Nonce::from_slice(&nonce), ciphertext.as_slice())
+            .map_err(|_| VoleError::MalformedMessage("Message failed authentication".to_string()))?;
+
+        if plaintext.len() < HEADER_LEN {
+            return Err(VoleError::MalformedMessage("Truncated frame header".to_string()));
+        }
+        let mut header = [0u8; HEADER_LEN];
+        header.copy_from_slice(&plaintext[..HEADER_LEN]);
+        let len = self.framer.check(&header, kind)?;
+        if HEADER_LEN + len > plaintext.len() {
+            return Err(VoleError::MalformedMessage("Frame longer than its ciphertext".to_string()));
+        }
+        plaintext.truncate(HEADER_LEN + len);
+        plaintext.drain(..HEADER_LEN);
+        Ok(plaintext)
+    }
+}
+
+impl<C: CommunicationChannel> CommunicationChannel for SecureChannel<C> {
+    fn send_32byte_block(&mut self, data: &[[u8; 32]]) -> Result<()> {
+        self.send_frame(MessageKind::Block32, &framing::encode_blocks(data))
+    }
+
+    fn receive_32byte_block(&mut self) -> Result<Vec<[u8; 32]>> {
+        let payload = self.receive_frame(MessageKind::Block32)?;
+        framing::decode_blocks(&payload)
+    }
+
+    fn send_bits(&mut self, bits: &[bool]) -> Result<()> {
+        self.send_frame(MessageKind::Bits, &framing::encode_bits(bits))
+    }
+
+    fn receive_bits(&mut self) -> Result<Vec<bool>> {
+        let payload = self.receive_frame(MessageKind::Bits)?;
+        framing::decode_bits(&payload)
+    }
+
+    fn send_stark252(&mut self, elements: &[FE]) -> Result<()> {
+        self.send_field_elements(elements)
+    }
+
+    fn receive_stark252(&mut self, count: usize) -> Result<Vec<FE>> {
+        self.receive_field_elements(count)
+    }
+
+    fn send_field_elements<F: VoleField>(&mut self, elements: &[FieldElement<F>]) -> Result<()> {
+        self.send_frame(MessageKind::FieldElements, &framing::encode_field_elements(elements))
+    }
+
+    fn receive_field_elements<F: VoleField>(&mut self, count: usize) -> Result<Vec<FieldElement<F>>> {
+        let payload = self.receive_frame(MessageKind::FieldElements)?;
+        framing::decode_field_elements(&payload, count)
+    }
+
+    fn send_point(&mut self, point: &EncodedPoint) -> Result<()> {
+        self.send_frame(MessageKind::Point, point.as_bytes())
+    }
+
+    fn receive_point(&mut self) -> Result<EncodedPoint> {
+        let payload = self.receive_frame(MessageKind::Point)?;
+        framing::decode_point(&payload)
+    }
+
+    fn send_data(&mut self, data: &[[u8; 16]]) -> Result<()> {
+        self.send_frame(MessageKind::Data, &framing::encode_blocks(data))
+    }
+
+    fn receive_data(&mut self) -> Result<Vec<[u8; 16]>> {
+        let payload = self.receive_frame(MessageKind::Data)?;
+        framing::decode_blocks(&payload)
+    }
+
+    fn flush(&mut self) -> Result<()> {
+        self.inner.flush()
+    }
+
+    fn stats(&self) -> Option<crate::counting_channel::CommStats> {
+        self.inner.stats()
+    }
+}
@@ -0,0 +1,170 @@
+use crate::comm_channel::{CommunicationChannel, FE};
+use crate::field::VoleField;
+use crate::error::{Result, VoleError};
+
+use p256::EncodedPoint;
+use lambdaworks_math::field::element::FieldElement;
+use std::thread;
+use std::time::{Duration, SystemTime, UNIX_EPOCH};
+
+/// One direction of an emulated link.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct LinkConfig {
+    /// One-way delay of every message
+    pub latency: Duration,
+    /// Bytes per second, `None` for unlimited
+    pub bandwidth: Option<u64>,
+}
+
+impl LinkConfig {
+    pub fn new(latency: Duration, bandwidth: Option<u64>) -> Self {
+        Self { latency, bandwidth }
+    }
+
+    /// No delay, no limit
+    pub fn unlimited() -> Self {
+        Self::new(Duration::ZERO, None)
+    }
+
+    /// 1 Gbps, 0.1 ms one-way
+    pub fn lan() -> Self {
+        Self::new(Duration::from_micros(100), Some(1_000_000_000 / 8))
+    }
+
+    /// 100 Mbps, 40 ms one-way
+    pub fn wan() -> Self {
+        Self::new(Duration::from_millis(40), Some(100_000_000 / 8))
+    }
+
+    fn transmission_time(&self, bytes: usize) -> Duration {
+        match self.bandwidth {
+            Some(bandwidth) => Duration::from_secs_f64(bytes as f64 / bandwidth as f64),
+            None => Duration::ZERO,
+        }
+    }
+}
+
+/// Emulates a link with latency and limited bandwidth on top of another channel.
+///
+/// Both ends must be wrapped, each with the config of its outgoing direction.
+/// Every message is preceded by a block carrying the time it would arrive over the
+/// emulated link, the receiver sleeps until then. The sender never waits, a message
+/// only starts on the link once the previous ones have gone through. The arrival
+/// times are wall-clock, so both ends must run on the same machine.
+///
+/// The headers are traffic of their own, put a `CountingChannel` outside, not inside.
+pub struct SimChannel<C: CommunicationChannel> {
+    inner: C,
+    link: LinkConfig,
+    link_free_at: Duration,
+}
+
+impl<C: CommunicationChannel> SimChannel<C> {
+    pub fn new(inner: C, link: LinkConfig) -> Self {
+        Self { inner, link, link_free_at: Duration::ZERO }
+    }
+
+    pub fn inner(&self) -> &C {
+        &self.inner
+    }
+
+    pub fn into_inner(self) -> C {
+        self.inner
+    }
+
+    fn now() -> Duration {
+        SystemTime::now().duration_since(UNIX_EPOCH).unwrap_or_default()
+    }
+
+    // Queues `bytes` on the link and tells the peer when they arrive
+    fn send_arrival(&mut self, bytes: usize) -> Result<()> {
+        let start = self.link_free_at.max(Self::now());
+        self.link_free_at = start + self.link.transmission_time(bytes);
+        let arrival = self.link_free_at + self.link.latency;
+        self.inner.send_data(&[arrival.as_nanos().to_le_bytes()])
+    }
+
+    fn receive_arrival(&mut self) -> Result<Duration> {
+        let header = self.inner.receive_data()?;
+        let block = header.first()
+            .ok_or_else(|| VoleError::MalformedMessage("Missing arrival time".to_string()))?;
+        Ok(Duration::from_nanos(u128::from_le_bytes(*block) as u64))
+    }
+
+    fn wait_until(arrival: Duration) {
+        let now = Self::now();
+        if arrival > now {
+            thread::sleep(arrival - now);
+        }
+    }
+
+    fn receive_delayed<T>(&mut self, receive: impl FnOnce(&mut C) -> Result<T>) -> Result<T> {
+        let arrival = self.receive_arrival()?;
+        let value = receive(&mut self.inner)?;
+        Self::wait_until(arrival);
+        Ok(value)
+    }
+}
+
+impl<C: CommunicationChannel> CommunicationChannel for SimChannel<C> {
+    fn send_32byte_block(&mut self, data: &[[u8; 32]]) -> Result<()> {
+        self.send_arrival(data.len() * 32)?;
+        self.inner.send_32byte_block(data)
+    }
+
+    fn receive_32byte_block(&mut self) -> Result<Vec<[u8; 32]>> {
+        self.receive_delayed(|inner| inner.receive_32byte_block())
+    }
+
+    fn send_bits(&mut self, bits: &[bool]) -> Result<()> {
+        self.send_arrival((bits.len() + 7) / 8)?;
+        self.inner.send_bits(bits)
+    }
+
+    fn receive_bits(&mut self) -> Result<Vec<bool>> {
+        self.receive_delayed(|inner| inner.receive_bits())
+    }
+
+    fn send_stark252(&mut self, elements: &[FE]) -> Result<()> {
+        self.send_field_elements(elements)
+    }
+
+    fn receive_stark252(&mut self, count: usize) -> Result<Vec<FE>> {
+        self.receive_field_elements(count)
+    }
+
+    fn send_field_elements<F: VoleField>(&mut self, elements: &[FieldElement<F>]) -> Result<()> {
+        self.send_arrival(elements.len() * F::BYTE_LEN)?;
+        self.inner.send_field_elements(elements)
+    }
+
+    fn receive_field_elements<F: VoleField>(&mut self, count: usize) -> Result<Vec<FieldElement<F>>> {
+        self.receive_delayed(|inner| inner.receive_field_elements::<F>(count))
+    }
+
+    fn send_point(&mut self, point: &EncodedPoint) -> Result<()> {
+        self.send_arrival(point.as_bytes().len())?;
+        self.inner.send_point(point)
+    }
+
+    fn receive_point(&mut self) -> Result<EncodedPoint> {
+        self.receive_delayed(|inner| inner.receive_point())
+    }
+
+    fn send_data(&mut self, data: &[[u8; 16]]) -> Result<()> {
+        self.send_arrival(data.len() * 16)?;
+        self.inner.send_data(data)
+    }
+
+    fn receive_data(&mut self) -> Result<Vec<[u8; 16]>> {
+        self.receive_delayed(|inner| inner.receive_data())
+    }
+
+    fn flush(&mut self) -> Result<()> {
+        self.inner.flush()
+    }
+
+    fn stats(&self) -> Option<crate::counting_channel::CommStats> {
+        self.inner.stats()
+    }
+}
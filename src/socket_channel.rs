@@ -1,247 +1,226 @@
 use crate::comm_channel::CommunicationChannel;
 
 use std::net::TcpStream;
+#[cfg(unix)]
+use std::os::unix::net::{UnixListener, UnixStream};
+use std::path::Path;
 use p256::EncodedPoint;
 
-use std::io::{Write, Read};
+use std::io::{self, BufReader, BufWriter, Write, Read};
 
 use lambdaworks_math::field::fields::fft_friendly::stark_252_prime_field::Stark252PrimeField;
 use lambdaworks_math::field::element::FieldElement;
-use lambdaworks_math::traits::ByteConversion;
+use crate::field::VoleField;
+use crate::framing::{self, Framer, MessageKind, HEADER_LEN};
+use crate::error::Result;
 
-pub type F = Stark252PrimeField;
-pub type FE = FieldElement<F>;
+pub type FE = FieldElement<Stark252PrimeField>;
 
-pub struct TcpChannel {
-    stream: TcpStream,
+/// Default size of both buffers of a `BufferedChannel`.
+pub const DEFAULT_BUFFER_SIZE: usize = 1 << 20;
+
+/// Channel over any byte stream, e.g. a socket or a pipe.
+///
+/// Every message goes out as one frame, see `framing::Framer` for the header.
+pub struct StreamChannel<S: Read + Write> {
+    stream: S,
+    framer: Framer,
 }
 
-impl TcpChannel {
-    /// Creates a new TcpChannel
-    pub fn new(stream: TcpStream) -> Self {
-        Self { stream }
+pub type TcpChannel = StreamChannel<TcpStream>;
+
+#[cfg(unix)]
+pub type UnixChannel = StreamChannel<UnixStream>;
+
+pub type StdioChannel = StreamChannel<StreamPair<io::Stdin, io::Stdout>>;
+
+/// Channel with a read buffer and a write buffer over the two halves of a stream.
+///
+/// Sends only fill the write buffer, which goes out on `flush` or when it is full.
+/// A party must therefore flush before it waits on its peer, the protocols do so
+/// at the end of each round.
+pub type BufferedChannel<R, W> = StreamChannel<StreamPair<BufReader<R>, BufWriter<W>>>;
+
+pub type BufferedTcpChannel = BufferedChannel<TcpStream, TcpStream>;
+
+/// Joins a read half and a write half into one stream, e.g. the two pipes of a child process.
+pub struct StreamPair<R: Read, W: Write> {
+    pub reader: R,
+    pub writer: W,
+}
+
+impl<R: Read, W: Write> Read for StreamPair<R, W> {
+    fn read(&mut self, buf: &mut [u8]) -> io::Result<usize> {
+        self.reader.read(buf)
     }
 }
 
-impl CommunicationChannel for TcpChannel {
-    /// Sends an array of bits over the TCP channel.
-    fn send_bits(&mut self, bits: &[bool]) -> std::io::Result<()> {
-        // Serialize bits into bytes
-        let mut byte_array = Vec::with_capacity((bits.len() + 7) / 8);
-        let mut current_byte = 0u8;
-        for (i, &bit) in bits.iter().enumerate() {
-            if bit {
-                current_byte |= 1 << (i % 8);
-            }
-            if i % 8 == 7 || i == bits.len() - 1 {
-                byte_array.push(current_byte);
-                current_byte = 0;
-            }
-        }
-
-        // Send the total number of bits (as u64) and the byte array
-        let num_bits = bits.len() as u64;
-        self.stream.write_all(&num_bits.to_le_bytes())?;
-        self.stream.write_all(&byte_array)?;
+impl<R: Read, W: Write> Write for StreamPair<R, W> {
+    fn write(&mut self, buf: &[u8]) -> io::Result<usize> {
+        self.writer.write(buf)
+    }
 
-        Ok(())
+    fn flush(&mut self) -> io::Result<()> {
+        self.writer.flush()
+    }
+}
+
+impl<R: Read, W: Write> StreamChannel<StreamPair<R, W>> {
+    /// Creates a channel that reads from `reader` and writes to `writer`
+    pub fn from_pair(reader: R, writer: W) -> Self {
+        Self::new(StreamPair { reader, writer })
     }
+}
 
-    /// Receives an array of bits over the TCP channel.
-    fn receive_bits(&mut self) -> std::io::Result<Vec<bool>> {
-        // Read the total number of bits (u64)
-        let mut num_bits_buf = [0u8; 8];
-        self.stream.read_exact(&mut num_bits_buf)?;
-        let num_bits = u64::from_le_bytes(num_bits_buf) as usize;
-
-        // Read the serialized byte array
-        let num_bytes = (num_bits + 7) / 8;
-        let mut byte_array = vec![0u8; num_bytes];
-        self.stream.read_exact(&mut byte_array)?;
-
-        // Deserialize bytes back into bits
-        let mut bits = Vec::with_capacity(num_bits);
-        for (i, &byte) in byte_array.iter().enumerate() {
-            for j in 0..8 {
-                if i * 8 + j >= num_bits {
-                    break;
-                }
-                bits.push((byte & (1 << j)) != 0);
-            }
-        }
-
-        Ok(bits)
-    }
-
-    /// Sends a vector of STARK-252 field elements over the TCP channel.
-    fn send_stark252(&mut self, elements: &[FE]) -> std::io::Result<()> {
-        // Define the chunk size (in bytes). For example, 32 elements (32 bytes each) per chunk.
-        const CHUNK_SIZE: usize = 1024; // Adjust this as needed
-
-        // Serialize all elements into a vector
-        let mut serialized_data = Vec::with_capacity(elements.len() * 32);
-        for element in elements {
-            serialized_data.extend_from_slice(&element.to_bytes_le());
-        }
-
-        // Calculate the total size of the data
-        let total_size = serialized_data.len() as u64;
-
-        // Send the total size
-        self.stream.write_all(&total_size.to_le_bytes())?;
-
-        // Send data in chunks
-        let mut start = 0;
-        while start < serialized_data.len() {
-            let end = (start + CHUNK_SIZE).min(serialized_data.len());
-            self.stream.write_all(&serialized_data[start..end])?;
-            start = end;
-        }
+impl<R: Read, W: Write> BufferedChannel<R, W> {
+    /// Buffers reads from `reader` and writes to `writer`, `DEFAULT_BUFFER_SIZE` bytes each
+    pub fn buffered(reader: R, writer: W) -> Self {
+        Self::buffered_with_capacity(reader, writer, DEFAULT_BUFFER_SIZE)
+    }
 
-        Ok(())
+    /// Buffers reads from `reader` and writes to `writer`, `capacity` bytes each
+    pub fn buffered_with_capacity(reader: R, writer: W, capacity: usize) -> Self {
+        Self::from_pair(BufReader::with_capacity(capacity, reader), BufWriter::with_capacity(capacity, writer))
     }
+}
 
-    fn receive_stark252(&mut self, count: usize) -> std::io::Result<Vec<FE>> {
-        // Define the chunk size (in bytes)
-        const CHUNK_SIZE: usize = 1024; // Adjust this as needed
-
-        // Read the total size prefix
-        let mut size_buf = [0u8; 8];
-        self.stream.read_exact(&mut size_buf)?;
-        let total_size = u64::from_le_bytes(size_buf);
-
-        // Validate the size
-        let expected_size = (count * 32) as u64;
-        if total_size != expected_size {
-            return Err(std::io::Error::new(
-                std::io::ErrorKind::InvalidData,
-                format!(
-                    "Unexpected data size received: expected {}, got {}",
-                    expected_size, total_size
-                ),
-            ));
-        }
-
-        // Receive data in chunks
-        let mut raw_data = vec![0u8; total_size as usize];
-        let mut received = 0;
-        while received < total_size as usize {
-            let end = (received + CHUNK_SIZE).min(total_size as usize);
-            self.stream.read_exact(&mut raw_data[received..end])?;
-            received = end;
-        }
-
-        // Deserialize the elements
-        let elements: Result<Vec<_>, _> = raw_data
-            .chunks_exact(32)
-            .map(FE::from_bytes_le)
-            .collect();
-
-        elements.map_err(|e| {
-            std::io::Error::new(
-                std::io::ErrorKind::InvalidData,
-                format!("Failed to deserialize STARK-252 element: {:?}", e),
-            )
-        })
-    }
-
-
-    fn send_point(&mut self, point: &EncodedPoint) {
-        let point_bytes = point.as_bytes(); // Serialize the point
-        let size = point_bytes.len() as u64;
-
-        // Send the size and then the serialized point data
-        self.stream
-            .write_all(&size.to_le_bytes())
-            .expect("Failed to send point size");
-        self.stream
-            .write_all(point_bytes)
-            .expect("Failed to send point");
+impl BufferedTcpChannel {
+    /// Buffers both directions of `stream`, `capacity` bytes each
+    pub fn buffered_tcp(stream: TcpStream, capacity: usize) -> Result<Self> {
+        let read_half = stream.try_clone()?;
+        Ok(Self::buffered_with_capacity(read_half, stream, capacity))
     }
+}
 
-    fn receive_point(&mut self) -> EncodedPoint {
-        // Read the size of the incoming point
-        let mut size_buf = [0u8; 8];
-        self.stream
-            .read_exact(&mut size_buf)
-            .expect("Failed to receive point size");
-        let size = u64::from_le_bytes(size_buf) as usize;
+impl StdioChannel {
+    /// Talks to the peer over stdin and stdout, e.g. when it started this process with piped stdio.
+    ///
+    /// Nothing else may write to stdout, the protocols themselves print nothing.
+    pub fn stdio() -> Self {
+        Self::from_pair(io::stdin(), io::stdout())
+    }
+}
 
-        // Read the serialized point data
-        let mut point_bytes = vec![0u8; size];
+#[cfg(unix)]
+impl UnixChannel {
+    /// Connects to the Unix domain socket at `path`
+    pub fn connect_unix<P: AsRef<Path>>(path: P) -> Result<Self> {
+        Ok(Self::new(UnixStream::connect(path)?))
+    }
+
+    /// Binds a Unix domain socket at `path` and waits for the peer to connect.
+    ///
+    /// The socket file is removed once the peer is in, it only serves for the rendezvous.
+    pub fn accept_unix<P: AsRef<Path>>(path: P) -> Result<Self> {
+        let listener = UnixListener::bind(&path)?;
+        let (stream, _) = listener.accept()?;
+        std::fs::remove_file(&path)?;
+        Ok(Self::new(stream))
+    }
+
+    /// Two connected ends, e.g. for parties in two threads or across a fork
+    pub fn unix_pair() -> Result<(Self, Self)> {
+        let (a, b) = UnixStream::pair()?;
+        Ok((Self::new(a), Self::new(b)))
+    }
+}
+
+impl<S: Read + Write> StreamChannel<S> {
+    /// Creates a new channel over `stream`
+    pub fn new(stream: S) -> Self {
+        Self { stream, framer: Framer::default() }
+    }
+
+    /// Gives back the underlying stream
+    pub fn into_inner(self) -> S {
         self.stream
-            .read_exact(&mut point_bytes)
-            .expect("Failed to receive point data");
+    }
 
-        // Deserialize the point
-        EncodedPoint::from_bytes(&point_bytes).expect("Invalid point received")
+    /// Tags every frame with `session`, the peer must use the same one
+    pub fn with_session(mut self, session: u32) -> Self {
+        self.framer = Framer::new(session);
+        self
     }
 
-    fn send_data(&mut self, data: &[[u8; 16]]) {
-        let size = data.len() as u64;
+    /// Writes the header and the payload in one go
+    fn send_frame(&mut self, kind: MessageKind, payload: &[u8]) -> Result<()> {
+        let frame = self.framer.frame(kind, payload);
+        self.stream.write_all(&frame)?;
+        Ok(())
+    }
 
-        // Send the size of the data array
-        self.stream.write_all(&size.to_le_bytes()).expect("Failed to send data size");
+    /// Reads the next frame, which must be of `kind`, and returns its payload
+    fn receive_frame(&mut self, kind: MessageKind) -> Result<Vec<u8>> {
+        let mut header = [0u8; HEADER_LEN];
+        self.stream.read_exact(&mut header)?;
+        let len = self.framer.check(&header, kind)?;
 
-        // Send the 128-bit blocks
-        for block in data {
-            self.stream.write_all(block).expect("Failed to send data block");
-        }
+        let mut payload = vec![0u8; len];
+        self.stream.read_exact(&mut payload)?;
+        Ok(payload)
     }
+}
 
-    fn receive_data(&mut self) -> Vec<[u8; 16]> {
-        let mut size_buf = [0u8; 8];
-        self.stream.read_exact(&mut size_buf).expect("Failed to receive data size");
-        let size = u64::from_le_bytes(size_buf) as usize;
+impl<S: Read + Write> CommunicationChannel for StreamChannel<S> {
+    /// Sends an array of bits over the channel.
+    fn send_bits(&mut self, bits: &[bool]) -> Result<()> {
+        self.send_frame(MessageKind::Bits, &framing::encode_bits(bits))
+    }
 
-        let mut data = Vec::with_capacity(size);
-        for _ in 0..size {
-            let mut block = [0u8; 16];
-            self.stream.read_exact(&mut block).expect("Failed to receive data block");
-            data.push(block);
-        }
-        data
+    /// Receives an array of bits over the channel.
+    fn receive_bits(&mut self) -> Result<Vec<bool>> {
+        let payload = self.receive_frame(MessageKind::Bits)?;
+        framing::decode_bits(&payload)
     }
 
-    fn send_32byte_block(&mut self, data: &[[u8; 32]]) {
-        let size = data.len() as u64;
+    /// Sends a vector of STARK-252 field elements over the channel.
+    fn send_stark252(&mut self, elements: &[FE]) -> Result<()> {
+        self.send_field_elements(elements)
+    }
 
-        // Send the size of the data array
-        self.stream
-            .write_all(&size.to_le_bytes())
-            .expect("Failed to send data size");
+    fn receive_stark252(&mut self, count: usize) -> Result<Vec<FE>> {
+        self.receive_field_elements(count)
+    }
 
-        // Send the 256-bit (32-byte) blocks
-        for block in data {
-            self.stream
-                .write_all(block)
-                .expect("Failed to send data block");
-        }
+    /// Sends a vector of field elements over the channel.
+    fn send_field_elements<F: VoleField>(&mut self, elements: &[FieldElement<F>]) -> Result<()> {
+        self.send_frame(MessageKind::FieldElements, &framing::encode_field_elements(elements))
     }
 
-    fn receive_32byte_block(&mut self) -> Vec<[u8; 32]> {
-        // Receive the size of the data array
-        let mut size_buf = [0u8; 8];
-        self.stream
-            .read_exact(&mut size_buf)
-            .expect("Failed to receive data size");
-        let size = u64::from_le_bytes(size_buf) as usize;
+    fn receive_field_elements<F: VoleField>(&mut self, count: usize) -> Result<Vec<FieldElement<F>>> {
+        let payload = self.receive_frame(MessageKind::FieldElements)?;
+        framing::decode_field_elements(&payload, count)
+    }
+
+    fn send_point(&mut self, point: &EncodedPoint) -> Result<()> {
+        self.send_frame(MessageKind::Point, point.as_bytes())
+    }
+
+    fn receive_point(&mut self) -> Result<EncodedPoint> {
+        let payload = self.receive_frame(MessageKind::Point)?;
+        framing::decode_point(&payload)
+    }
+
+    fn send_data(&mut self, data: &[[u8; 16]]) -> Result<()> {
+        self.send_frame(MessageKind::Data, &framing::encode_blocks(data))
+    }
 
-        // Receive the 256-bit (32-byte) blocks
-        let mut data = Vec::with_capacity(size);
-        for _ in 0..size {
-            let mut block = [0u8; 32];
-            self.stream
-                .read_exact(&mut block)
-                .expect("Failed to receive data block");
-            data.push(block);
-        }
-        data
+    fn receive_data(&mut self) -> Result<Vec<[u8; 16]>> {
+        let payload = self.receive_frame(MessageKind::Data)?;
+        framing::decode_blocks(&payload)
     }
 
+    fn send_32byte_block(&mut self, data: &[[u8; 32]]) -> Result<()> {
+        self.send_frame(MessageKind::Block32, &framing::encode_blocks(data))
+    }
 
+    fn receive_32byte_block(&mut self) -> Result<Vec<[u8; 32]>> {
+        let payload = self.receive_frame(MessageKind::Block32)?;
+        framing::decode_blocks(&payload)
+    }
 
-    fn flush(&mut self) {
-        self.stream.flush().expect("Failed to flush stream");
+    fn flush(&mut self) -> Result<()> {
+        self.stream.flush()?;
+        Ok(())
     }
 }
@@ -0,0 +1,424 @@
+use crate::ot::OTCO;
+use crate::comm_channel::CommunicationChannel;
+use crate::async_comm_channel::{AsyncCommunicationChannel, SyncChannel, block_on};
+use crate::error::{Result, VoleError, CheckFailure};
+use crate::prg::PRG;
+use crate::transpose::transpose;
+use crate::ot_extension::{CotSender, CotReceiver, OtExtension};
+use crate::iknp::{block_to_bool, BLOCK_SIZE, bool_to_block, xor_blocks, send_blocks, receive_blocks, check_send_async, check_recv_async};
+
+/// Largest `k`, the receiver expands 2^k PRGs for every `k` bits of Δ.
+pub const MAX_K: usize = 10;
+
+/// SoftSpokenOT extension (Roy, CRYPTO 2022) with `N`-byte COT blocks, from `8 * N` base OTs.
+///
+/// Δ is cut into chunks of `k` bits, each the Δ of a small VOLE over GF(2^k) built from a
+/// (2^k - 1)-out-of-2^k punctured OT: the receiver expands a GGM tree with 2^k leaves and
+/// the sender learns all of them but the one at its chunk of Δ, from `k` base OTs. Every
+/// chunk costs the receiver one correction of its choice bits where IKNP sends `k`, so the
+/// extension sends about `k` times less, for about `2^k / k` times the PRG work. `k = 1`
+/// is IKNP again. The malicious check is IKNP's.
+pub struct SoftSpokenOT<const N: usize = 32> {
+    base_ot: OTCO,
+    k: usize,
+    malicious: bool,
+    setup: bool,
+    delta: Option<[u8; N]>,
+    s: Vec<bool>,
+    // A PRG per leaf of each chunk's tree, the sender has none at its punctured leaf
+    leaves: Vec<Vec<Option<PRG>>>,
+    local_r: Vec<bool>,
+    local_out: Vec<[u8; N]>,
+    // Per-block scratch, allocated once: the matrix rows, the row of one leaf and the corrections
+    rows: Vec<[u8; N]>,
+    leaf_row: Vec<[u8; N]>,
+    corrections: Vec<[u8; N]>,
+    // Output of a partial or check block
+    block_out: Vec<[u8; N]>,
+}
+
+impl<const N: usize> SoftSpokenOT<N> {
+    /// Number of base OTs, i.e. bits in a COT block
+    pub const NUM_BITS: usize = 8 * N;
+
+    // Blocks in a row of the matrix, one bit per COT of an extension block
+    const ROW_BLOCKS: usize = BLOCK_SIZE / Self::NUM_BITS;
+
+    const WIDTH_CHECK: () = assert!(N == 16 || N == 32, "SoftSpokenOT blocks are 16 or 32 bytes");
+
+    /// `k` bits of Δ per small VOLE, from 1 to `MAX_K`
+    pub fn new(k: usize, malicious: bool) -> Result<Self> {
+        let () = Self::WIDTH_CHECK;
+        if k == 0 || k > MAX_K {
+            return Err(VoleError::Parameter(format!("SoftSpokenOT needs 1 <= k <= {}, got {}", MAX_K, k)));
+        }
+        let chunks = (Self::NUM_BITS + k - 1) / k;
+        Ok(Self {
+            base_ot: OTCO::new(),
+            k,
+            malicious,
+            setup: false,
+            delta: None,
+            s: vec![false; Self::NUM_BITS],
+            leaves: Vec::new(),
+            local_r: vec![false; 2 * Self::NUM_BITS],
+            local_out: vec![[0u8; N]; BLOCK_SIZE],
+            rows: vec![[0u8; N]; BLOCK_SIZE],
+            leaf_row: vec![[0u8; N]; Self::ROW_BLOCKS],
+            corrections: vec![[0u8; N]; chunks * Self::ROW_BLOCKS],
+            block_out: vec![[0u8; N]; BLOCK_SIZE],
+        })
+    }
+
+    pub fn k(&self) -> usize {
+        self.k
+    }
+
+    pub fn malicious(&self) -> bool {
+        self.malicious
+    }
+
+    // First bit and number of bits of each chunk of Δ, the last one may be short
+    fn chunks(&self) -> Vec<(usize, usize)> {
+        (0..Self::NUM_BITS)
+            .step_by(self.k)
+            .map(|start| (start, self.k.min(Self::NUM_BITS - start)))
+            .collect()
+    }
+
+    pub fn setup_send<IO: CommunicationChannel>(&mut self, io: &mut IO, in_s: Option<&[bool]>) -> Result<()> {
+        block_on(self.setup_send_async(&mut SyncChannel(io), in_s))
+    }
+
+    /// Async variant of `setup_send`.
+    pub async fn setup_send_async<IO: AsyncCommunicationChannel>(&mut self, io: &mut IO, in_s: Option<&[bool]>) -> Result<()> {
+        self.setup = true;
+
+        if let Some(in_s) = in_s {
+            self.s.copy_from_slice(in_s);
+        } else {
+            let mut prg = PRG::new(None, 0);
+            prg.random_bool_array(&mut self.s);
+        }
+
+        // At each level, the sibling of the node on the path to the chunk of Δ
+        let chunks = self.chunks();
+        let mut choices = Vec::with_capacity(Self::NUM_BITS);
+        for &(start, bits) in chunks.iter() {
+            let alpha = chunk_value(&self.s[start..start + bits]);
+            for level in 0..bits {
+                choices.push((alpha >> (bits - 1 - level)) & 1 == 0);
+            }
+        }
+        let mut sums = Vec::with_capacity(Self::NUM_BITS);
+        self.base_ot.recv_async(io, &choices, &mut sums).await?;
+        if sums.len() != Self::NUM_BITS {
+            return Err(VoleError::MalformedMessage(format!("Expected {} base OTs, got {}", Self::NUM_BITS, sums.len())));
+        }
+
+        self.leaves = chunks.iter()
+            .map(|&(start, bits)| {
+                let alpha = chunk_value(&self.s[start..start + bits]);
+                punctured_leaves(bits, alpha, &sums[start..start + bits])
+                    .iter()
+                    .map(|leaf| leaf.map(|seed| PRG::new(Some(&seed), 0)))
+                    .collect()
+            })
+            .collect();
+
+        self.delta = Some(bool_to_block(&self.s));
+        Ok(())
+    }
+
+    pub fn setup_recv<IO: CommunicationChannel>(&mut self, io: &mut IO) -> Result<()> {
+        block_on(self.setup_recv_async(&mut SyncChannel(io)))
+    }
+
+    /// Async variant of `setup_recv`.
+    pub async fn setup_recv_async<IO: AsyncCommunicationChannel>(&mut self, io: &mut IO) -> Result<()> {
+        self.setup = true;
+
+        let mut prg = PRG::new(None, 0);
+        let mut m0 = Vec::with_capacity(Self::NUM_BITS);
+        let mut m1 = Vec::with_capacity(Self::NUM_BITS);
+        let mut leaves = Vec::new();
+        for &(_, bits) in self.chunks().iter() {
+            let mut root = [[0u8; 16]];
+            prg.random_block(&mut root);
+            let (tree_leaves, sums) = expand_tree(root[0], bits);
+            for [sum0, sum1] in sums {
+                m0.push(sum0);
+                m1.push(sum1);
+            }
+            leaves.push(tree_leaves.iter().map(|seed| Some(PRG::new(Some(seed), 0))).collect());
+        }
+        self.base_ot.send_async(io, &m0, &m1).await?;
+        self.leaves = leaves;
+        Ok(())
+    }
+
+    pub fn send_cot<IO: CommunicationChannel>(&mut self, io: &mut IO, data: &mut [[u8; N]], length: usize) -> Result<()> {
+        block_on(self.send_cot_async(&mut SyncChannel(io), data, length))
+    }
+
+    /// Async variant of `send_cot`.
+    pub async fn send_cot_async<IO: AsyncCommunicationChannel>(&mut self, io: &mut IO, data: &mut [[u8; N]], length: usize) -> Result<()> {
+        self.send_pre_async(io, data, length).await?;
+        self.check_send(io, data, length).await
+    }
+
+    // The COTs, then in malicious mode the block the check masks with
+    async fn send_pre_async<IO: AsyncCommunicationChannel>(&mut self, io: &mut IO, data: &mut [[u8; N]], length: usize) -> Result<()> {
+        if !self.setup {
+            self.setup_send_async(io, None).await?;
+        }
+
+        let mut idx = 0;
+        while idx < length {
+            let len = BLOCK_SIZE.min(length - idx);
+            let mut temp_out = std::mem::take(&mut self.block_out);
+            let res = self.send_block_async(io, &mut temp_out, len).await;
+            data[idx..idx + len].copy_from_slice(&temp_out[..len]);
+            self.block_out = temp_out;
+            res?;
+            idx += len;
+        }
+
+        if self.malicious {
+            let mut temp_out = std::mem::take(&mut self.local_out);
+            let res = self.send_block_async(io, &mut temp_out, 2 * Self::NUM_BITS).await;
+            self.local_out = temp_out;
+            res?;
+        }
+        Ok(())
+    }
+
+    pub fn recv_cot<IO: CommunicationChannel>(&mut self, io: &mut IO, data: &mut [[u8; N]], r: &[bool], length: usize) -> Result<()> {
+        block_on(self.recv_cot_async(&mut SyncChannel(io), data, r, length))
+    }
+
+    /// Async variant of `recv_cot`.
+    pub async fn recv_cot_async<IO: AsyncCommunicationChannel>(&mut self, io: &mut IO, data: &mut [[u8; N]], r: &[bool], length: usize) -> Result<()> {
+        self.recv_pre_async(io, data, r, length).await?;
+        self.check_recv(io, data, r, length).await
+    }
+
+    async fn recv_pre_async<IO: AsyncCommunicationChannel>(&mut self, io: &mut IO, data: &mut [[u8; N]], r: &[bool], length: usize) -> Result<()> {
+        if !self.setup {
+            self.setup_recv_async(io).await?;
+        }
+
+        let block_r: Vec<[u8; N]> = r[..length].chunks(Self::NUM_BITS).map(bool_to_block).collect();
+
+        let mut idx = 0;
+        while idx < length {
+            let len = BLOCK_SIZE.min(length - idx);
+            let r_row = &block_r[idx / Self::NUM_BITS..(idx + len + Self::NUM_BITS - 1) / Self::NUM_BITS];
+            let mut temp_out = std::mem::take(&mut self.block_out);
+            let res = self.recv_block_async(io, &mut temp_out, r_row, len).await;
+            data[idx..idx + len].copy_from_slice(&temp_out[..len]);
+            self.block_out = temp_out;
+            res?;
+            idx += len;
+        }
+
+        if self.malicious {
+            let mut prg = PRG::new(None, 0);
+            prg.random_bool_array(&mut self.local_r);
+            let local_r_block: Vec<[u8; N]> = self.local_r.chunks(Self::NUM_BITS).map(bool_to_block).collect();
+            let mut temp_out = std::mem::take(&mut self.local_out);
+            let res = self.recv_block_async(io, &mut temp_out, &local_r_block, 2 * Self::NUM_BITS).await;
+            self.local_out = temp_out;
+            res?;
+        }
+        io.flush().await?;
+        Ok(())
+    }
+
+    // Sender side of one matrix: row i is Δ_i times the choice bits plus the receiver's row i
+    async fn send_block_async<IO: AsyncCommunicationChannel>(&mut self, io: &mut IO, out: &mut [[u8; N]], length: usize) -> Result<()> {
+        let used = (length + Self::NUM_BITS - 1) / Self::NUM_BITS;
+        let chunks = self.chunks();
+        let corrections = receive_blocks::<_, N>(io).await?;
+        if corrections.len() != chunks.len() * Self::ROW_BLOCKS {
+            return Err(VoleError::MalformedMessage(format!(
+                "Expected {} SoftSpokenOT corrections, got {}", chunks.len() * Self::ROW_BLOCKS, corrections.len()
+            )));
+        }
+
+        let (rows, leaf_row) = (&mut self.rows, &mut self.leaf_row);
+        rows.fill([0u8; N]);
+        for (j, (&(start, bits), prgs)) in chunks.iter().zip(self.leaves.iter_mut()).enumerate() {
+            let alpha = chunk_value(&self.s[start..start + bits]);
+            // Row b gets the leaves x with x_b != Δ_b, which never includes the punctured one
+            for (x, prg) in prgs.iter_mut().enumerate() {
+                if let Some(prg) = prg {
+                    prg.random_blocks(&mut leaf_row[..used]);
+                    for b in 0..bits {
+                        if ((x ^ alpha) >> b) & 1 == 1 {
+                            let row = (start + b) * Self::ROW_BLOCKS;
+                            xor_blocks(&mut rows[row..row + used], &leaf_row[..used]);
+                        }
+                    }
+                }
+            }
+            let correction = &corrections[j * Self::ROW_BLOCKS..j * Self::ROW_BLOCKS + used];
+            for b in 0..bits {
+                if self.s[start + b] {
+                    let row = (start + b) * Self::ROW_BLOCKS;
+                    xor_blocks(&mut rows[row..row + used], correction);
+                }
+            }
+        }
+
+        transpose(out.as_flattened_mut(), rows.as_flattened(), Self::NUM_BITS, BLOCK_SIZE);
+        Ok(())
+    }
+
+    // Receiver side: row b of a chunk sums the leaves x with x_b = 1, and the sum of all
+    // leaves is corrected to the choice bits
+    async fn recv_block_async<IO: AsyncCommunicationChannel>(&mut self, io: &mut IO, out: &mut [[u8; N]], r: &[[u8; N]], length: usize) -> Result<()> {
+        let used = (length + Self::NUM_BITS - 1) / Self::NUM_BITS;
+        let chunks = self.chunks();
+
+        let (rows, leaf_row, corrections) = (&mut self.rows, &mut self.leaf_row, &mut self.corrections);
+        rows.fill([0u8; N]);
+        corrections.fill([0u8; N]);
+        for (j, (&(start, bits), prgs)) in chunks.iter().zip(self.leaves.iter_mut()).enumerate() {
+            let correction = &mut corrections[j * Self::ROW_BLOCKS..j * Self::ROW_BLOCKS + used];
+            for (x, prg) in prgs.iter_mut().enumerate() {
+                let prg = prg.as_mut().ok_or_else(|| VoleError::Parameter("The receiver knows every leaf".to_string()))?;
+                prg.random_blocks(&mut leaf_row[..used]);
+                xor_blocks(correction, &leaf_row[..used]);
+                for b in 0..bits {
+                    if (x >> b) & 1 == 1 {
+                        let row = (start + b) * Self::ROW_BLOCKS;
+                        xor_blocks(&mut rows[row..row + used], &leaf_row[..used]);
+                    }
+                }
+            }
+            xor_blocks(correction, r);
+        }
+
+        send_blocks(io, corrections).await?;
+        transpose(out.as_flattened_mut(), rows.as_flattened(), Self::NUM_BITS, BLOCK_SIZE);
+        Ok(())
+    }
+}
+
+impl<const N: usize> CotSender<N> for SoftSpokenOT<N> {
+    async fn setup_sender<IO: AsyncCommunicationChannel>(&mut self, io: &mut IO, delta: &[u8; N]) -> Result<()> {
+        self.setup_send_async(io, Some(&block_to_bool(delta))).await
+    }
+
+    async fn extend_send<IO: AsyncCommunicationChannel>(&mut self, io: &mut IO, data: &mut [[u8; N]], length: usize) -> Result<()> {
+        self.send_pre_async(io, data, length).await
+    }
+
+    async fn check_send<IO: AsyncCommunicationChannel>(&mut self, io: &mut IO, data: &[[u8; N]], length: usize) -> Result<()> {
+        if self.malicious {
+            let delta = self.delta.ok_or_else(|| VoleError::Parameter("Delta must be set during setup".to_string()))?;
+            if !check_send_async(io, data, length, &self.local_out, delta).await? {
+                return Err(VoleError::ConsistencyCheck(CheckFailure::SoftSpoken));
+            }
+        }
+        Ok(())
+    }
+}
+
+impl<const N: usize> CotReceiver<N> for SoftSpokenOT<N> {
+    async fn setup_receiver<IO: AsyncCommunicationChannel>(&mut self, io: &mut IO) -> Result<()> {
+        self.setup_recv_async(io).await
+    }
+
+    async fn extend_recv<IO: AsyncCommunicationChannel>(&mut self, io: &mut IO, data: &mut [[u8; N]], r: &[bool], length: usize) -> Result<()> {
+        self.recv_pre_async(io, data, r, length).await
+    }
+
+    async fn check_recv<IO: AsyncCommunicationChannel>(&mut self, io: &mut IO, data: &[[u8; N]], r: &[bool], length: usize) -> Result<()> {
+        if self.malicious {
+            check_recv_async(io, data, r, length, &self.local_out, &self.local_r).await?;
+        }
+        Ok(())
+    }
+}
+
+impl<const N: usize> OtExtension<N> for SoftSpokenOT<N> {
+    fn name(&self) -> String {
+        format!("SoftSpokenOT k={}", self.k)
+    }
+
+    fn malicious(&self) -> bool {
+        self.malicious
+    }
+}
+
+// The bits of a chunk of Δ as a leaf index, first bit lowest
+fn chunk_value(bits: &[bool]) -> usize {
+    bits.iter().enumerate().map(|(b, &bit)| (bit as usize) << b).sum()
+}
+
+fn expand_node(seed: &[u8; 16]) -> [[u8; 16]; 2] {
+    let mut children = [[0u8; 16]; 2];
+    PRG::new(Some(seed), 0).random_block(&mut children);
+    children
+}
+
+// GGM tree of the given depth, node i has children 2i and 2i + 1, so the path to leaf x
+// follows its bits from the highest. Returns the leaves and, for each level below the
+// root, the sums of its left and of its right children.
+fn expand_tree(root: [u8; 16], depth: usize) -> (Vec<[u8; 16]>, Vec<[[u8; 16]; 2]>) {
+    let mut level = vec![root];
+    let mut sums = Vec::with_capacity(depth);
+    for _ in 0..depth {
+        let mut next = Vec::with_capacity(2 * level.len());
+        let mut sum = [[0u8; 16]; 2];
+        for node in level.iter() {
+            let children = expand_node(node);
+            for (s, child) in sum.iter_mut().zip(children.iter()) {
+                xor_into(s, child);
+            }
+            next.extend_from_slice(&children);
+        }
+        sums.push(sum);
+        level = next;
+    }
+    (level, sums)
+}
+
+// All leaves but `alpha`, from the sums of the siblings of its path, level by level
+fn punctured_leaves(depth: usize, alpha: usize, sums: &[[u8; 16]]) -> Vec<Option<[u8; 16]>> {
+    let mut level = vec![None];
+    let mut path = 0;
+    for (l, sum) in sums.iter().enumerate().take(depth) {
+        let bit = (alpha >> (depth - 1 - l)) & 1;
+        let mut next = vec![None; 2 * level.len()];
+        for (i, node) in level.iter().enumerate() {
+            if let Some(seed) = node {
+                let [left, right] = expand_node(seed);
+                next[2 * i] = Some(left);
+                next[2 * i + 1] = Some(right);
+            }
+        }
+        // The sibling is its level's sum minus the other known nodes on its side
+        let sibling = 2 * path + (1 - bit);
+        let mut value = *sum;
+        for (i, node) in next.iter().enumerate() {
+            if i % 2 == 1 - bit && i != sibling {
+                if let Some(node) = node {
+                    xor_into(&mut value, node);
+                }
+            }
+        }
+        next[sibling] = Some(value);
+        path = 2 * path + bit;
+        level = next;
+    }
+    level
+}
+
+fn xor_into(a: &mut [u8; 16], b: &[u8; 16]) {
+    for (x, y) in a.iter_mut().zip(b.iter()) {
+        *x ^= y;
+    }
+}
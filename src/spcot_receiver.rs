@@ -0,0 +1,102 @@
+use crate::two_key_prp::TwoKeyPRP;
+use crate::comm_channel::CommunicationChannel;
+use crate::preot::OTPre;
+use crate::hash::Block;
+use crate::spcot_sender::xor_block_inplace;
+use crate::error::{Result, VoleError};
+
+/// Single-point COT receiver over GF(2^128): the binary analogue of `SpfssRecverFp`.
+pub struct SpcotRecver {
+    m: Vec<Block>,
+    pub(crate) b: Vec<bool>,
+    choice_pos: usize,
+    depth: usize,
+    leave_n: usize,
+    share: Block,
+}
+
+impl SpcotRecver {
+    /// Create a new SpcotRecver instance, `b` must be set before `recv`.
+    pub fn new(depth: usize) -> Self {
+        let leave_n = 1 << (depth - 1);
+        Self {
+            m: vec![[0u8; 16]; depth - 1],
+            b: vec![false; depth - 1],
+            choice_pos: 0,
+            depth,
+            leave_n,
+            share: [0u8; 16],
+        }
+    }
+
+    pub fn get_index(&mut self) -> usize {
+        self.choice_pos = 0;
+        for i in 0..self.depth-1 {
+            self.choice_pos <<= 1;
+            if !self.b[i] {
+                self.choice_pos += 1;
+            }
+        }
+        self.choice_pos
+    }
+
+    /// Receive the level sums and the secret sum.
+    pub fn recv<IO: CommunicationChannel>(&mut self, io: &mut IO, ot: &mut OTPre<16>, s: usize) -> Result<()> {
+        ot.recv(io, &mut self.m, &self.b, self.depth - 1, s)?;
+        self.share = *io.receive_data()?.first()
+            .ok_or_else(|| VoleError::MalformedMessage("Missing SPCOT secret sum".to_string()))?;
+        Ok(())
+    }
+
+    /// Reconstruct the punctured GGM tree into `ggm_tree_mem`.
+    pub fn compute(&mut self, ggm_tree_mem: &mut [Block]) {
+        self.reconstruct_tree(ggm_tree_mem);
+
+        let pos = self.get_index();
+        ggm_tree_mem[pos] = self.share;
+        for i in 0..self.leave_n {
+            if i != pos {
+                let leaf = ggm_tree_mem[i];
+                xor_block_inplace(&mut ggm_tree_mem[pos], &leaf);
+            }
+        }
+    }
+
+    /// Recover every node except the punctured path, level by level.
+    fn reconstruct_tree(&mut self, ggm_tree_mem: &mut [Block]) {
+        let prp = TwoKeyPRP::new();
+        let mut children = [[0u8; 16]; 2];
+        let mut pos = 0;
+
+        for h in 0..self.depth - 1 {
+            let sz = 2 << h;
+            // Walk backwards so parents are not overwritten, the children of the punctured node are unknown
+            for i in (0..sz / 2).rev() {
+                if h == 0 || i == pos {
+                    ggm_tree_mem[2 * i] = [0u8; 16];
+                    ggm_tree_mem[2 * i + 1] = [0u8; 16];
+                } else {
+                    let parent = ggm_tree_mem[i];
+                    prp.block_expand_1to2(&mut children, &parent);
+                    ggm_tree_mem[2 * i] = children[0];
+                    ggm_tree_mem[2 * i + 1] = children[1];
+                }
+            }
+            if h == self.depth - 2 {
+                for leaf in ggm_tree_mem[..sz].iter_mut() {
+                    leaf[0] &= 0xfe;
+                }
+            }
+
+            // b[h] = false means we got the sum of the left children
+            let lr = if self.b[h] { 1 } else { 0 };
+            let to_fill_idx = 2 * pos + lr;
+            let mut sum = self.m[h];
+            for i in (lr..sz).step_by(2) {
+                xor_block_inplace(&mut sum, &ggm_tree_mem[i]);
+            }
+            ggm_tree_mem[to_fill_idx] = sum;
+            pos = 2 * pos + 1 - lr;
+        }
+    }
+}
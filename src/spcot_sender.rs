@@ -0,0 +1,99 @@
+use crate::two_key_prp::TwoKeyPRP;
+use crate::prg::PRG;
+use crate::comm_channel::CommunicationChannel;
+use crate::preot::OTPre;
+use crate::hash::Block;
+use crate::error::Result;
+
+/// Single-point COT sender over GF(2^128): the binary analogue of `SpfssSenderFp`.
+pub struct SpcotSender {
+    seed: Block,
+    delta: Block,
+    secret_sum: Block,
+    m0: Vec<Block>,
+    m1: Vec<Block>,
+    depth: usize,
+    leave_n: usize,
+}
+
+impl SpcotSender {
+    /// Create a new SpcotSender instance.
+    pub fn new(depth: usize) -> Self {
+        let leave_n = 1 << (depth - 1);
+        let mut prg = PRG::new(None, 0);
+        let mut seed = [[0u8; 16]; 1];
+        prg.random_block(&mut seed);
+        Self {
+            seed: seed[0],
+            delta: [0u8; 16],
+            secret_sum: [0u8; 16],
+            m0: vec![[0u8; 16]; depth - 1],
+            m1: vec![[0u8; 16]; depth - 1],
+            depth,
+            leave_n,
+        }
+    }
+
+    /// Build the GGM tree into `ggm_tree_mem`, the punctured leaf will be shifted by `delta`.
+    pub fn compute(&mut self, ggm_tree_mem: &mut [Block], delta: Block) {
+        self.delta = delta;
+        self.ggm_tree_gen(ggm_tree_mem);
+    }
+
+    /// Send OT messages and secret sum.
+    pub fn send<IO: CommunicationChannel>(&mut self, io: &mut IO, ot: &mut OTPre<16>, s: usize) -> Result<()> {
+        ot.send(io, &self.m0, &self.m1, self.depth - 1, s)?;
+        io.send_data(&[self.secret_sum])
+    }
+
+    /// Generate the GGM tree level by level, expanding in place.
+    // The LSB of every leaf is cleared, so that LSB of the receiver's output is its choice bit
+    fn ggm_tree_gen(&mut self, ggm_tree_mem: &mut [Block]) {
+        let prp = TwoKeyPRP::new();
+        let mut children = [[0u8; 16]; 2];
+
+        prp.block_expand_1to2(&mut ggm_tree_mem[0..2], &self.seed);
+        for h in 0..self.depth - 1 {
+            let sz = 2 << h;
+            if h > 0 {
+                // Parents live in the first half, walk backwards so they are not overwritten
+                for i in (0..sz / 2).rev() {
+                    let parent = ggm_tree_mem[i];
+                    prp.block_expand_1to2(&mut children, &parent);
+                    ggm_tree_mem[2 * i] = children[0];
+                    ggm_tree_mem[2 * i + 1] = children[1];
+                }
+            }
+            if h == self.depth - 2 {
+                for leaf in ggm_tree_mem[..sz].iter_mut() {
+                    leaf[0] &= 0xfe;
+                }
+            }
+
+            self.m0[h] = [0u8; 16];
+            self.m1[h] = [0u8; 16];
+            for i in (0..sz).step_by(2) {
+                xor_block_inplace(&mut self.m0[h], &ggm_tree_mem[i]);
+                xor_block_inplace(&mut self.m1[h], &ggm_tree_mem[i + 1]);
+            }
+        }
+
+        // Compute the secret sum
+        self.secret_sum = self.delta;
+        for leaf in ggm_tree_mem.iter().take(self.leave_n) {
+            xor_block_inplace(&mut self.secret_sum, leaf);
+        }
+    }
+}
+
+pub(crate) fn block_to_32byte(block: &Block) -> [u8; 32] {
+    let mut out = [0u8; 32];
+    out[..16].copy_from_slice(block);
+    out
+}
+
+pub(crate) fn xor_block_inplace(a: &mut Block, b: &Block) {
+    for i in 0..16 {
+        a[i] ^= b[i];
+    }
+}
@@ -1,41 +1,48 @@
-use crate::two_key_prp::TwoKeyPRP;
+use crate::two_key_prp::{TwoKeyPRP, GgmExpansion};
 use crate::prg::PRG;
 use crate::comm_channel::CommunicationChannel;
+use crate::async_comm_channel::{AsyncCommunicationChannel, SyncChannel, block_on};
 use crate::preot::OTPre;
-use crate::hash::Hash;
-use lambdaworks_math::field::fields::fft_friendly::stark_252_prime_field::Stark252PrimeField;
+use crate::error::{Result, VoleError, CheckFailure};
+use crate::hash::{Hash, Block};
+use crate::spcot_sender::xor_block_inplace;
+use crate::field::{VoleField, DefaultField};
 use lambdaworks_math::field::element::FieldElement;
-use lambdaworks_math::traits::ByteConversion;
 use std::convert::TryInto;
 
-pub type F = Stark252PrimeField;
-pub type FE = FieldElement<F>;
-
-pub struct SpfssRecverFp {
-    ggm_tree: Vec<FE>,
-    m: Vec<FE>,
+/// Rebuilds the sender's tree of 128-bit blocks but for the punctured path, then maps the leaves into `F`.
+pub struct SpfssRecverFp<F: VoleField = DefaultField> {
+    ggm_tree: Vec<FieldElement<F>>,
+    m: Vec<Block>,
     pub(crate) b: Vec<bool>,
     choice_pos: usize,
     depth: usize,
     leave_n: usize,
-    share: FE,
+    share: FieldElement<F>,
+    expansion: GgmExpansion,
 }
 
-impl SpfssRecverFp {
+impl<F: VoleField> SpfssRecverFp<F> {
     /// Create a new SpfssRecverFp instance.
     pub fn new(depth: usize) -> Self {
         let leave_n = 1 << (depth - 1);
         Self {
-            ggm_tree: vec![FE::zero(); leave_n],
-            m: vec![FE::zero(); depth - 1],
+            ggm_tree: vec![FieldElement::zero(); leave_n],
+            m: vec![[0u8; 16]; depth - 1],
             b: vec![false; depth - 1],
             choice_pos: (1 << depth - 1) - 1,
             depth,
             leave_n,
-            share: FE::zero(),
+            share: FieldElement::zero(),
+            expansion: GgmExpansion::default(),
         }
     }
 
+    /// How the GGM tree is expanded, the sender must use the same.
+    pub fn set_expansion(&mut self, expansion: GgmExpansion) {
+        self.expansion = expansion;
+    }
+
     pub fn get_index(&self) -> usize {
         let mut choice_pos = 0;
         for i in 0..self.depth-1 {
@@ -48,23 +55,35 @@ impl SpfssRecverFp {
     }
 
     /// Receive the message and reconstruct the tree.
-    pub fn recv<IO: CommunicationChannel>(&mut self, io: &mut IO, ot: &mut OTPre, s: usize) {
+    pub fn recv<IO: CommunicationChannel>(&mut self, io: &mut IO, ot: &mut OTPre, s: usize) -> Result<()> {
+        block_on(self.recv_async(&mut SyncChannel(io), ot, s))
+    }
+
+    /// Async variant of `recv`.
+    pub async fn recv_async<IO: AsyncCommunicationChannel>(&mut self, io: &mut IO, ot: &mut OTPre, s: usize) -> Result<()> {
         let mut receive_data = vec![[0u8; 32]; self.depth - 1];
-        ot.recv(io, &mut receive_data, &mut self.b, self.depth - 1, s);
+        ot.recv_async(io, &mut receive_data, &mut self.b, self.depth - 1, s).await?;
 
+        // The level sums are blocks, zero padded to the OT message size
+        if receive_data.iter().any(|x| x[16..].iter().any(|&byte| byte != 0)) {
+            return Err(VoleError::MalformedMessage("Invalid GGM level sum".to_string()));
+        }
         self.m = receive_data
             .iter()
-            .map(|x| FE::from_bytes_le(x).unwrap())
-            .collect::<Vec<FE>>();
-        self.share = io.receive_stark252(1).expect("Failed to receive share")[0];
+            .map(|x| x[..16].try_into().unwrap())
+            .collect();
+        self.share = io.receive_field_elements::<F>(1).await?[0];
+        Ok(())
     }
 
     /// Compute the GGM tree and reconstruct the nodes.
-    pub fn compute(&mut self, ggm_tree_mem: &mut [FE], delta2: FE) {
-        self.reconstruct_tree();
-        self.ggm_tree[self.choice_pos] = FE::zero();
+    pub fn compute(&mut self, ggm_tree_mem: &mut [FieldElement<F>], delta2: FieldElement<F>) {
+        let prp = TwoKeyPRP::with_expansion(self.expansion);
+        let nodes = self.reconstruct_tree(&prp);
+        prp.leaves_to_field(&mut self.ggm_tree, &nodes);
+        self.ggm_tree[self.choice_pos] = FieldElement::zero();
 
-        let mut nodes_sum = FE::zero();
+        let mut nodes_sum = FieldElement::zero();
         for i in 0..self.leave_n {
             nodes_sum += self.ggm_tree[i];
         }
@@ -75,96 +94,76 @@ impl SpfssRecverFp {
         ggm_tree_mem.copy_from_slice(&self.ggm_tree);
     }
 
-    /// Reconstruct the GGM tree.
-    fn reconstruct_tree(&mut self) {
-        let mut to_fill_idx = 0;
-        let mut prp = TwoKeyPRP::new();
-
-        for i in 1..self.depth {
-            to_fill_idx *= 2;
-            self.ggm_tree[to_fill_idx] = FE::zero();
-            self.ggm_tree[to_fill_idx + 1] = FE::zero();
-
-            if !self.b[i - 1] {
-                self.layer_recover(i, 0, to_fill_idx, self.m[i - 1], &mut prp);
-                to_fill_idx += 1;
-            } else {
-                self.layer_recover(i, 1, to_fill_idx + 1, self.m[i - 1], &mut prp);
+    /// Recover every node except the punctured path, level by level.
+    fn reconstruct_tree(&self, prp: &TwoKeyPRP) -> Vec<Block> {
+        let mut nodes = vec![[0u8; 16]; self.leave_n];
+        let mut pos = 0;
+
+        for h in 0..self.depth - 1 {
+            let sz = 2 << h;
+            // Walk backwards so parents are not overwritten, the children of the punctured node are unknown
+            if h > 0 {
+                for i in (0..sz / 2).step_by(2).rev() {
+                    let parents = [nodes[i], nodes[i + 1]];
+                    prp.node_expand_2to4(&mut nodes[2*i..2*i+4], &parents);
+                }
+            }
+            nodes[2 * pos] = [0u8; 16];
+            nodes[2 * pos + 1] = [0u8; 16];
+
+            // b[h] = false means we got the sum of the left children
+            let lr = if self.b[h] { 1 } else { 0 };
+            let to_fill_idx = 2 * pos + lr;
+            let mut sum = self.m[h];
+            for i in (lr..sz).step_by(2) {
+                xor_block_inplace(&mut sum, &nodes[i]);
             }
+            nodes[to_fill_idx] = sum;
+            pos = 2 * pos + 1 - lr;
         }
+        nodes
     }
 
-    /// Recover a single layer of the GGM tree.
-    fn layer_recover(
-        &mut self,
-        depth: usize,
-        lr: usize,
-        to_fill_idx: usize,
-        sum: FE,
-        prp: &mut TwoKeyPRP,
-    ) {
-        let item_n = 1 << depth;
-        let mut nodes_sum = FE::zero();
-        let mut lr_start = 0;
-        if lr != 0 {
-            lr_start = 1;
-        }
-
-        for i in (lr_start..item_n).step_by(2) {
-            nodes_sum += self.ggm_tree[i];
-        }
-
-        self.ggm_tree[to_fill_idx] = sum - nodes_sum;
-
-        if depth == self.depth - 1 {
-            return;
-        }
-
-        let tmp = self.ggm_tree.clone();
-
-        for i in (0..item_n).step_by(2).rev() {
-            prp.node_expand_2to4(
-                &mut self.ggm_tree[i * 2..i * 2 + 4],
-                &tmp[i..i + 2],
-            );
-        }
+    /// Consistency check for the protocol.
+    pub fn consistency_check<IO: CommunicationChannel>(&mut self, io: &mut IO, z: FieldElement<F>, beta: FieldElement<F>) -> Result<()> {
+        block_on(self.consistency_check_async(&mut SyncChannel(io), z, beta))
     }
 
-    /// Consistency check for the protocol.
-    pub fn consistency_check<IO: CommunicationChannel>(&mut self, io: &mut IO, z: FE, beta: FE) {
+    /// Async variant of `consistency_check`.
+    pub async fn consistency_check_async<IO: AsyncCommunicationChannel>(&mut self, io: &mut IO, z: FieldElement<F>, beta: FieldElement<F>) -> Result<()> {
         // z = y + delta * beta
 
         let hash = Hash::new();
-        let digest = hash.hash_32byte_block(&self.share.to_bytes_le());
-        let uni_hash_seed = FE::from_bytes_le(&digest).unwrap();
-        let mut chi = vec![FE::zero(); self.leave_n];
+        let digest = hash.hash_32byte_block(&F::to_block(&self.share));
+        let uni_hash_seed = F::from_random_bytes(&digest);
+        let mut chi = vec![FieldElement::zero(); self.leave_n];
         uni_hash_coeff_gen(&mut chi, uni_hash_seed, self.leave_n);
 
         // Compute x_star
         let x_star = chi[self.choice_pos] * beta - beta;
         // Send x_star
-        io.send_stark252(&[x_star]).unwrap();
+        io.send_field_elements(&[x_star]).await?;
+        io.flush().await?;
 
         // receive delta for tes        // Compute W
         let w = vector_inner_product(&chi, &self.ggm_tree) - z;
 
         // Receive V and verify
-        let v = io.receive_stark252(1).expect("Failed to receive V")[0];
+        let v = io.receive_field_elements::<F>(1).await?[0];
 
         if w != v {
-            panic!("SPFSS consistency check failed!");
-        } else {
-            println!("SPFSS successful!");
+            return Err(VoleError::ConsistencyCheck(CheckFailure::Spfss));
         }
+        Ok(())
     }
 
-    pub fn consistency_check_msg_gen<IO: CommunicationChannel>(&mut self, chi_alpha: &mut FE, w: &mut FE, io: &mut IO, seed: FE) {
+    pub fn consistency_check_msg_gen(&mut self, chi_alpha: &mut FieldElement<F>, w: &mut FieldElement<F>, seed: FieldElement<F>) {
         // println!("Seed: {:?}", seed);
-        let mut chi = vec![FE::zero(); self.leave_n];
+        let mut chi = vec![FieldElement::zero(); self.leave_n];
 
         let hash = Hash::new();
-        let digest = hash.hash_32byte_block(&seed.to_bytes_le());
-        let uni_hash_seed = FE::from_bytes_le(&digest).unwrap();
+        let digest = hash.hash_32byte_block(&F::to_block(&seed));
+        let uni_hash_seed = F::from_random_bytes(&digest);
 
         uni_hash_coeff_gen(&mut chi, uni_hash_seed, self.leave_n);
 
@@ -174,13 +173,11 @@ impl SpfssRecverFp {
 }
 
 /// Compute modular inner product.
-fn vector_inner_product(vec1: &[FE], vec2: &[FE]) -> FE {
-    vec1.iter()
-        .zip(vec2)
-        .fold(FE::zero(), |acc, (v1, v2)| acc + (*v1 * *v2))
+fn vector_inner_product<F: VoleField>(vec1: &[FieldElement<F>], vec2: &[FieldElement<F>]) -> FieldElement<F> {
+    F::inner_product(vec1, vec2)
 }
 
-pub fn uni_hash_coeff_gen(coeff: &mut [FE], seed: FE, sz: usize) {
+pub fn uni_hash_coeff_gen<F: VoleField>(coeff: &mut [FieldElement<F>], seed: FieldElement<F>, sz: usize) {
     if sz == 0 {
         return;
     }
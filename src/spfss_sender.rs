@@ -1,114 +1,137 @@
-use crate::two_key_prp::TwoKeyPRP;
+use crate::two_key_prp::{TwoKeyPRP, GgmExpansion};
 use crate::prg::PRG;
 use crate::comm_channel::CommunicationChannel;
+use crate::async_comm_channel::{AsyncCommunicationChannel, SyncChannel, block_on};
 use crate::preot::OTPre;
-use crate::hash::Hash;
-use lambdaworks_math::field::fields::fft_friendly::stark_252_prime_field::Stark252PrimeField;
+use crate::error::Result;
+use crate::hash::{Hash, Block};
+use crate::spcot_sender::{block_to_32byte, xor_block_inplace};
+use crate::field::{VoleField, DefaultField};
 use lambdaworks_math::field::element::FieldElement;
-use lambdaworks_math::traits::ByteConversion;
 use std::convert::TryInto;
 
-pub type F = Stark252PrimeField;
-pub type FE = FieldElement<F>;
-
-pub struct SpfssSenderFp {
-    seed: FE,
-    delta: FE,
-    secret_sum: FE,
-    ggm_tree: Vec<FE>,
-    m0: Vec<FE>,
-    m1: Vec<FE>,
+/// The GGM tree is made of 128-bit blocks whatever `F` is, only its leaves are mapped into `F`.
+pub struct SpfssSenderFp<F: VoleField = DefaultField> {
+    seed: Block,
+    delta: FieldElement<F>,
+    secret_sum: FieldElement<F>,
+    ggm_tree: Vec<FieldElement<F>>,
+    m0: Vec<Block>,
+    m1: Vec<Block>,
     depth: usize,
     leave_n: usize,
-    prg: PRG,
+    expansion: GgmExpansion,
 }
 
-impl SpfssSenderFp {
+impl<F: VoleField> SpfssSenderFp<F> {
     /// Create a new SpfssSenderFp instance.
     pub fn new(depth: usize) -> Self {
-        let leave_n = 1 << (depth - 1);
         let mut prg = PRG::new(None, 0);
-        let mut seed = [FE::zero(); 1];
-        prg.random_stark252_elements(&mut seed);
+        let mut seed = [[0u8; 16]; 1];
+        prg.random_block(&mut seed);
+        Self::with_seed(depth, seed[0])
+    }
+
+    /// Create a new SpfssSenderFp instance whose GGM tree grows from `seed`.
+    pub fn with_seed(depth: usize, seed: Block) -> Self {
+        let leave_n = 1 << (depth - 1);
         Self {
-            seed: seed[0],
-            delta: FE::zero(),
-            secret_sum: FE::zero(),
-            ggm_tree: vec![FE::zero(); leave_n],
-            m0: vec![FE::zero(); depth - 1],
-            m1: vec![FE::zero(); depth - 1],
+            seed,
+            delta: FieldElement::zero(),
+            secret_sum: FieldElement::zero(),
+            ggm_tree: vec![FieldElement::zero(); leave_n],
+            m0: vec![[0u8; 16]; depth - 1],
+            m1: vec![[0u8; 16]; depth - 1],
             depth,
             leave_n,
-            prg,
+            expansion: GgmExpansion::default(),
         }
     }
 
+    /// How the GGM tree is expanded, the receiver must use the same.
+    pub fn set_expansion(&mut self, expansion: GgmExpansion) {
+        self.expansion = expansion;
+    }
+
     /// Sender GGM tree infos thru OT
-    pub fn compute(&mut self, ggm_tree_mem: &mut [FE], secret: FE, gamma: FE) {
+    pub fn compute(&mut self, ggm_tree_mem: &mut [FieldElement<F>], secret: FieldElement<F>, gamma: FieldElement<F>) {
         self.delta = secret.clone();
         self.ggm_tree_gen(ggm_tree_mem, secret, gamma);
     }
 
     /// Send OT messages and secret sum.
-    pub fn send<IO: CommunicationChannel>(&mut self, io: &mut IO, ot: &mut OTPre, s: usize) {
+    pub fn send<IO: CommunicationChannel>(&mut self, io: &mut IO, ot: &mut OTPre, s: usize) -> Result<()> {
+        block_on(self.send_async(&mut SyncChannel(io), ot, s))
+    }
+
+    /// Async variant of `send`.
+    pub async fn send_async<IO: AsyncCommunicationChannel>(&mut self, io: &mut IO, ot: &mut OTPre, s: usize) -> Result<()> {
         let ot_msg_0 = self.m0
             .iter()
-            .map(|x| x.to_bytes_le())
+            .map(|x| block_to_32byte(x))
             .collect::<Vec<[u8; 32]>>();
         let ot_msg_1 = self.m1
             .iter()
-            .map(|x| x.to_bytes_le())
+            .map(|x| block_to_32byte(x))
             .collect::<Vec<[u8; 32]>>();
 
-        ot.send(io, &ot_msg_0, &ot_msg_1, self.depth - 1, s);
-        io.send_stark252(&[self.secret_sum]).expect("Failed to send secret sum.");
+        ot.send_async(io, &ot_msg_0, &ot_msg_1, self.depth - 1, s).await?;
+        io.send_field_elements(&[self.secret_sum]).await
     }
 
     /// Generate the GGM tree from the top.
-    // Generate the GGM tree to ggm_tree_mem first, then copy it into self.ggm_tree for later check
-    fn ggm_tree_gen(&mut self, ggm_tree_mem: &mut [FE], secret: FE, gamma: FE) {
-        let mut prp = TwoKeyPRP::new();
+    // The nodes are expanded in place level by level, then the leaves go to self.ggm_tree for
+    // the later check and to ggm_tree_mem
+    fn ggm_tree_gen(&mut self, ggm_tree_mem: &mut [FieldElement<F>], secret: FieldElement<F>, gamma: FieldElement<F>) {
+        let prp = TwoKeyPRP::with_expansion(self.expansion);
+        let mut nodes = vec![[0u8; 16]; self.leave_n];
         // Generate the first layer of the GGM tree
-        prp.node_expand_1to2(&mut ggm_tree_mem[0..2], &self.seed);
-        self.m0[0] = ggm_tree_mem[0];
-        self.m1[0] = ggm_tree_mem[1];
+        prp.node_expand_1to2(&mut nodes[0..2], &self.seed);
+        self.m0[0] = nodes[0];
+        self.m1[0] = nodes[1];
 
-        // Process all layers
+        // Process all layers, walking backwards so parents are not overwritten
         for h in 1..self.depth - 1 {
-            self.m0[h] = FE::zero();
-            self.m1[h] = FE::zero();
             let sz = 1 << h;
-            for i in (0..sz).step_by(2) {
-                prp.node_expand_2to4(
-                    &mut self.ggm_tree[2*i..2*i+4], 
-                    &ggm_tree_mem[i..i+2]
-                );
-                self.m0[h] += self.ggm_tree[i * 2] + self.ggm_tree[i * 2 + 2];
-                self.m1[h] += self.ggm_tree[i * 2 + 1] + self.ggm_tree[i * 2 + 3];
+            for i in (0..sz).step_by(2).rev() {
+                let parents = [nodes[i], nodes[i + 1]];
+                prp.node_expand_2to4(&mut nodes[2*i..2*i+4], &parents);
+            }
+            self.m0[h] = [0u8; 16];
+            self.m1[h] = [0u8; 16];
+            for i in (0..2 * sz).step_by(2) {
+                xor_block_inplace(&mut self.m0[h], &nodes[i]);
+                xor_block_inplace(&mut self.m1[h], &nodes[i + 1]);
             }
-            ggm_tree_mem[..2*sz].copy_from_slice(&self.ggm_tree[..2*sz]);
         }
+        prp.leaves_to_field(&mut self.ggm_tree, &nodes);
+        ggm_tree_mem.copy_from_slice(&self.ggm_tree);
 
         // Compute the secret sum
-        self.secret_sum = FE::zero();
-        for node in ggm_tree_mem.iter().take(self.leave_n) {
+        self.secret_sum = FieldElement::zero();
+        for node in self.ggm_tree.iter() {
             self.secret_sum = self.secret_sum - *node;
         }
         self.secret_sum += gamma;
     }
 
     /// Consistency check: Protocol PI_spsVOLE
-    pub fn consistency_check<IO: CommunicationChannel>(&mut self, io: &mut IO, y: FE) {
+    pub fn consistency_check<IO: CommunicationChannel>(&mut self, io: &mut IO, y: FieldElement<F>) -> Result<()> {
+        block_on(self.consistency_check_async(&mut SyncChannel(io), y))
+    }
+
+    /// Async variant of `consistency_check`.
+    pub async fn consistency_check_async<IO: AsyncCommunicationChannel>(&mut self, io: &mut IO, y: FieldElement<F>) -> Result<()> {
         // z = y + delta * beta
 
         let hash = Hash::new();
-        let digest = hash.hash_32byte_block(&self.secret_sum.to_bytes_le());
-        let uni_hash_seed = FE::from_bytes_le(&digest).unwrap();
-        let mut chi = vec![FE::zero(); self.leave_n];
+        let digest = hash.hash_32byte_block(&F::to_block(&self.secret_sum));
+        let uni_hash_seed = F::from_random_bytes(&digest);
+        let mut chi = vec![FieldElement::zero(); self.leave_n];
         uni_hash_coeff_gen(&mut chi, uni_hash_seed, self.leave_n);
 
         // Receive x_star
-        let x_star = io.receive_stark252(1).expect("Failed to receive x_star")[0];
+        let x_star = io.receive_field_elements::<F>(1).await?[0];
 
         // Compute y_star
         let y_star = y - x_star * self.delta;
@@ -116,18 +139,17 @@ impl SpfssSenderFp {
         // Compute V
         let v = vector_inner_product(&chi, &self.ggm_tree) - y_star;
 
-        println!("v: {:?}", v);
-
         // Send V
-        io.send_stark252(&[v]).expect("Failed to send V");
+        io.send_field_elements(&[v]).await?;
+        io.flush().await
     }
 
-    pub fn consistency_check_msg_gen<IO: CommunicationChannel>(&mut self, v: &mut FE, io: &mut IO, seed: FE) {
-        let mut chi = vec![FE::zero(); self.leave_n];
+    pub fn consistency_check_msg_gen(&mut self, v: &mut FieldElement<F>, seed: FieldElement<F>) {
+        let mut chi = vec![FieldElement::zero(); self.leave_n];
 
         let hash = Hash::new();
-        let digest = hash.hash_32byte_block(&seed.to_bytes_le());
-        let uni_hash_seed = FE::from_bytes_le(&digest).unwrap();
+        let digest = hash.hash_32byte_block(&F::to_block(&seed));
+        let uni_hash_seed = F::from_random_bytes(&digest);
 
         uni_hash_coeff_gen(&mut chi, uni_hash_seed, self.leave_n);
 
@@ -136,13 +158,11 @@ impl SpfssSenderFp {
 }
 
 /// Compute modular inner product.
-fn vector_inner_product(vec1: &[FE], vec2: &[FE]) -> FE {
-    vec1.iter()
-        .zip(vec2)
-        .fold(FE::zero(), |acc, (v1, v2)| acc + (*v1 * *v2))
+fn vector_inner_product<F: VoleField>(vec1: &[FieldElement<F>], vec2: &[FieldElement<F>]) -> FieldElement<F> {
+    F::inner_product(vec1, vec2)
 }
 
-pub fn uni_hash_coeff_gen(coeff: &mut [FE], seed: FE, sz: usize) {
+pub fn uni_hash_coeff_gen<F: VoleField>(coeff: &mut [FieldElement<F>], seed: FieldElement<F>, sz: usize) {
     if sz == 0 {
         return;
     }
@@ -0,0 +1,290 @@
+use crate::comm_channel::{CommunicationChannel, FE};
+use crate::field::VoleField;
+use crate::framing::{MessageKind, MAX_FRAME_LEN, encode_bits, decode_bits, encode_field_elements, decode_field_elements, decode_point, encode_blocks, decode_blocks};
+use crate::error::{Result, VoleError};
+
+use p256::EncodedPoint;
+use lambdaworks_math::field::element::FieldElement;
+use std::fs::File;
+use std::io::{BufReader, BufWriter, ErrorKind, Read, Write};
+use std::path::Path;
+
+/// One message of a transcript, as seen by the party that recorded it.
+///
+/// On disk: direction byte (0 sent, 1 received), kind byte, u64 payload length, payload.
+/// Bits are stored as a u64 count followed by the packed bits, everything else as raw bytes.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct TranscriptEntry {
+    pub sent: bool,
+    pub kind: MessageKind,
+    pub payload: Vec<u8>,
+}
+
+impl TranscriptEntry {
+    pub fn write_to<W: Write>(&self, writer: &mut W) -> Result<()> {
+        writer.write_all(&[!self.sent as u8, self.kind as u8])?;
+        writer.write_all(&(self.payload.len() as u64).to_le_bytes())?;
+        writer.write_all(&self.payload)?;
+        Ok(())
+    }
+
+    /// Reads the next entry, `None` at the end of the transcript.
+    ///
+    /// A truncated entry, or one longer than `MAX_FRAME_LEN`, is a `MalformedMessage`.
+    pub fn read_from<R: Read>(reader: &mut R) -> Result<Option<Self>> {
+        let mut header = [0u8; 10];
+        loop {
+            match reader.read(&mut header[..1]) {
+                Ok(0) => return Ok(None),
+                Ok(_) => break,
+                Err(e) if e.kind() == ErrorKind::Interrupted => {}
+                Err(e) => return Err(e.into()),
+            }
+        }
+        read_entry_part(reader, &mut header[1..])?;
+        let mut len_buf = [0u8; 8];
+        len_buf.copy_from_slice(&header[2..]);
+        let len = u64::from_le_bytes(len_buf);
+        if len > MAX_FRAME_LEN as u64 {
+            return Err(VoleError::MalformedMessage(format!(
+                "Transcript entry of {} bytes, more than the {} allowed", len, MAX_FRAME_LEN
+            )));
+        }
+        let mut payload = vec![0u8; len as usize];
+        read_entry_part(reader, &mut payload)?;
+        let kind = MessageKind::from_tag(header[1])
+            .ok_or_else(|| VoleError::MalformedMessage(format!("Unknown transcript tag {}", header[1])))?;
+        Ok(Some(TranscriptEntry { sent: header[0] == 0, kind, payload }))
+    }
+}
+
+// The rest of an entry that has started, running out is a truncated transcript
+fn read_entry_part<R: Read>(reader: &mut R, buf: &mut [u8]) -> Result<()> {
+    reader.read_exact(buf).map_err(|e| match e.kind() {
+        ErrorKind::UnexpectedEof => VoleError::MalformedMessage("Truncated transcript entry".to_string()),
+        _ => e.into(),
+    })
+}
+
+/// Passes everything through to `inner` and appends each message, sent or received, to a transcript.
+///
+/// Record with `prg::set_thread_seed` (`replay` feature) set on the party's thread, and a
+/// `ReplayChannel` over the transcript reruns that party alone, bit for bit.
+pub struct RecordingChannel<C: CommunicationChannel, W: Write> {
+    inner: C,
+    writer: W,
+}
+
+impl<C: CommunicationChannel> RecordingChannel<C, BufWriter<File>> {
+    /// Records into a new file at `path`
+    pub fn create<P: AsRef<Path>>(inner: C, path: P) -> Result<Self> {
+        Ok(Self::new(inner, BufWriter::new(File::create(path)?)))
+    }
+}
+
+impl<C: CommunicationChannel, W: Write> RecordingChannel<C, W> {
+    pub fn new(inner: C, writer: W) -> Self {
+        Self { inner, writer }
+    }
+
+    /// Flushes the transcript and gives back both halves
+    pub fn into_parts(mut self) -> Result<(C, W)> {
+        self.writer.flush()?;
+        Ok((self.inner, self.writer))
+    }
+
+    fn record(&mut self, sent: bool, kind: MessageKind, payload: Vec<u8>) -> Result<()> {
+        TranscriptEntry { sent, kind, payload }.write_to(&mut self.writer)
+    }
+}
+
+impl<C: CommunicationChannel, W: Write> CommunicationChannel for RecordingChannel<C, W> {
+    fn send_32byte_block(&mut self, data: &[[u8; 32]]) -> Result<()> {
+        self.record(true, MessageKind::Block32, encode_blocks(data))?;
+        self.inner.send_32byte_block(data)
+    }
+
+    fn receive_32byte_block(&mut self) -> Result<Vec<[u8; 32]>> {
+        let data = self.inner.receive_32byte_block()?;
+        self.record(false, MessageKind::Block32, encode_blocks(&data))?;
+        Ok(data)
+    }
+
+    fn send_bits(&mut self, bits: &[bool]) -> Result<()> {
+        self.record(true, MessageKind::Bits, encode_bits(bits))?;
+        self.inner.send_bits(bits)
+    }
+
+    fn receive_bits(&mut self) -> Result<Vec<bool>> {
+        let bits = self.inner.receive_bits()?;
+        self.record(false, MessageKind::Bits, encode_bits(&bits))?;
+        Ok(bits)
+    }
+
+    fn send_stark252(&mut self, elements: &[FE]) -> Result<()> {
+        self.send_field_elements(elements)
+    }
+
+    fn receive_stark252(&mut self, count: usize) -> Result<Vec<FE>> {
+        self.receive_field_elements(count)
+    }
+
+    fn send_field_elements<F: VoleField>(&mut self, elements: &[FieldElement<F>]) -> Result<()> {
+        self.record(true, MessageKind::FieldElements, encode_field_elements(elements))?;
+        self.inner.send_field_elements(elements)
+    }
+
+    fn receive_field_elements<F: VoleField>(&mut self, count: usize) -> Result<Vec<FieldElement<F>>> {
+        let elements = self.inner.receive_field_elements::<F>(count)?;
+        self.record(false, MessageKind::FieldElements, encode_field_elements(&elements))?;
+        Ok(elements)
+    }
+
+    fn send_point(&mut self, point: &EncodedPoint) -> Result<()> {
+        self.record(true, MessageKind::Point, point.as_bytes().to_vec())?;
+        self.inner.send_point(point)
+    }
+
+    fn receive_point(&mut self) -> Result<EncodedPoint> {
+        let point = self.inner.receive_point()?;
+        self.record(false, MessageKind::Point, point.as_bytes().to_vec())?;
+        Ok(point)
+    }
+
+    fn send_data(&mut self, data: &[[u8; 16]]) -> Result<()> {
+        self.record(true, MessageKind::Data, encode_blocks(data))?;
+        self.inner.send_data(data)
+    }
+
+    fn receive_data(&mut self) -> Result<Vec<[u8; 16]>> {
+        let data = self.inner.receive_data()?;
+        self.record(false, MessageKind::Data, encode_blocks(&data))?;
+        Ok(data)
+    }
+
+    fn flush(&mut self) -> Result<()> {
+        self.writer.flush()?;
+        self.inner.flush()
+    }
+
+    fn stats(&self) -> Option<crate::counting_channel::CommStats> {
+        self.inner.stats()
+    }
+}
+
+/// Plays the peer's side of a recorded transcript back to one party, no peer needed.
+///
+/// Receives return the recorded messages in order. Sends are checked against the
+/// recording unless `ignore_sends` is set, so the first place where the rerun departs
+/// from the recorded run comes back as a `Desync` error. They only match
+/// when the rerun uses the same `prg::set_thread_seed` as the recorded one.
+pub struct ReplayChannel<R: Read> {
+    reader: R,
+    position: usize,
+    check_sends: bool,
+}
+
+impl ReplayChannel<BufReader<File>> {
+    /// Replays the transcript in the file at `path`
+    pub fn open<P: AsRef<Path>>(path: P) -> Result<Self> {
+        Ok(Self::new(BufReader::new(File::open(path)?)))
+    }
+}
+
+impl<R: Read> ReplayChannel<R> {
+    pub fn new(reader: R) -> Self {
+        Self { reader, position: 0, check_sends: true }
+    }
+
+    /// Accepts any send, for reruns with different randomness
+    pub fn ignore_sends(mut self) -> Self {
+        self.check_sends = false;
+        self
+    }
+
+    /// Number of transcript entries played so far
+    pub fn position(&self) -> usize {
+        self.position
+    }
+
+    fn next_entry(&mut self, sent: bool, kind: MessageKind) -> Result<Vec<u8>> {
+        let entry = TranscriptEntry::read_from(&mut self.reader)?.ok_or_else(|| {
+            VoleError::Io(std::io::Error::new(ErrorKind::UnexpectedEof, "End of the transcript"))
+        })?;
+        self.position += 1;
+        if entry.sent != sent || entry.kind != kind {
+            return Err(VoleError::Desync(format!(
+                "Replay diverged at message {}: expected {} {:?}, the transcript has {} {:?}",
+                self.position - 1,
+                if sent { "sent" } else { "received" }, kind,
+                if entry.sent { "sent" } else { "received" }, entry.kind
+            )));
+        }
+        Ok(entry.payload)
+    }
+
+    fn replay_send(&mut self, kind: MessageKind, payload: Vec<u8>) -> Result<()> {
+        let recorded = self.next_entry(true, kind)?;
+        if self.check_sends && recorded != payload {
+            return Err(VoleError::Desync(format!(
+                "Replay diverged at message {}: sent {:?} differs from the transcript",
+                self.position - 1, kind
+            )));
+        }
+        Ok(())
+    }
+}
+
+impl<R: Read> CommunicationChannel for ReplayChannel<R> {
+    fn send_32byte_block(&mut self, data: &[[u8; 32]]) -> Result<()> {
+        self.replay_send(MessageKind::Block32, encode_blocks(data))
+    }
+
+    fn receive_32byte_block(&mut self) -> Result<Vec<[u8; 32]>> {
+        decode_blocks(&self.next_entry(false, MessageKind::Block32)?)
+    }
+
+    fn send_bits(&mut self, bits: &[bool]) -> Result<()> {
+        self.replay_send(MessageKind::Bits, encode_bits(bits))
+    }
+
+    fn receive_bits(&mut self) -> Result<Vec<bool>> {
+        decode_bits(&self.next_entry(false, MessageKind::Bits)?)
+    }
+
+    fn send_stark252(&mut self, elements: &[FE]) -> Result<()> {
+        self.send_field_elements(elements)
+    }
+
+    fn receive_stark252(&mut self, count: usize) -> Result<Vec<FE>> {
+        self.receive_field_elements(count)
+    }
+
+    fn send_field_elements<F: VoleField>(&mut self, elements: &[FieldElement<F>]) -> Result<()> {
+        self.replay_send(MessageKind::FieldElements, encode_field_elements(elements))
+    }
+
+    fn receive_field_elements<F: VoleField>(&mut self, count: usize) -> Result<Vec<FieldElement<F>>> {
+        decode_field_elements(&self.next_entry(false, MessageKind::FieldElements)?, count)
+    }
+
+    fn send_point(&mut self, point: &EncodedPoint) -> Result<()> {
+        self.replay_send(MessageKind::Point, point.as_bytes().to_vec())
+    }
+
+    fn receive_point(&mut self) -> Result<EncodedPoint> {
+        decode_point(&self.next_entry(false, MessageKind::Point)?)
+    }
+
+    fn send_data(&mut self, data: &[[u8; 16]]) -> Result<()> {
+        self.replay_send(MessageKind::Data, encode_blocks(data))
+    }
+
+    fn receive_data(&mut self) -> Result<Vec<[u8; 16]>> {
+        decode_blocks(&self.next_entry(false, MessageKind::Data)?)
+    }
+
+    fn flush(&mut self) -> Result<()> {
+        Ok(())
+    }
+}
@@ -0,0 +1,111 @@
+#[cfg(target_arch = "x86")]
+use std::arch::x86::*;
+#[cfg(target_arch = "x86_64")]
+use std::arch::x86_64::*;
+
+/// Writes the transpose of `input`, `rows` x `cols` bits, to `out`, `cols` x `rows` bits.
+///
+/// Matrices are stored row after row, bit `j` of a row in byte `j / 8` at bit `j % 8`.
+/// Both dimensions must be multiples of 8, and `out` is overwritten. AVX2 or SSE2 are
+/// used when the CPU has them, all paths give the same output.
+pub fn transpose(out: &mut [u8], input: &[u8], rows: usize, cols: usize) {
+    check_dimensions(out, input, rows, cols);
+
+    #[cfg(any(target_arch = "x86", target_arch = "x86_64"))]
+    {
+        if rows % 32 == 0 && is_x86_feature_detected!("avx2") {
+            // SAFETY: AVX2 is available and the dimensions were checked
+            unsafe { transpose_avx2(out, input, rows, cols) };
+            return;
+        }
+        if rows % 16 == 0 && is_x86_feature_detected!("sse2") {
+            // SAFETY: SSE2 is available and the dimensions were checked
+            unsafe { transpose_sse2(out, input, rows, cols) };
+            return;
+        }
+    }
+    transpose_portable(out, input, rows, cols);
+}
+
+/// `transpose` without SIMD, 8 x 8 bit blocks at a time in a u64.
+pub fn transpose_portable(out: &mut [u8], input: &[u8], rows: usize, cols: usize) {
+    check_dimensions(out, input, rows, cols);
+    let (row_bytes, col_bytes) = (cols / 8, rows / 8);
+
+    for r in (0..rows).step_by(8) {
+        for c in 0..row_bytes {
+            // Byte i of x is row r + i, so bit 8i + j is entry (r + i, 8c + j)
+            let mut x = 0u64;
+            for i in 0..8 {
+                x |= (input[(r + i) * row_bytes + c] as u64) << (8 * i);
+            }
+            let x = transpose8x8(x);
+            for j in 0..8 {
+                out[(8 * c + j) * col_bytes + r / 8] = (x >> (8 * j)) as u8;
+            }
+        }
+    }
+}
+
+fn check_dimensions(out: &[u8], input: &[u8], rows: usize, cols: usize) {
+    assert!(rows % 8 == 0 && cols % 8 == 0, "Bit matrix dimensions must be multiples of 8");
+    assert_eq!(input.len(), rows * cols / 8, "Input is not a {} x {} bit matrix", rows, cols);
+    assert_eq!(out.len(), rows * cols / 8, "Output is not a {} x {} bit matrix", cols, rows);
+}
+
+// Swaps bit 8i + j with bit 8j + i
+fn transpose8x8(mut x: u64) -> u64 {
+    let t = (x ^ (x >> 7)) & 0x00AA_00AA_00AA_00AA;
+    x ^= t ^ (t << 7);
+    let t = (x ^ (x >> 14)) & 0x0000_CCCC_0000_CCCC;
+    x ^= t ^ (t << 14);
+    let t = (x ^ (x >> 28)) & 0x0000_0000_F0F0_F0F0;
+    x ^= t ^ (t << 28);
+    x
+}
+
+// The top bit of byte i goes to bit i of the movemask, so gathering byte c of 16 rows
+// and shifting left between movemasks yields bits 8c + 7 down to 8c of those rows.
+#[cfg(any(target_arch = "x86", target_arch = "x86_64"))]
+#[target_feature(enable = "sse2")]
+unsafe fn transpose_sse2(out: &mut [u8], input: &[u8], rows: usize, cols: usize) {
+    let (row_bytes, col_bytes) = (cols / 8, rows / 8);
+    let mut gathered = [0u8; 16];
+
+    for r in (0..rows).step_by(16) {
+        for c in 0..row_bytes {
+            for (i, byte) in gathered.iter_mut().enumerate() {
+                *byte = input[(r + i) * row_bytes + c];
+            }
+            let mut v = _mm_loadu_si128(gathered.as_ptr() as *const __m128i);
+            for j in (0..8).rev() {
+                let bits = (_mm_movemask_epi8(v) as u16).to_le_bytes();
+                let at = (8 * c + j) * col_bytes + r / 8;
+                out[at..at + 2].copy_from_slice(&bits);
+                v = _mm_slli_epi64(v, 1);
+            }
+        }
+    }
+}
+
+#[cfg(any(target_arch = "x86", target_arch = "x86_64"))]
+#[target_feature(enable = "avx2")]
+unsafe fn transpose_avx2(out: &mut [u8], input: &[u8], rows: usize, cols: usize) {
+    let (row_bytes, col_bytes) = (cols / 8, rows / 8);
+    let mut gathered = [0u8; 32];
+
+    for r in (0..rows).step_by(32) {
+        for c in 0..row_bytes {
+            for (i, byte) in gathered.iter_mut().enumerate() {
+                *byte = input[(r + i) * row_bytes + c];
+            }
+            let mut v = _mm256_loadu_si256(gathered.as_ptr() as *const __m256i);
+            for j in (0..8).rev() {
+                let bits = (_mm256_movemask_epi8(v) as u32).to_le_bytes();
+                let at = (8 * c + j) * col_bytes + r / 8;
+                out[at..at + 4].copy_from_slice(&bits);
+                v = _mm256_slli_epi64(v, 1);
+            }
+        }
+    }
+}
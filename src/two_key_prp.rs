@@ -1,81 +1,150 @@
-use aes::Aes256;
+use aes::Aes128;
 use aes::cipher::{BlockEncrypt, KeyInit, generic_array::GenericArray};
-use lambdaworks_math::field::fields::fft_friendly::stark_252_prime_field::Stark252PrimeField;
+use aes::cipher::consts::U16;
 use lambdaworks_math::field::element::FieldElement;
-use lambdaworks_math::traits::ByteConversion;
-
-pub type F = Stark252PrimeField;
-pub type FE = FieldElement<F>;
+use crate::field::VoleField;
+use crate::hash::Block;
+
+type AesBlock = GenericArray<u8, U16>;
+
+// Public keys of the fixed-key permutations: left child, right child, leaves to field elements
+const FIXED_KEYS: [[u8; 16]; 3] = [[0u8; 16], [1u8; 16], [2u8; 16]];
+// Most parents expanded by one call
+const MAX_PARENTS: usize = 4;
+// Leaves mapped to field elements by one call
+const MAX_LEAVES: usize = 8;
+
+/// How a GGM tree node is expanded into its two children.
+///
+/// Both ends of a tree must use the same one, the trees differ otherwise.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum GgmExpansion {
+    /// AES-128 keyed with the parent, one key schedule per node.
+    ReKeyed,
+    /// Fixed-key AES-128 as a correlation-robust hash of the parent, `π_b(s) ⊕ s` for
+    /// child `b`. The key schedules are done once, when the PRP is created.
+    #[default]
+    FixedKey,
+}
 
+/// GGM tree expansion over 128-bit blocks.
+///
+/// The nodes stay blocks whatever the field, only the leaves are mapped into it,
+/// so a small field does not make the hidden nodes any easier to guess.
 pub struct TwoKeyPRP {
+    expansion: GgmExpansion,
+    fixed: [Aes128; 3],
 }
 
 impl TwoKeyPRP {
+    /// Expands nodes with `GgmExpansion::default()`, the fixed-key one
     pub fn new() -> Self {
-        TwoKeyPRP {}
+        Self::with_expansion(GgmExpansion::default())
     }
 
-    /// Expand a single parent field element into two child field elements.
-    pub fn node_expand_1to2(&self, children: &mut [FE], parent: &FE) {
-        assert_eq!(children.len(), 2, "Node expand from 1 to 2 expects children to be an array of size 2.");
-
-        let parent_bytes = parent.to_bytes_le();
-        let aes_key = Aes256::new(GenericArray::from_slice(&parent_bytes));
+    pub fn with_expansion(expansion: GgmExpansion) -> Self {
+        TwoKeyPRP {
+            expansion,
+            fixed: FIXED_KEYS.map(|key| Aes128::new(GenericArray::from_slice(&key))),
+        }
+    }
 
-        // Prepare a buffer for 4 blocks (16 bytes each)
-        let mut expanded_parent: [_; 4] = core::array::from_fn(|i| GenericArray::clone_from_slice(&[i as u8; 16]));
+    /// Expand a single parent node into two child nodes.
+    pub fn node_expand_1to2(&self, children: &mut [Block], parent: &Block) {
+        assert_eq!(children.len(), 2, "Node expand from 1 to 2 expects children to be an array of size 2.");
+        if self.expansion == GgmExpansion::FixedKey {
+            return self.fixed_key_expand(children, std::slice::from_ref(parent));
+        }
+        self.block_expand_1to2(children, parent);
+    }
 
-        // Encrypt the 4 blocks using the AES key
-        aes_key.encrypt_blocks(&mut expanded_parent);
+    /// Unrolled version: Expand two parent nodes into four child nodes.
+    pub fn node_expand_2to4(&self, children: &mut [Block], parents: &[Block]) {
+        if self.expansion == GgmExpansion::FixedKey {
+            return self.fixed_key_expand(&mut children[..4], &parents[..2]);
+        }
+        self.block_expand_1to2(&mut children[0..2], &parents[0]);
+        self.block_expand_1to2(&mut children[2..4], &parents[1]);
+    }
 
-        // Combine the first 2 blocks into `left_child` (32 bytes)
-        let left_child_bytes = [expanded_parent[0].as_slice(), expanded_parent[1].as_slice()].concat();
+    /// Unrolled version: Expand four parent nodes into eight child nodes.
+    pub fn node_expand_4to8(&self, children: &mut [Block; 8], parents: &[Block; 4]) {
+        if self.expansion == GgmExpansion::FixedKey {
+            return self.fixed_key_expand(children, parents);
+        }
+        for (pair, parent) in children.chunks_mut(2).zip(parents.iter()) {
+            self.block_expand_1to2(pair, parent);
+        }
+    }
 
-        // Combine the next 2 blocks into `right_child` (32 bytes)
-        let right_child_bytes = [expanded_parent[2].as_slice(), expanded_parent[3].as_slice()].concat();
+    /// Expands up to `MAX_PARENTS` parents at once, the children of parent `i` go to `2i` and `2i + 1`.
+    fn fixed_key_expand(&self, children: &mut [Block], parents: &[Block]) {
+        assert!(parents.len() <= MAX_PARENTS && children.len() == 2 * parents.len());
+        let mut inputs = [AesBlock::default(); MAX_PARENTS];
+        for (input, parent) in inputs.iter_mut().zip(parents) {
+            input.copy_from_slice(parent);
+        }
+        let inputs = &inputs[..parents.len()];
+
+        // Child b is π_b(s) ⊕ s
+        for b in 0..2 {
+            let mut outputs = [AesBlock::default(); MAX_PARENTS];
+            let outputs = &mut outputs[..inputs.len()];
+            outputs.copy_from_slice(inputs);
+            self.fixed[b].encrypt_blocks(outputs);
+            for i in 0..parents.len() {
+                xor_block(&mut outputs[i], &inputs[i]);
+                children[2 * i + b].copy_from_slice(&outputs[i]);
+            }
+        }
+    }
 
-        // Convert the byte arrays back into field elements
-        children[0] = FE::from_bytes_le(&left_child_bytes).expect("Failed to convert left_child bytes to field element");
-        children[1] = FE::from_bytes_le(&right_child_bytes).expect("Failed to convert right_child bytes to field element");
+    /// Maps each leaf to a field element, block `h` of its bytes being `π_2(s ⊕ h) ⊕ s ⊕ h`.
+    pub fn leaves_to_field<F: VoleField>(&self, out: &mut [FieldElement<F>], leaves: &[Block]) {
+        assert_eq!(out.len(), leaves.len(), "One field element per leaf");
+        assert!((16..=32).contains(&F::RANDOM_BYTES), "{} maps fewer than 128 bits to an element", F::NAME);
+        // Enough blocks for what `from_random_bytes` reads
+        let blocks = (F::RANDOM_BYTES + 15) / 16;
+
+        for (out, leaves) in out.chunks_mut(MAX_LEAVES).zip(leaves.chunks(MAX_LEAVES)) {
+            let mut inputs = [AesBlock::default(); 2 * MAX_LEAVES];
+            for (i, leaf) in leaves.iter().enumerate() {
+                for h in 0..blocks {
+                    inputs[i * blocks + h].copy_from_slice(leaf);
+                    inputs[i * blocks + h][15] ^= h as u8;
+                }
+            }
+            let inputs = &inputs[..leaves.len() * blocks];
+            let mut outputs = [AesBlock::default(); 2 * MAX_LEAVES];
+            let outputs = &mut outputs[..inputs.len()];
+            outputs.copy_from_slice(inputs);
+            self.fixed[2].encrypt_blocks(outputs);
+
+            for (i, out) in out.iter_mut().enumerate() {
+                let mut bytes = [0u8; 32];
+                for h in 0..blocks {
+                    xor_block(&mut outputs[i * blocks + h], &inputs[i * blocks + h]);
+                    bytes[16 * h..16 * (h + 1)].copy_from_slice(&outputs[i * blocks + h]);
+                }
+                *out = F::from_random_bytes(&bytes);
+            }
+        }
     }
 
-    /// Unrolled version: Expand two parent field elements into four child field elements.
-    pub fn node_expand_2to4(&self, children: &mut [FE], parents: &[FE]) {
-        let mut temp_children = [FE::zero(); 2];
+    /// Expand a 128-bit parent block into two child blocks.
+    pub fn block_expand_1to2(&self, children: &mut [Block], parent: &Block) {
+        let aes_key = Aes128::new(GenericArray::from_slice(parent));
 
-        // Expand first parent into the first two children
-        self.node_expand_1to2(&mut temp_children, &parents[0]);
-        children[0] = temp_children[0].clone();
-        children[1] = temp_children[1].clone();
+        let mut expanded_parent: [_; 2] = core::array::from_fn(|i| GenericArray::clone_from_slice(&[i as u8; 16]));
+        aes_key.encrypt_blocks(&mut expanded_parent);
 
-        // Expand second parent into the next two children
-        self.node_expand_1to2(&mut temp_children, &parents[1]);
-        children[2] = temp_children[0].clone();
-        children[3] = temp_children[1].clone();
+        children[0].copy_from_slice(&expanded_parent[0]);
+        children[1].copy_from_slice(&expanded_parent[1]);
     }
+}
 
-    /// Unrolled version: Expand four parent field elements into eight child field elements.
-    pub fn node_expand_4to8(&self, children: &mut [FE; 8], parents: &[FE; 4]) {
-        let mut temp_children = [FE::zero(); 2];
-
-        // Expand first parent into the first two children
-        self.node_expand_1to2(&mut temp_children, &parents[0]);
-        children[0] = temp_children[0].clone();
-        children[1] = temp_children[1].clone();
-
-        // Expand second parent into the next two children
-        self.node_expand_1to2(&mut temp_children, &parents[1]);
-        children[2] = temp_children[0].clone();
-        children[3] = temp_children[1].clone();
-
-        // Expand third parent into the next two children
-        self.node_expand_1to2(&mut temp_children, &parents[2]);
-        children[4] = temp_children[0].clone();
-        children[5] = temp_children[1].clone();
-
-        // Expand fourth parent into the last two children
-        self.node_expand_1to2(&mut temp_children, &parents[3]);
-        children[6] = temp_children[0].clone();
-        children[7] = temp_children[1].clone();
+fn xor_block(a: &mut AesBlock, b: &AesBlock) {
+    for (x, y) in a.iter_mut().zip(b.iter()) {
+        *x ^= y;
     }
 }
@@ -1,16 +1,23 @@
 use crate::preot::OTPre;
 use crate::comm_channel::CommunicationChannel;
+use crate::async_comm_channel::{AsyncCommunicationChannel, SyncChannel, block_on};
 use crate::base_cot::BaseCot;
+use crate::iknp::IKNP;
+use crate::ot_extension::OtExtension;
 use crate::lpn::Lpn;
 use crate::mpfss_reg::MpfssReg;
+use crate::two_key_prp::GgmExpansion;
 use crate::base_svole::BaseSvole;
-use lambdaworks_math::field::fields::fft_friendly::stark_252_prime_field::Stark252PrimeField;
+use crate::error::{Result, VoleError, CheckFailure};
+use crate::counting_channel::CommStats;
+use crate::hash::Hash;
+use crate::parallel::default_threads;
+use crate::field::{VoleField, VoleSubfield, DefaultField};
 use lambdaworks_math::field::element::FieldElement;
-use lambdaworks_math::traits::ByteConversion;
-use std::time::Instant;
+use std::fmt;
 
-pub type F = Stark252PrimeField;
-pub type FE = FieldElement<F>;
+/// Version of the VOLE protocol and its messages, both parties must run the same one.
+pub const PROTOCOL_VERSION: u32 = 2;
 
 pub struct PrimalLPNParameterFp61 {
     n: usize,
@@ -60,16 +67,16 @@ impl PrimalLPNParameterFp61 {
         t_pre0: usize,
         k_pre0: usize,
         log_bin_sz_pre0: usize,
-    ) -> Self {
+    ) -> Result<Self> {
         // Ensure parameters are valid
         if n != t * (1 << log_bin_sz)
             || n_pre != t_pre * (1 << log_bin_sz_pre)
             || n_pre < k + t + 1
         {
-            panic!("LPN parameter not matched");
+            return Err(VoleError::Parameter("LPN parameter not matched".to_string()));
         }
 
-        Self {
+        Ok(Self {
             n,
             t,
             k,
@@ -82,13 +89,34 @@ impl PrimalLPNParameterFp61 {
             t_pre0,
             k_pre0,
             log_bin_sz_pre0,
-        }
+        })
+    }
+
+    /// 6400 VOLEs per extend, small enough for tests and examples, not for production.
+    pub fn small() -> Self {
+        Self::with_params(6400, 100, 1000, 6, 1280, 40, 500, 5, 1600, 100, 300, 4)
+            .expect("The small parameters are consistent")
     }
 
     // Compute buffer size
     pub fn buf_sz(&self) -> usize {
         self.n - self.t - self.k - 1
     }
+
+    /// SHA-256 of all twelve parameters, what the handshake compares
+    pub fn digest(&self) -> [u8; 32] {
+        let mut hash = Hash::new();
+        for p in [
+            self.n, self.t, self.k, self.log_bin_sz,
+            self.n_pre, self.t_pre, self.k_pre, self.log_bin_sz_pre,
+            self.n_pre0, self.t_pre0, self.k_pre0, self.log_bin_sz_pre0,
+        ].iter() {
+            hash.put(&(*p as u64).to_le_bytes());
+        }
+        let mut digest = [0u8; 32];
+        hash.digest(&mut digest);
+        digest
+    }
 }
 
 // Default instance
@@ -139,91 +167,280 @@ pub const PHUOC_LPN: PrimalLPNParameterFp61 = PrimalLPNParameterFp61 {
     log_bin_sz_pre0: 5,
 };
 
-pub struct VoleTriple {
+/// Traffic of one party split by protocol phase.
+///
+/// Only filled in when the channel keeps counters, i.e. a `CountingChannel`.
+/// LPN is local and sends nothing.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub struct CommBreakdown {
+    /// Handshake and base OTs of the IKNP and COPE setups
+    pub base_ot: CommStats,
+    /// COT extension feeding the MPFSS
+    pub iknp: CommStats,
+    /// Base sVOLE from COPE, with its check
+    pub base_svole: CommStats,
+    /// MPFSS, with its checks
+    pub mpfss: CommStats,
+}
+
+impl CommBreakdown {
+    pub fn total(&self) -> CommStats {
+        self.base_ot + self.iknp + self.base_svole + self.mpfss
+    }
+}
+
+impl fmt::Display for CommBreakdown {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        writeln!(f, "base OT:    {}", self.base_ot)?;
+        writeln!(f, "IKNP:       {}", self.iknp)?;
+        writeln!(f, "base sVOLE: {}", self.base_svole)?;
+        writeln!(f, "MPFSS:      {}", self.mpfss)?;
+        write!(f, "total:      {}", self.total())
+    }
+}
+
+#[derive(Clone, Copy)]
+enum Phase {
+    BaseOt,
+    Iknp,
+    BaseSvole,
+    Mpfss,
+}
+
+/// sVOLE y = k + Δ·z with z in `F` and Δ, k, y in the MAC field `K`.
+///
+/// `K = F` is the plain VOLE, an extension such as `Ext2<F>` gives subfield VOLE,
+/// so protocols over small primes still get MACs of `K`'s size. The base COTs come
+/// from the OT extension `E`, IKNP unless built `with_extension`. The MPFSS trees
+/// use the default `GgmExpansion` unless built `with_expansion`.
+pub struct VoleTriple<F: VoleSubfield<K> = DefaultField, K: VoleField = F, E: OtExtension<32> = IKNP> {
     party: usize,
     param: PrimalLPNParameterFp61,
     m: usize,
     ot_used: usize,
     ot_limit: usize,
     is_malicious: bool,
+    threads: usize,
+    expansion: GgmExpansion,
     extend_initialized: bool,
     pre_ot_inplace: bool,
+    failed_check: Option<CheckFailure>, // set once a check fails, no correlation is handed out after
 
-    pre_y: Vec<FE>,
-    pre_z: Vec<FE>,
-    pre_x: Vec<FE>,
-    vole_y: Vec<FE>,
-    vole_z: Vec<FE>,
-    vole_x: Vec<FE>,
+    pre_y: Vec<FieldElement<K>>,
+    pre_z: Vec<FieldElement<F>>,
+    pre_x: Vec<FieldElement<F>>,
+    vole_y: Vec<FieldElement<K>>,
+    vole_z: Vec<FieldElement<F>>,
+    vole_x: Vec<FieldElement<F>>,
 
-    cot: BaseCot,
+    cot: BaseCot<32, E>,
     pre_ot: Option<OTPre>,
 
-    delta: FE,
-    mpfss: Option<MpfssReg>,
+    delta: FieldElement<K>,
+    mpfss: Option<MpfssReg<F, K>>,
+
+    setup_comm: CommBreakdown,
+    extend_comm: CommBreakdown,
+}
+
+impl<F: VoleSubfield<K>, K: VoleField> VoleTriple<F, K> {
+    /// Checks with the peer that both run the same version, fields, parameters and security model,
+    /// then generates the base OTs.
+    pub fn new<IO: CommunicationChannel>(party: usize, malicious: bool, io: &mut IO, param: PrimalLPNParameterFp61) -> Result<Self> {
+        block_on(Self::new_async(party, malicious, &mut SyncChannel(io), param))
+    }
+
+    /// Async variant of `new`.
+    pub async fn new_async<IO: AsyncCommunicationChannel>(party: usize, malicious: bool, io: &mut IO, param: PrimalLPNParameterFp61) -> Result<Self> {
+        Self::with_extension_async(party, io, param, IKNP::new(malicious)).await
+    }
 }
 
-impl VoleTriple {
-    pub fn new<IO: CommunicationChannel>(party: usize, malicious: bool, io: &mut IO, param: PrimalLPNParameterFp61) -> Self {
+impl<F: VoleSubfield<K>, K: VoleField, E: OtExtension<32>> VoleTriple<F, K, E> {
+    /// Like `new`, with the base COTs from `extension`, which must not be set up yet.
+    ///
+    /// The security model is the extension's, and the peer must run the same extension.
+    pub fn with_extension<IO: CommunicationChannel>(party: usize, io: &mut IO, param: PrimalLPNParameterFp61, extension: E) -> Result<Self> {
+        block_on(Self::with_extension_async(party, &mut SyncChannel(io), param, extension))
+    }
+
+    /// Like `with_extension`, with the MPFSS trees expanded by `expansion`.
+    ///
+    /// The peer must use the same expansion.
+    pub fn with_expansion<IO: CommunicationChannel>(party: usize, io: &mut IO, param: PrimalLPNParameterFp61, extension: E, expansion: GgmExpansion) -> Result<Self> {
+        block_on(Self::with_expansion_async(party, &mut SyncChannel(io), param, extension, expansion))
+    }
+
+    // Both parties send what they are about to run and check what the peer runs,
+    // so a mismatch fails here instead of as a hang or garbage output later
+    async fn handshake_async<IO: AsyncCommunicationChannel>(party: usize, malicious: bool, expansion: GgmExpansion, extension: &str, io: &mut IO, param: &PrimalLPNParameterFp61) -> Result<()> {
+        let mut info = [0u8; 32];
+        info[..4].copy_from_slice(&PROTOCOL_VERSION.to_le_bytes());
+        info[4] = party as u8;
+        info[5] = malicious as u8;
+        info[6] = expansion as u8;
+        let len = extension.len().min(25);
+        info[7..7 + len].copy_from_slice(&extension.as_bytes()[..len]);
+        let fields = format!("{}/{}", F::NAME, K::NAME);
+        let mut field_id = [0u8; 32];
+        let len = fields.len().min(32);
+        field_id[..len].copy_from_slice(&fields.as_bytes()[..len]);
+
+        io.send_32byte_block(&[info, field_id, param.digest()]).await?;
+        io.flush().await?;
+        let peer = io.receive_32byte_block().await?;
+        if peer.len() != 3 {
+            return Err(VoleError::MalformedMessage(format!("Handshake of {} blocks", peer.len())));
+        }
+
+        let mut version = [0u8; 4];
+        version.copy_from_slice(&peer[0][..4]);
+        let version = u32::from_le_bytes(version);
+        if version != PROTOCOL_VERSION {
+            return Err(VoleError::Handshake(format!(
+                "protocol version {}, the peer runs version {}", PROTOCOL_VERSION, version
+            )));
+        }
+        if peer[0][4] as usize == party {
+            return Err(VoleError::Handshake(format!(
+                "both parties are the {}", if party == 0 { "sender" } else { "receiver" }
+            )));
+        }
+        if (peer[0][5] != 0) != malicious {
+            return Err(VoleError::Handshake(format!(
+                "{} run, the peer runs {}",
+                if malicious { "malicious" } else { "semi-honest" },
+                if malicious { "semi-honest" } else { "malicious" }
+            )));
+        }
+        if peer[0][6] != info[6] {
+            let peer_expansion = [GgmExpansion::ReKeyed, GgmExpansion::FixedKey]
+                .iter()
+                .find(|e| **e as u8 == peer[0][6])
+                .map_or_else(|| format!("unknown ({})", peer[0][6]), |e| format!("{:?}", e));
+            return Err(VoleError::Handshake(format!(
+                "GGM expansion {:?}, the peer uses {}", expansion, peer_expansion
+            )));
+        }
+        if peer[0][7..] != info[7..] {
+            let peer_extension = String::from_utf8_lossy(&peer[0][7..]).trim_end_matches('\0').to_string();
+            return Err(VoleError::Handshake(format!(
+                "OT extension {}, the peer runs {}", extension, peer_extension
+            )));
+        }
+        if peer[1] != field_id {
+            let peer_fields = String::from_utf8_lossy(&peer[1]).trim_end_matches('\0').to_string();
+            return Err(VoleError::Handshake(format!(
+                "fields {}, the peer uses {}", fields, peer_fields
+            )));
+        }
+        if peer[2] != param.digest() {
+            return Err(VoleError::Handshake("the peer uses other LPN parameters".to_string()));
+        }
+        Ok(())
+    }
+
+    /// Async variant of `with_extension`.
+    pub async fn with_extension_async<IO: AsyncCommunicationChannel>(party: usize, io: &mut IO, param: PrimalLPNParameterFp61, extension: E) -> Result<Self> {
+        Self::with_expansion_async(party, io, param, extension, GgmExpansion::default()).await
+    }
+
+    /// Async variant of `with_expansion`.
+    pub async fn with_expansion_async<IO: AsyncCommunicationChannel>(party: usize, io: &mut IO, param: PrimalLPNParameterFp61, extension: E, expansion: GgmExpansion) -> Result<Self> {
+        let malicious = extension.malicious();
         let n_pre = param.n_pre;
         let t_pre = param.t_pre;
         let n = param.n;
         let t = param.t;
-        let mut cot = BaseCot::new(party, malicious);
-        cot.cot_gen_pre(io, None);
+        let before = io.stats();
+        Self::handshake_async(party, malicious, expansion, &extension.name(), io, &param).await?;
+        let mut cot = BaseCot::with_extension(party, extension);
+        cot.cot_gen_pre_async(io, None).await?;
+        let mut setup_comm = CommBreakdown::default();
+        if let Some(base_ot) = before.zip(io.stats()).and_then(|(before, after)| after.since(&before)) {
+            setup_comm.base_ot = base_ot;
+        }
 
-        VoleTriple {
+        Ok(VoleTriple {
             party: party,
             param: param,
             m: 0,
             ot_used: 0,
             ot_limit: 0,
             is_malicious: malicious,
+            threads: default_threads(),
+            expansion: expansion,
             extend_initialized: false,
             pre_ot_inplace: false,
+            failed_check: None,
 
-            pre_y: vec![FE::zero(); n_pre],
-            pre_z: vec![FE::zero(); n_pre],
-            pre_x: vec![FE::zero(); t_pre + 1],
-            vole_y: vec![FE::zero(); n],
-            vole_z: vec![FE::zero(); n],
-            vole_x: vec![FE::zero(); t + 1],
+            pre_y: vec![FieldElement::zero(); n_pre],
+            pre_z: vec![FieldElement::zero(); n_pre],
+            pre_x: vec![FieldElement::zero(); t_pre + 1],
+            vole_y: vec![FieldElement::zero(); n],
+            vole_z: vec![FieldElement::zero(); n],
+            vole_x: vec![FieldElement::zero(); t + 1],
 
             cot: cot,
             pre_ot: None,
 
-            delta: FE::zero(),
+            delta: FieldElement::zero(),
             mpfss: None,
-        }
+
+            setup_comm: setup_comm,
+            extend_comm: CommBreakdown::default(),
+        })
+    }
+
+    pub fn extend_send<IO: CommunicationChannel>(&mut self, io: &mut IO, y: &mut [FieldElement<K>], mpfss: &mut MpfssReg<F, K>, pre_ot: &mut OTPre, lpn: &mut Lpn<F, K>, key: &[FieldElement<K>], t: usize) -> Result<()> {
+        block_on(self.extend_send_async(&mut SyncChannel(io), y, mpfss, pre_ot, lpn, key, t))
     }
 
-    pub fn extend_send<IO: CommunicationChannel>(&mut self, io: &mut IO, y: &mut [FE], mpfss: &mut MpfssReg, pre_ot: &mut OTPre, lpn: &mut Lpn, key: &[FE], t: usize) {
+    /// Async variant of `extend_send`.
+    pub async fn extend_send_async<IO: AsyncCommunicationChannel>(&mut self, io: &mut IO, y: &mut [FieldElement<K>], mpfss: &mut MpfssReg<F, K>, pre_ot: &mut OTPre, lpn: &mut Lpn<F, K>, key: &[FieldElement<K>], t: usize) -> Result<()> {
+        self.ensure_not_aborted()?;
         mpfss.sender_init(self.delta);
-        mpfss.mpfss_sender(io, pre_ot, key, y);
+        let before = io.stats();
+        let res = mpfss.mpfss_sender_async(io, pre_ot, key, y).await;
+        self.record_comm(Phase::Mpfss, before, io.stats());
+        self.abort_on_failure(res)?;
         pre_ot.reset();
 
         // println!("Test mpfss: {:?}", y[0] + key[t+1] * self.delta);
 
         // // y is already a regular vector (concat of n/t unit vectors), which corresponses to the noise in LPN
 
-        let start = Instant::now();
-        lpn.compute_send(y, &key[t+1..]);
-        println!("Time taken for LPN: {:?}", start.elapsed());
+        lpn.compute_send(y, &key[t + F::DEGREE..]);
+        Ok(())
     }
 
-    pub fn extend_recv<IO: CommunicationChannel>(&mut self, io: &mut IO, y: &mut [FE], z: &mut [FE], mpfss: &mut MpfssReg, pre_ot: &mut OTPre, lpn: &mut Lpn, mac: &[FE], u: &[FE], t: usize) {
+    pub fn extend_recv<IO: CommunicationChannel>(&mut self, io: &mut IO, y: &mut [FieldElement<K>], z: &mut [FieldElement<F>], mpfss: &mut MpfssReg<F, K>, pre_ot: &mut OTPre, lpn: &mut Lpn<F, K>, mac: &[FieldElement<K>], u: &[FieldElement<F>], t: usize) -> Result<()> {
+        block_on(self.extend_recv_async(&mut SyncChannel(io), y, z, mpfss, pre_ot, lpn, mac, u, t))
+    }
+
+    /// Async variant of `extend_recv`.
+    pub async fn extend_recv_async<IO: AsyncCommunicationChannel>(&mut self, io: &mut IO, y: &mut [FieldElement<K>], z: &mut [FieldElement<F>], mpfss: &mut MpfssReg<F, K>, pre_ot: &mut OTPre, lpn: &mut Lpn<F, K>, mac: &[FieldElement<K>], u: &[FieldElement<F>], t: usize) -> Result<()> {
+        self.ensure_not_aborted()?;
         mpfss.receiver_init();
-        mpfss.mpfss_receiver(io, pre_ot, mac, u, y, z);
+        let before = io.stats();
+        let res = mpfss.mpfss_receiver_async(io, pre_ot, mac, u, y, z).await;
+        self.record_comm(Phase::Mpfss, before, io.stats());
+        self.abort_on_failure(res)?;
         pre_ot.reset();
 
         // println!("Test mpfss: {:?}", (y[0] + mac[t+1] * self.delta) - (z[0] + u[t+1] * self.delta) * self.delta);
 
-        let start = Instant::now();
-        lpn.compute_recv(y, z, &mac[t+1..], &u[t+1..]);
-        println!("Time taken for LPN: {:?}", start.elapsed());
+        lpn.compute_recv(y, z, &mac[t + F::DEGREE..], &u[t + F::DEGREE..]);
+        Ok(())
     }
 
-    pub fn setup_sender<IO: CommunicationChannel>(&mut self, io: &mut IO, delta: FE) {
+    pub fn setup_sender<IO: CommunicationChannel>(&mut self, io: &mut IO, delta: FieldElement<K>) -> Result<()> {
+        block_on(self.setup_sender_async(&mut SyncChannel(io), delta))
+    }
+
+    /// Async variant of `setup_sender`.
+    pub async fn setup_sender_async<IO: AsyncCommunicationChannel>(&mut self, io: &mut IO, delta: FieldElement<K>) -> Result<()> {
+        self.ensure_not_aborted()?;
         self.delta = delta;
         // io.send_stark252(&[self.delta]).expect("Cannot send test delta"); //debug only
 
@@ -232,25 +449,36 @@ impl VoleTriple {
         let mut seed_field_pre0 = [0u8; 32];
         seed_field_pre0[0] = 1;
         let mut lpn_pre0 = Lpn::new(self.param.k_pre0, self.param.n_pre0, &seed_pre0, &seed_field_pre0);
+        lpn_pre0.set_threads(self.threads);
         let mut mpfss_pre0 = MpfssReg::new(self.param.n_pre0, self.param.t_pre0, self.param.log_bin_sz_pre0, self.party);
+        mpfss_pre0.set_threads(self.threads);
+        mpfss_pre0.set_ggm_expansion(self.expansion);
         mpfss_pre0.set_malicious();
         let mut pre_ot_ini0 = OTPre::new(self.param.log_bin_sz_pre0, self.param.t_pre0);
 
         let m_pre0 = self.param.log_bin_sz_pre0 * self.param.t_pre0;
-        self.cot.cot_gen_preot(io, &mut pre_ot_ini0, m_pre0, None);
+        let before = io.stats();
+        let res = self.cot.cot_gen_preot_async(io, &mut pre_ot_ini0, m_pre0, None).await;
+        self.record_comm(Phase::Iknp, before, io.stats());
+        self.abort_on_failure(res)?;
 
         // mac = key + delta * u
-        let triple_n0 = 1 + self.param.t_pre0 + self.param.k_pre0;
-        let mut key = vec![FE::zero(); triple_n0];
-        let mut svole0 = BaseSvole::new_sender(io, self.delta);
-        svole0.triple_gen_send(io, &mut key, triple_n0);
+        let triple_n0 = F::DEGREE + self.param.t_pre0 + self.param.k_pre0;
+        let mut key = vec![FieldElement::zero(); triple_n0];
+        let before = io.stats();
+        let mut svole0 = BaseSvole::<F, K>::new_sender_async(io, self.delta).await?;
+        let mid = io.stats();
+        self.record_comm(Phase::BaseOt, before, mid);
+        let res = svole0.triple_gen_send_async(io, &mut key, triple_n0).await;
+        self.record_comm(Phase::BaseSvole, mid, io.stats());
+        self.abort_on_failure(res)?;
 
         // println!("Test base svole: {:?}", key[0]);
 
-        io.flush();
+        io.flush().await?;
 
-        let mut pre_y0 = vec![FE::zero(); self.param.n_pre0];
-        self.extend_send(io, &mut pre_y0, &mut mpfss_pre0, &mut pre_ot_ini0, &mut lpn_pre0, &key, self.param.t_pre0);
+        let mut pre_y0 = vec![FieldElement::zero(); self.param.n_pre0];
+        self.extend_send_async(io, &mut pre_y0, &mut mpfss_pre0, &mut pre_ot_ini0, &mut lpn_pre0, &key, self.param.t_pre0).await?;
 
         // println!("Test LPN: {:?}", pre_y0[0]);
 
@@ -259,51 +487,75 @@ impl VoleTriple {
         let mut seed_field_pre = [0u8; 32];
         seed_field_pre[0] = 1;
         let mut lpn_pre = Lpn::new(self.param.k_pre, self.param.n_pre, &seed_pre, &seed_field_pre);
+        lpn_pre.set_threads(self.threads);
         let mut mpfss_pre = MpfssReg::new(self.param.n_pre, self.param.t_pre, self.param.log_bin_sz_pre, self.party); 
+        mpfss_pre.set_threads(self.threads);
+        mpfss_pre.set_ggm_expansion(self.expansion);
         mpfss_pre.set_malicious();
         let mut pre_ot_ini = OTPre::new(self.param.log_bin_sz_pre, self.param.t_pre);
 
         let m_pre = self.param.log_bin_sz_pre * self.param.t_pre;
-        self.cot.cot_gen_preot(io, &mut pre_ot_ini, m_pre, None);
+        let before = io.stats();
+        let res = self.cot.cot_gen_preot_async(io, &mut pre_ot_ini, m_pre, None).await;
+        self.record_comm(Phase::Iknp, before, io.stats());
+        self.abort_on_failure(res)?;
 
         // 
-        let triple_n = 1 + self.param.t_pre + self.param.k_pre;        
-        let mut pre_y = vec![FE::zero(); self.param.n_pre];
-        self.extend_send(io, &mut pre_y, &mut mpfss_pre, &mut pre_ot_ini, &mut lpn_pre, &pre_y0[..triple_n], self.param.t_pre);
+        let triple_n = F::DEGREE + self.param.t_pre + self.param.k_pre;        
+        let mut pre_y = vec![FieldElement::zero(); self.param.n_pre];
+        self.extend_send_async(io, &mut pre_y, &mut mpfss_pre, &mut pre_ot_ini, &mut lpn_pre, &pre_y0[..triple_n], self.param.t_pre).await?;
         self.pre_y.copy_from_slice(&pre_y);
 
         self.pre_ot_inplace = true;
+        Ok(())
+    }
+
+    pub fn setup_receiver<IO: CommunicationChannel>(&mut self, io: &mut IO) -> Result<()> {
+        block_on(self.setup_receiver_async(&mut SyncChannel(io)))
     }
 
-    pub fn setup_receiver<IO: CommunicationChannel>(&mut self, io: &mut IO) {
-        // self.delta = io.receive_stark252(1).expect("Failed to receive test delta")[0]; //debug only
+    /// Async variant of `setup_receiver`.
+    pub async fn setup_receiver_async<IO: AsyncCommunicationChannel>(&mut self, io: &mut IO) -> Result<()> {
+        self.ensure_not_aborted()?;
+        // self.delta = io.receive_field_elements::<F>(1).expect("Failed to receive test delta")[0]; //debug only
 
         let seed_pre0 = [0u8; 16];
         // let seed_field_pre0 = [[0u8; 16]; 4];
         let mut seed_field_pre0 = [0u8; 32];
         seed_field_pre0[0] = 1;
         let mut lpn_pre0 = Lpn::new(self.param.k_pre0, self.param.n_pre0, &seed_pre0, &seed_field_pre0);
+        lpn_pre0.set_threads(self.threads);
         let mut mpfss_pre0 = MpfssReg::new(self.param.n_pre0, self.param.t_pre0, self.param.log_bin_sz_pre0, self.party);
+        mpfss_pre0.set_threads(self.threads);
+        mpfss_pre0.set_ggm_expansion(self.expansion);
         mpfss_pre0.set_malicious();
         let mut pre_ot_ini0 = OTPre::new(self.param.log_bin_sz_pre0, self.param.t_pre0);
 
         let m_pre0 = self.param.log_bin_sz_pre0 * self.param.t_pre0;
-        self.cot.cot_gen_preot(io, &mut pre_ot_ini0, m_pre0, None);
+        let before = io.stats();
+        let res = self.cot.cot_gen_preot_async(io, &mut pre_ot_ini0, m_pre0, None).await;
+        self.record_comm(Phase::Iknp, before, io.stats());
+        self.abort_on_failure(res)?;
 
         // mac = key + delta * u
-        let triple_n0 = 1 + self.param.t_pre0 + self.param.k_pre0;
-        let mut mac = vec![FE::zero(); triple_n0];
-        let mut u = vec![FE::zero(); triple_n0];
-        let mut svole0 = BaseSvole::new_receiver(io);
-        svole0.triple_gen_recv(io, &mut mac, &mut u, triple_n0);
+        let triple_n0 = F::DEGREE + self.param.t_pre0 + self.param.k_pre0;
+        let mut mac = vec![FieldElement::zero(); triple_n0];
+        let mut u = vec![FieldElement::zero(); triple_n0];
+        let before = io.stats();
+        let mut svole0 = BaseSvole::<F, K>::new_receiver_async(io).await?;
+        let mid = io.stats();
+        self.record_comm(Phase::BaseOt, before, mid);
+        let res = svole0.triple_gen_recv_async(io, &mut mac, &mut u, triple_n0).await;
+        self.record_comm(Phase::BaseSvole, mid, io.stats());
+        self.abort_on_failure(res)?;
 
         // println!("Test base svole: {:?}", mac[0] - u[0] * self.delta);
 
-        io.flush();
+        io.flush().await?;
 
-        let mut pre_y0 = vec![FE::zero(); self.param.n_pre0];
-        let mut pre_z0 = vec![FE::zero(); self.param.n_pre0];
-        self.extend_recv(io, &mut pre_y0, &mut pre_z0, &mut mpfss_pre0, &mut pre_ot_ini0, &mut lpn_pre0, &mac, &u, self.param.t_pre0);
+        let mut pre_y0 = vec![FieldElement::zero(); self.param.n_pre0];
+        let mut pre_z0 = vec![FieldElement::zero(); self.param.n_pre0];
+        self.extend_recv_async(io, &mut pre_y0, &mut pre_z0, &mut mpfss_pre0, &mut pre_ot_ini0, &mut lpn_pre0, &mac, &u, self.param.t_pre0).await?;
 
         // println!("Test lpn: {:?}", pre_y0[0] - pre_z0[0] * self.delta);
 
@@ -312,69 +564,125 @@ impl VoleTriple {
         let mut seed_field_pre = [0u8; 32];
         seed_field_pre[0] = 1;
         let mut lpn_pre = Lpn::new(self.param.k_pre, self.param.n_pre, &seed_pre, &seed_field_pre);
+        lpn_pre.set_threads(self.threads);
         let mut mpfss_pre = MpfssReg::new(self.param.n_pre, self.param.t_pre, self.param.log_bin_sz_pre, self.party); 
+        mpfss_pre.set_threads(self.threads);
+        mpfss_pre.set_ggm_expansion(self.expansion);
         mpfss_pre.set_malicious();
         let mut pre_ot_ini = OTPre::new(self.param.log_bin_sz_pre, self.param.t_pre);
 
         let m_pre = self.param.log_bin_sz_pre * self.param.t_pre;
-        self.cot.cot_gen_preot(io, &mut pre_ot_ini, m_pre, None);
+        let before = io.stats();
+        let res = self.cot.cot_gen_preot_async(io, &mut pre_ot_ini, m_pre, None).await;
+        self.record_comm(Phase::Iknp, before, io.stats());
+        self.abort_on_failure(res)?;
 
         // 
-        let triple_n = 1 + self.param.t_pre + self.param.k_pre;        
-        let mut pre_y = vec![FE::zero(); self.param.n_pre];
-        let mut pre_z = vec![FE::zero(); self.param.n_pre];
-        self.extend_recv(io, &mut pre_y, &mut pre_z, &mut mpfss_pre, &mut pre_ot_ini, &mut lpn_pre, &pre_y0[..triple_n], &pre_z0[..triple_n], self.param.t_pre);
+        let triple_n = F::DEGREE + self.param.t_pre + self.param.k_pre;        
+        let mut pre_y = vec![FieldElement::zero(); self.param.n_pre];
+        let mut pre_z = vec![FieldElement::zero(); self.param.n_pre];
+        self.extend_recv_async(io, &mut pre_y, &mut pre_z, &mut mpfss_pre, &mut pre_ot_ini, &mut lpn_pre, &pre_y0[..triple_n], &pre_z0[..triple_n], self.param.t_pre).await?;
         self.pre_y.copy_from_slice(&pre_y);
         self.pre_z.copy_from_slice(&pre_z);
 
         self.pre_ot_inplace = true;
+        Ok(())
+    }
+
+    /// Number of threads expanding the MPFSS trees and encoding LPN, one per core by default.
+    /// The output and the messages are the same for any number.
+    pub fn set_threads(&mut self, threads: usize) {
+        self.threads = threads.max(1);
+    }
+
+    /// Traffic of `new` and `setup_*` by phase.
+    pub fn setup_comm(&self) -> &CommBreakdown {
+        &self.setup_comm
+    }
+
+    /// Traffic of the `extend` calls since the last `reset_extend_comm`, by phase.
+    pub fn extend_comm(&self) -> &CommBreakdown {
+        &self.extend_comm
+    }
+
+    pub fn reset_extend_comm(&mut self) {
+        self.extend_comm = CommBreakdown::default();
+    }
+
+    // Traffic goes to the setup until it is done, to the extensions after
+    fn record_comm(&mut self, phase: Phase, before: Option<CommStats>, after: Option<CommStats>) {
+        if let Some(traffic) = before.zip(after).and_then(|(before, after)| after.since(&before)) {
+            let breakdown = if self.pre_ot_inplace { &mut self.extend_comm } else { &mut self.setup_comm };
+            let stats = match phase {
+                Phase::BaseOt => &mut breakdown.base_ot,
+                Phase::Iknp => &mut breakdown.iknp,
+                Phase::BaseSvole => &mut breakdown.base_svole,
+                Phase::Mpfss => &mut breakdown.mpfss,
+            };
+            *stats += traffic;
+        }
     }
 
     pub fn extend_initialization(&mut self) {
-        self.m = self.param.k + self.param.t + 1;
+        self.m = self.param.k + self.param.t + F::DEGREE;
         self.ot_limit = self.param.n - self.m;
         self.ot_used = self.ot_limit;
         self.extend_initialized = true;
     }
 
-    pub fn extend_once<IO: CommunicationChannel>(&mut self, io: &mut IO, data_y: &mut [FE], data_z: &mut [FE], mpfss: &mut MpfssReg, pre_ot: &mut OTPre, lpn: &mut Lpn) {
-        self.cot.cot_gen_preot(io, pre_ot, self.param.t * self.param.log_bin_sz, None);
-        let mut pre_y = vec![FE::zero(); self.m];
+    pub fn extend_once<IO: CommunicationChannel>(&mut self, io: &mut IO, data_y: &mut [FieldElement<K>], data_z: &mut [FieldElement<F>], mpfss: &mut MpfssReg<F, K>, pre_ot: &mut OTPre, lpn: &mut Lpn<F, K>) -> Result<()> {
+        block_on(self.extend_once_async(&mut SyncChannel(io), data_y, data_z, mpfss, pre_ot, lpn))
+    }
+
+    /// Async variant of `extend_once`.
+    pub async fn extend_once_async<IO: AsyncCommunicationChannel>(&mut self, io: &mut IO, data_y: &mut [FieldElement<K>], data_z: &mut [FieldElement<F>], mpfss: &mut MpfssReg<F, K>, pre_ot: &mut OTPre, lpn: &mut Lpn<F, K>) -> Result<()> {
+        self.ensure_not_aborted()?;
+        let before = io.stats();
+        let res = self.cot.cot_gen_preot_async(io, pre_ot, self.param.t * self.param.log_bin_sz, None).await;
+        self.record_comm(Phase::Iknp, before, io.stats());
+        self.abort_on_failure(res)?;
+        let mut pre_y = vec![FieldElement::zero(); self.m];
         pre_y.copy_from_slice(&self.pre_y[..self.m]);
-        let mut pre_z = vec![FE::zero(); self.m];
+        let mut pre_z = vec![FieldElement::zero(); self.m];
         pre_z.copy_from_slice(&self.pre_z[..self.m]);
         if self.party == 0{
-            self.extend_send(io, data_y, mpfss, pre_ot, lpn, &pre_y, self.param.t);
+            self.extend_send_async(io, data_y, mpfss, pre_ot, lpn, &pre_y, self.param.t).await?;
         } else {
-            self.extend_recv(io, data_y, data_z, mpfss, pre_ot, lpn, &pre_y, &pre_z, self.param.t);
+            self.extend_recv_async(io, data_y, data_z, mpfss, pre_ot, lpn, &pre_y, &pre_z, self.param.t).await?;
         }
         self.pre_y[..self.m].copy_from_slice(&data_y[self.ot_limit..]);
         self.pre_z[..self.m].copy_from_slice(&data_z[self.ot_limit..]);
+        Ok(())
+    }
+
+    /// Writes `num` fresh correlations to the start of `data_y` and `data_z`
+    pub fn extend<IO: CommunicationChannel>(&mut self, io: &mut IO, data_y: &mut [FieldElement<K>], data_z: &mut [FieldElement<F>], num: usize) -> Result<()> {
+        block_on(self.extend_async(&mut SyncChannel(io), data_y, data_z, num))
     }
 
-    pub fn extend<IO: CommunicationChannel>(&mut self, io: &mut IO, data_y: &mut [FE], data_z: &mut [FE], num: usize) {
+    /// Async variant of `extend`.
+    pub async fn extend_async<IO: AsyncCommunicationChannel>(&mut self, io: &mut IO, data_y: &mut [FieldElement<K>], data_z: &mut [FieldElement<F>], num: usize) -> Result<()> {
+        self.ensure_not_aborted()?;
         if self.extend_initialized == false {
-            panic!("Run extend_initialization first!");
+            return Err(VoleError::Parameter("Run extend_initialization first!".to_string()));
         }
 
         if num <= self.silent_ot_left() {
-            data_y.copy_from_slice(&self.vole_y[self.ot_used..self.ot_used+num]);
-            data_z.copy_from_slice(&self.vole_z[self.ot_used..self.ot_used+num]);
-            return;
+            data_y[..num].copy_from_slice(&self.vole_y[self.ot_used..self.ot_used+num]);
+            data_z[..num].copy_from_slice(&self.vole_z[self.ot_used..self.ot_used+num]);
+            self.ot_used += num;
+            return Ok(());
         }
 
         let gened = self.silent_ot_left();
         let mut copied = 0;
         if gened > 0 {
-            data_y.copy_from_slice(&self.vole_y[self.ot_used..self.ot_used+gened]);
-            data_z.copy_from_slice(&self.vole_z[self.ot_used..self.ot_used+gened]);
+            data_y[..gened].copy_from_slice(&self.vole_y[self.ot_used..self.ot_used+gened]);
+            data_z[..gened].copy_from_slice(&self.vole_z[self.ot_used..self.ot_used+gened]);
             copied += gened;
         }
 
-        println!("gened: {}", gened);
-
-        self.m = self.param.k + self.param.t + 1;
-        println!("m: {}", self.m);
+        self.m = self.param.k + self.param.t + F::DEGREE;
         let mut round_inplace = 0;
         if num > gened + self.m {
             round_inplace = (num - gened - self.m) / self.ot_limit;
@@ -385,57 +693,66 @@ impl VoleTriple {
             last_round_ot -= self.ot_limit;
         }
 
-        println!("round inplace: {}", round_inplace);
-
         let mut pre_ot = OTPre::new(self.param.log_bin_sz, self.param.t);
         let seed = [0u8; 16];
         let mut seed_field = [0u8; 32];
         seed_field[0] = 1;
         let mut lpn = Lpn::new(self.param.k, self.param.n, &seed, &seed_field);
+        lpn.set_threads(self.threads);
         let mut mpfss = MpfssReg::new(self.param.n, self.param.t, self.param.log_bin_sz, self.party); 
+        mpfss.set_threads(self.threads);
+        mpfss.set_ggm_expansion(self.expansion);
         mpfss.set_malicious();
 
+        // The last m outputs of a round are the next round's pre-VOLEs, so each round yields ot_limit
         for i in 0..round_inplace {
-            self.extend_once(io, &mut data_y[copied..copied+self.param.n], &mut data_z[copied..copied+self.param.n], &mut mpfss, &mut pre_ot, &mut lpn);
+            self.extend_once_async(io, &mut data_y[copied..copied+self.param.n], &mut data_z[copied..copied+self.param.n], &mut mpfss, &mut pre_ot, &mut lpn).await?;
             self.ot_used = self.ot_limit;
-            copied += self.param.n;
+            copied += self.ot_limit;
         }
 
         if round_memcpy {
-            let mut tmp_y = vec![FE::zero(); self.param.n];
-            let mut tmp_z = vec![FE::zero(); self.param.n];
-            self.extend_once(io, &mut tmp_y, &mut tmp_z, &mut mpfss, &mut pre_ot, &mut lpn);
+            let mut tmp_y = vec![FieldElement::zero(); self.param.n];
+            let mut tmp_z = vec![FieldElement::zero(); self.param.n];
+            self.extend_once_async(io, &mut tmp_y, &mut tmp_z, &mut mpfss, &mut pre_ot, &mut lpn).await?;
             self.vole_y.copy_from_slice(&tmp_y);
             self.vole_z.copy_from_slice(&tmp_z);
-            data_y[copied..copied+self.param.n].copy_from_slice(&tmp_y);
-            data_z[copied..copied+self.param.n].copy_from_slice(&tmp_z);
+            data_y[copied..copied+self.ot_limit].copy_from_slice(&tmp_y[..self.ot_limit]);
+            data_z[copied..copied+self.ot_limit].copy_from_slice(&tmp_z[..self.ot_limit]);
             self.ot_used = self.ot_limit;
-            copied += self.param.n;
+            copied += self.ot_limit;
         }
 
         if last_round_ot > 0 {
-            let mut tmp_y = vec![FE::zero(); self.param.n];
-            let mut tmp_z = vec![FE::zero(); self.param.n];
-            self.extend_once(io, &mut tmp_y, &mut tmp_z, &mut mpfss, &mut pre_ot, &mut lpn);
+            let mut tmp_y = vec![FieldElement::zero(); self.param.n];
+            let mut tmp_z = vec![FieldElement::zero(); self.param.n];
+            self.extend_once_async(io, &mut tmp_y, &mut tmp_z, &mut mpfss, &mut pre_ot, &mut lpn).await?;
             self.vole_y.copy_from_slice(&tmp_y);
             self.vole_z.copy_from_slice(&tmp_z);
-            data_y[copied..].copy_from_slice(&tmp_y[..last_round_ot]);
-            data_z[copied..].copy_from_slice(&tmp_z[..last_round_ot]);
+            data_y[copied..num].copy_from_slice(&tmp_y[..last_round_ot]);
+            data_z[copied..num].copy_from_slice(&tmp_z[..last_round_ot]);
             self.ot_used = last_round_ot;
         }
-    }        
+        Ok(())
+    }
+
+    pub fn extend_inplace<IO: CommunicationChannel>(&mut self, io: &mut IO, data_y: &mut [FieldElement<K>], data_z: &mut [FieldElement<F>], byte_space: usize) -> Result<()> {
+        block_on(self.extend_inplace_async(&mut SyncChannel(io), data_y, data_z, byte_space))
+    }
 
-    pub fn extend_inplace<IO: CommunicationChannel>(&mut self, io: &mut IO, data_y: &mut [FE], data_z: &mut [FE], byte_space: usize) {
+    /// Async variant of `extend_inplace`.
+    pub async fn extend_inplace_async<IO: AsyncCommunicationChannel>(&mut self, io: &mut IO, data_y: &mut [FieldElement<K>], data_z: &mut [FieldElement<F>], byte_space: usize) -> Result<()> {
+        self.ensure_not_aborted()?;
         if byte_space < self.param.n {
-            panic!("Not enough space");
+            return Err(VoleError::Parameter("Not enough space".to_string()));
         }
         if self.extend_initialized == false {
-            panic!("Run extend_initialization first!");
+            return Err(VoleError::Parameter("Run extend_initialization first!".to_string()));
         }
 
         let tp_output_n = byte_space - self.m;
         if tp_output_n % self.ot_limit != 0 {
-            panic!("call byte_memory_need_inplace to know the byte_space needed");
+            return Err(VoleError::Parameter("call byte_memory_need_inplace to know the byte_space needed".to_string()));
         }
 
         let round = tp_output_n / self.ot_limit;
@@ -446,14 +763,38 @@ impl VoleTriple {
         let mut seed_field = [0u8; 32];
         seed_field[0] = 1;
         let mut lpn = Lpn::new(self.param.k, self.param.n, &seed, &seed_field);
+        lpn.set_threads(self.threads);
         let mut mpfss = MpfssReg::new(self.param.n, self.param.t, self.param.log_bin_sz, self.party); 
+        mpfss.set_threads(self.threads);
+        mpfss.set_ggm_expansion(self.expansion);
         mpfss.set_malicious();
 
         for i in 0..round {
-            self.extend_once(io, &mut data_y[copied..copied+self.param.n], &mut data_z[copied..copied+self.param.n], &mut mpfss, &mut pre_ot, &mut lpn);
+            self.extend_once_async(io, &mut data_y[copied..copied+self.param.n], &mut data_z[copied..copied+self.param.n], &mut mpfss, &mut pre_ot, &mut lpn).await?;
             self.ot_used = self.ot_limit;
             copied += self.param.n;
         }
+        Ok(())
+    }
+
+    /// Remember a failed check, so that every later call is refused.
+    fn abort_on_failure<T>(&mut self, res: Result<T>) -> Result<T> {
+        if let Err(VoleError::ConsistencyCheck(check)) = &res {
+            self.failed_check = Some(*check);
+        }
+        res
+    }
+
+    fn ensure_not_aborted(&self) -> Result<()> {
+        match self.failed_check {
+            Some(check) => Err(VoleError::Aborted(check)),
+            None => Ok(()),
+        }
+    }
+
+    /// The check that made this instance abort, if any.
+    pub fn failed_check(&self) -> Option<CheckFailure> {
+        self.failed_check
     }
 
     pub fn byte_memory_need_inplace(&self, tp_need: usize) -> usize {
@@ -466,19 +807,26 @@ impl VoleTriple {
     }
 
     // debug only
-    pub fn check_triple<IO: CommunicationChannel>(&self, io: &mut IO, x: FE, y: &[FE], z: &[FE], size: usize) {
+    pub fn check_triple<IO: CommunicationChannel>(&self, io: &mut IO, x: FieldElement<K>, y: &[FieldElement<K>], z: &[FieldElement<F>], size: usize) -> Result<()> {
+        block_on(self.check_triple_async(&mut SyncChannel(io), x, y, z, size))
+    }
+
+    /// Async variant of `check_triple`.
+    pub async fn check_triple_async<IO: AsyncCommunicationChannel>(&self, io: &mut IO, x: FieldElement<K>, y: &[FieldElement<K>], z: &[FieldElement<F>], size: usize) -> Result<()> {
         if self.party == 0 {
-            io.send_stark252(&[x]).expect("Failed to send delta test.");
-            io.send_stark252(&y).expect("Failed to send k test.");
+            io.send_field_elements(&[x]).await?;
+            io.send_field_elements(&y[..size]).await?;
+            io.flush().await?;
         } else {
             // want y = k + delta * z
-            let delta = io.receive_stark252(1).expect("Failed to receive delta test.")[0];
-            let k = io.receive_stark252(size).expect("Failed to receive k test");
+            let delta = io.receive_field_elements::<K>(1).await?[0];
+            let k = io.receive_field_elements::<K>(size).await?;
             for i in 0..size {
-                if y[i] != k[i] + delta * z[i] {
-                    panic!("tripple error at index {}", i);
+                if y[i] != k[i] + F::scale(&z[i], &delta) {
+                    return Err(VoleError::ConsistencyCheck(CheckFailure::Triple(i)));
                 }
             }
         }
+        Ok(())
     }
 }
\ No newline at end of file